@@ -0,0 +1,306 @@
+use std::collections::VecDeque;
+
+use cgmath::prelude::*;
+
+use super::HasPosition;
+use crate::Vec3F;
+
+// Deep enough for any sane spread of bodies; stops coincident points from splitting forever.
+const MAX_DEPTH: usize = 32;
+
+pub trait HasMass {
+  fn mass(&self) -> f32;
+}
+
+// Octree over point masses for Barnes–Hut gravity. Every node keeps the total mass and centre of
+// mass of the bodies beneath it, so a distant cluster can stand in for all of its members.
+// Nodes are laid out breadth first like `KdTree`, with a node's children stored next to each other.
+pub struct BarnesHutTree<T: HasPosition + HasMass> {
+  data: Vec<T>,
+  indices: Vec<usize>,
+  nodes: Vec<OctreeNode>,
+  leaf_size: usize,
+}
+
+impl<T: HasPosition + HasMass> Default for BarnesHutTree<T> {
+  fn default() -> Self {
+    Self {
+      data: Vec::new(),
+      indices: Vec::new(),
+      nodes: Vec::new(),
+      leaf_size: 1,
+    }
+  }
+}
+
+impl<T: HasPosition + HasMass> BarnesHutTree<T> {
+  pub fn new(data: Vec<T>, leaf_size: usize) -> Self {
+    let mut ret = Self {
+      leaf_size: leaf_size.max(1),
+      ..Self::default()
+    };
+    ret.set_data(data);
+    ret
+  }
+
+  pub fn set_data(&mut self, data: Vec<T>) {
+    self.clear();
+    self.data = data;
+    self.indices = (0..self.data.len()).collect();
+    if self.data.is_empty() {
+      return;
+    }
+    let (center, half_size) = self.bounding_cube();
+    let mut splits_queue = VecDeque::new();
+    splits_queue.push_back((OctreeNode::new(0, self.data.len(), center, half_size), 0usize));
+    while let Some((mut node, depth)) = splits_queue.pop_front() {
+      self.summarize(&mut node);
+      if node.end_index - node.start_index > self.leaf_size && depth < MAX_DEPTH {
+        let data = &self.data;
+        self.indices[node.start_index..node.end_index].sort_by_key(|&i| octant(data[i].position(), &node.center));
+        // Same trick as `KdTree`: children are queued behind everything already waiting, so they
+        // land in `nodes` right after those.
+        let queued = splits_queue.len();
+        let first_child = self.nodes.len() + 1 + queued;
+        let quarter = node.half_size / 2f32;
+        let mut start = node.start_index;
+        while start < node.end_index {
+          let child_octant = octant(self.data[self.indices[start]].position(), &node.center);
+          let mut end = start + 1;
+          while end < node.end_index && octant(self.data[self.indices[end]].position(), &node.center) == child_octant {
+            end += 1;
+          }
+          let child_center = node.center + octant_offset(child_octant) * quarter;
+          splits_queue.push_back((OctreeNode::new(start, end, child_center, quarter), depth + 1));
+          start = end;
+        }
+        node.children = Some((first_child, splits_queue.len() - queued));
+      }
+      self.nodes.push(node);
+    }
+  }
+
+  pub fn clear(&mut self) {
+    self.data.clear();
+    self.indices.clear();
+    self.nodes.clear();
+  }
+
+  pub fn count(&self) -> usize {
+    self.data.len()
+  }
+
+  pub fn data(&self) -> &Vec<T> {
+    &self.data
+  }
+
+  pub fn total_mass(&self) -> f32 {
+    self.nodes.first().map_or(0f32, |root| root.mass)
+  }
+
+  pub fn center_of_mass(&self) -> Option<Vec3F> {
+    self.nodes.first().map(|root| root.center_of_mass)
+  }
+
+  // Sum of m r / (|r|^2 + softening^2)^(3/2) over every body, so multiply by G for an
+  // acceleration. A node is treated as a single mass once its width over its distance drops
+  // below `theta`; zero makes the sum exact. Bodies sitting exactly at `position` are skipped.
+  pub fn field_at(&self, position: &Vec3F, theta: f32, softening: f32) -> Vec3F {
+    let mut field = Vec3F::zero();
+    let softening2 = softening * softening;
+    self.reduce(position, theta, |center, mass| {
+      let offset = center - position;
+      let r2 = offset.magnitude2();
+      if r2 > 0f32 {
+        let r2 = r2 + softening2;
+        field += offset * (mass / (r2 * r2.sqrt()));
+      }
+    });
+    field
+  }
+
+  // Sum of -m / sqrt(|r|^2 + softening^2), with the same approximation as `field_at`.
+  pub fn potential_at(&self, position: &Vec3F, theta: f32, softening: f32) -> f32 {
+    let mut potential = 0f32;
+    let softening2 = softening * softening;
+    self.reduce(position, theta, |center, mass| {
+      let r2 = (center - position).magnitude2();
+      if r2 > 0f32 {
+        potential -= mass / (r2 + softening2).sqrt();
+      }
+    });
+    potential
+  }
+
+  fn reduce<R: FnMut(&Vec3F, f32)>(&self, position: &Vec3F, theta: f32, mut reducer: R) {
+    if self.nodes.is_empty() {
+      return;
+    }
+    let mut nodes = vec![0];
+    while let Some(node_ind) = nodes.pop() {
+      let node = &self.nodes[node_ind];
+      match node.children {
+        Some((first_child, count)) => {
+          let distance = (node.center_of_mass - position).magnitude();
+          let width = 2f32 * node.half_size;
+          if !node.contains(position) && width < theta * distance {
+            reducer(&node.center_of_mass, node.mass);
+          } else {
+            nodes.extend(first_child..first_child + count);
+          }
+        }
+        None => {
+          for i in node.start_index..node.end_index {
+            let body = &self.data[self.indices[i]];
+            reducer(body.position(), body.mass());
+          }
+        }
+      }
+    }
+  }
+
+  fn summarize(&self, node: &mut OctreeNode) {
+    let (mass, moment) =
+      self.indices[node.start_index..node.end_index]
+        .iter()
+        .fold((0f32, Vec3F::zero()), |(mass, moment), &i| {
+          let body = &self.data[i];
+          (mass + body.mass(), moment + body.position() * body.mass())
+        });
+    node.mass = mass;
+    node.center_of_mass = if mass > 0f32 { moment / mass } else { node.center };
+  }
+
+  fn bounding_cube(&self) -> (Vec3F, f32) {
+    let first = *self.data[0].position();
+    let (lows, highs) = self.data.iter().fold((first, first), |(lo, hi), body| {
+      let p = body.position();
+      (
+        Vec3F::new(lo.x.min(p.x), lo.y.min(p.y), lo.z.min(p.z)),
+        Vec3F::new(hi.x.max(p.x), hi.y.max(p.y), hi.z.max(p.z)),
+      )
+    });
+    let extent = highs - lows;
+    let half_size = extent.x.max(extent.y).max(extent.z) / 2f32;
+    ((lows + highs) / 2f32, half_size.max(f32::EPSILON))
+  }
+}
+
+fn octant(position: &Vec3F, center: &Vec3F) -> usize {
+  (position.x >= center.x) as usize
+    | ((position.y >= center.y) as usize) << 1
+    | ((position.z >= center.z) as usize) << 2
+}
+
+fn octant_offset(octant: usize) -> Vec3F {
+  let sign = |bit: usize| if octant & bit == 0 { -1f32 } else { 1f32 };
+  Vec3F::new(sign(1), sign(2), sign(4))
+}
+
+// Inclusive on the low end, exclusive on the high
+struct OctreeNode {
+  pub start_index: usize,
+  pub end_index: usize,
+  pub center: Vec3F,
+  pub half_size: f32,
+  pub mass: f32,
+  pub center_of_mass: Vec3F,
+  // First child and how many there are; empty octants get no node.
+  pub children: Option<(usize, usize)>,
+}
+
+impl OctreeNode {
+  pub fn new(s: usize, e: usize, center: Vec3F, half_size: f32) -> Self {
+    Self {
+      start_index: s,
+      end_index: e,
+      center,
+      half_size,
+      mass: 0f32,
+      center_of_mass: center,
+      children: None,
+    }
+  }
+
+  fn contains(&self, position: &Vec3F) -> bool {
+    let offset = position - self.center;
+    offset.x.abs() <= self.half_size && offset.y.abs() <= self.half_size && offset.z.abs() <= self.half_size
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  struct TesterBody(Vec3F, f32);
+
+  impl HasPosition for TesterBody {
+    fn position(&self) -> &Vec3F {
+      &self.0
+    }
+  }
+
+  impl HasMass for TesterBody {
+    fn mass(&self) -> f32 {
+      self.1
+    }
+  }
+
+  fn cloud(count: usize) -> Vec<TesterBody> {
+    (0..count)
+      .map(|i| {
+        TesterBody(
+          Vec3F::new(((i * 7) % 23) as f32, ((i * 13) % 17) as f32, ((i * 5) % 11) as f32),
+          1f32 + (i % 3) as f32,
+        )
+      })
+      .collect()
+  }
+
+  fn brute_force_field(bodies: &[TesterBody], position: &Vec3F) -> Vec3F {
+    bodies
+      .iter()
+      .filter(|b| b.0 != *position)
+      .fold(Vec3F::zero(), |acc, b| {
+        let offset = b.0 - position;
+        acc + offset * (b.1 / offset.magnitude().powi(3))
+      })
+  }
+
+  #[test]
+  fn root_holds_total_mass_and_centre() {
+    let bodies = vec![
+      TesterBody(Vec3F::new(0f32, 0f32, 0f32), 1f32),
+      TesterBody(Vec3F::new(4f32, 0f32, 0f32), 3f32),
+      TesterBody(Vec3F::new(4f32, 0f32, 0f32), 1f32),
+    ];
+    let tree = BarnesHutTree::new(bodies, 1);
+    assert_eq!(tree.count(), 3);
+    assert_eq!(tree.total_mass(), 5f32);
+    assert_eq!(tree.center_of_mass(), Some(Vec3F::new(3.2f32, 0f32, 0f32)));
+  }
+
+  #[test]
+  fn zero_theta_is_exact() {
+    let bodies = cloud(64);
+    let probes: Vec<Vec3F> = bodies.iter().step_by(9).map(|b| b.0).collect();
+    let expected: Vec<Vec3F> = probes.iter().map(|p| brute_force_field(&bodies, p)).collect();
+    let tree = BarnesHutTree::new(bodies, 2);
+    for (probe, expected) in probes.iter().zip(expected.iter()) {
+      assert!((tree.field_at(probe, 0f32, 0f32) - expected).magnitude() < 1e-4f32);
+    }
+  }
+
+  #[test]
+  fn approximation_stays_close_to_brute_force() {
+    let bodies = cloud(200);
+    let probe = Vec3F::new(60f32, -20f32, 30f32);
+    let expected = brute_force_field(&bodies, &probe);
+    let tree = BarnesHutTree::new(bodies, 1);
+    let approximate = tree.field_at(&probe, 0.5f32, 0f32);
+    // Monopole-only nodes are good to a couple of percent at theta = 0.5.
+    assert!((approximate - expected).magnitude() / expected.magnitude() < 0.02f32);
+    let exact_potential = tree.potential_at(&probe, 0f32, 0f32);
+    assert!((tree.potential_at(&probe, 0.5f32, 0f32) - exact_potential).abs() / exact_potential.abs() < 0.01f32);
+  }
+}
@@ -54,6 +54,10 @@ impl<T: HasPosition> SpatialIndex<T> for KdTree<T> {
         node.partition_value = midpt;
         let dim = (node.dimension + 1) % 3;
         let mid_index = (node.end_index + node.start_index) / 2;
+        // Nodes land in partition_tree in the order they leave the queue, so the children
+        // will sit after this node and everything already waiting in the queue.
+        let left_child = self.partition_tree.len() + 1 + splits_queue.len();
+        node.children = Some((left_child, left_child + 1));
         splits_queue.push_back(KdTreeNode::new(node.start_index, mid_index, dim));
         splits_queue.push_back(KdTreeNode::new(mid_index, node.end_index, dim));
       }
@@ -93,13 +97,13 @@ impl<T: HasPosition> KdTree<T> {
     let mut heap = BinaryHeap::new();
     let k = (start_i + end_i) / 2;
     for i in start_i..end_i {
-      heap.push(Reverse(SortIndexArrayElem::new(&self.data, i, d)));
+      heap.push(Reverse(SortIndexArrayElem::new(&self.data, self.indices[i], d)));
     }
     let mut ret = self.data[self.indices[start_i]].position()[d];
     for i in start_i..end_i {
       let elem = heap.pop().unwrap().0;
       self.indices[i] = elem.index;
-      if elem.index == k {
+      if i == k {
         ret = *elem.value();
       }
     }
@@ -111,28 +115,33 @@ impl<T: HasPosition> KdTree<T> {
   }
 
   fn kd_reduce<R: FnMut(&usize) -> ()>(&self, position: &Vec3F, radius: &f32, mut reducer: R) {
+    if self.partition_tree.is_empty() {
+      return;
+    }
     let mut nodes = vec![0];
     let r2 = radius * radius;
     while nodes.len() > 0 {
       let node_ind = nodes.pop().unwrap();
       let node = &self.partition_tree[node_ind];
-      let left_child = node_ind * 2 + 1;
-      let right_child = node_ind * 2 + 2;
-      if left_child < nodes.len() && node.partition_value >= position[node.dimension] - radius {
-        // Need to recurse down left
-        nodes.push(left_child);
-      }
-      if right_child < nodes.len() && node.partition_value <= position[node.dimension] + radius {
-        // Need to recurse down right
-        nodes.push(right_child);
-      }
-      if nodes.len() < left_child {
-        // We are at a leaf. So time to accumulate!
-        for i in node.start_index..node.end_index {
-          let elem_pos = self.data[self.indices[i]].position();
-          let delta = elem_pos - position;
-          if delta.dot(delta) < r2 {
-            reducer(&self.indices[i]);
+      match node.children {
+        Some((left_child, right_child)) => {
+          if node.partition_value >= position[node.dimension] - radius {
+            // Need to recurse down left
+            nodes.push(left_child);
+          }
+          if node.partition_value <= position[node.dimension] + radius {
+            // Need to recurse down right
+            nodes.push(right_child);
+          }
+        }
+        None => {
+          // We are at a leaf. So time to accumulate!
+          for i in node.start_index..node.end_index {
+            let elem_pos = self.data[self.indices[i]].position();
+            let delta = elem_pos - position;
+            if delta.dot(delta) < r2 {
+              reducer(&self.indices[i]);
+            }
           }
         }
       }
@@ -146,6 +155,7 @@ struct KdTreeNode {
   pub end_index: usize,
   pub partition_value: f32,
   pub dimension: usize,
+  pub children: Option<(usize, usize)>,
 }
 
 impl KdTreeNode {
@@ -155,6 +165,7 @@ impl KdTreeNode {
       end_index: e,
       partition_value: 0f32,
       dimension,
+      children: None,
     }
   }
 }
@@ -263,6 +274,29 @@ mod test {
     });
   }
 
+  #[test]
+  fn test_uneven_tree_matches_brute_force() {
+    // 17 points with a node size of 8 split into leaves at different depths.
+    let verts: Vec<TesterPoint> = (0..17)
+      .map(|i| TesterPoint(Vec3F::new(((i * 7) % 17) as f32, ((i * 5) % 3) as f32, (i % 4) as f32)))
+      .collect();
+    let expected: Vec<Vec<usize>> = (0..17)
+      .map(|i| {
+        let center = verts[i].0;
+        (0..verts.len())
+          .filter(|j| (verts[*j].0 - center).magnitude2() < 6.25f32)
+          .collect()
+      })
+      .collect();
+    let centers: Vec<Vec3F> = verts.iter().map(|v| v.0).collect();
+    let tree = KdTree::new(verts, 8);
+    for (center, expected) in centers.iter().zip(expected.iter()) {
+      let mut found = tree.query_near(center, 2.5f32);
+      found.sort();
+      assert_eq!(&found, expected);
+    }
+  }
+
   fn generate_voxel_points(dims: usize) -> Vec<TesterPoint> {
     let mut verts = Vec::new();
     for i in 0..dims {
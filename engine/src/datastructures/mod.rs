@@ -1,4 +1,5 @@
 mod avl_tree;
+mod barnes_hut;
 mod kd_tree;
 mod registry;
 mod spatial_index;
@@ -6,6 +7,7 @@ mod n_tree;
 
 // pub use self::dirty_bit_storage::*;
 pub use self::avl_tree::*;
+pub use self::barnes_hut::*;
 pub use self::kd_tree::*;
 pub use self::registry::*;
 pub use self::spatial_index::*;
@@ -10,6 +10,7 @@ pub struct DebugMetrics {
   pub frame_time: CompoundStopwatch,
   pub render_time: CompoundStopwatch,
   pub poly_count: Counter,
+  pub energy: EnergyMonitor,
 }
 
 impl Default for DebugMetrics {
@@ -20,10 +21,46 @@ impl Default for DebugMetrics {
       frame_time: CompoundStopwatch::new(120u32),
       render_time: CompoundStopwatch::new(120u32),
       poly_count: Counter::default(),
+      energy: EnergyMonitor::default(),
     }
   }
 }
 
+// Total energy of a simulation that should conserve it, compared against the first reading
+// taken since the last reset.
+#[derive(Default)]
+pub struct EnergyMonitor {
+  baseline: Option<f64>,
+  current: f64,
+}
+
+impl EnergyMonitor {
+  pub fn record(&mut self, energy: f64) {
+    self.baseline.get_or_insert(energy);
+    self.current = energy;
+  }
+
+  // Call whenever the system changes in a way that should change its energy.
+  pub fn reset(&mut self) {
+    self.baseline = None;
+  }
+
+  pub fn current(&self) -> Option<f64> {
+    self.baseline.map(|_| self.current)
+  }
+
+  // Change since the baseline, relative to it unless the baseline is zero.
+  pub fn drift(&self) -> Option<f64> {
+    self.baseline.map(|baseline| {
+      if baseline == 0f64 {
+        self.current
+      } else {
+        (self.current - baseline) / baseline.abs()
+      }
+    })
+  }
+}
+
 pub struct DebugMetricsSystem {
   timestep_averager: Averager,
 }
@@ -59,6 +96,13 @@ impl<'a> MonoBehavior<'a> for DebugMetricsSystem {
     panel.set_str("DrawCalls", format!("{}", debugger.draw_calls.get()));
     panel.set_str("TimestepValue", format!("{:.6} Avg", self.timestep_averager.get_avg()));
     panel.set_str("PolyCount", format!("{} ", debugger.poly_count.get()));
+    panel.set_str(
+      "EnergyDrift",
+      match (debugger.energy.current(), debugger.energy.drift()) {
+        (Some(energy), Some(drift)) => format!("{:+.3e} ({:.4} Total)", drift, energy),
+        _ => "N/A".to_string(),
+      },
+    );
     debugger.frame_time.start();
   }
 
@@ -77,5 +121,6 @@ impl<'a> SystemDebugger<'a> for DebugMetricsSystem {
       .push_line("DrawCalls", LabeledText::new("NaN", "Draw Calls"))
       .push_line("PolyCount", LabeledText::new("NaN", "Poly Count"))
       .push_line("TimestepValue", LabeledText::new("NaN", "Timestep Value"))
+      .push_line("EnergyDrift", LabeledText::new("N/A", "Energy Drift"))
   }
 }
@@ -4,6 +4,7 @@ use cgmath::prelude::*;
 use specs::prelude::*;
 use specs::{Component, NullStorage, VecStorage};
 
+use crate::relativity::RelativisticObserver;
 use crate::utils::*;
 
 const DEG_89: cgmath::Rad<f32> = cgmath::Rad(1.5533430342749532f32);
@@ -95,6 +96,21 @@ impl Camera {
     self.right().cross(self.front()).normalize()
   }
 
+  // Distance along the view ray to a sphere at world position `center`, as the observer sees it.
+  pub fn pick_apparent(&self, observer: &RelativisticObserver, center: &Vec3F, radius: f32) -> Option<f32> {
+    let apparent = observer.apparent_position(center);
+    let facing = self.front();
+    let to_center = apparent - self.position;
+    let along = to_center.dot(facing);
+    let miss2 = to_center.magnitude2() - along * along;
+    let r2 = radius * radius;
+    if along < 0f32 || miss2 > r2 {
+      None
+    } else {
+      Some(along - (r2 - miss2).sqrt())
+    }
+  }
+
   pub fn facing_matrix(&self) -> [f32; 3] {
     let facing = self.front();
     return [
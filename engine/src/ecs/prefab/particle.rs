@@ -83,8 +83,8 @@ impl PrefabBuilder for ParticleBuilder {
         .with(RigidBody::new(
           state.velocity.unwrap(),
           -Vec3F::unit_y(),
-          QuatF::zero(),
-          QuatF::zero(),
+          Vec3F::zero(),
+          Vec3F::zero(),
         ))
     });
     if state.gravity.is_some() {
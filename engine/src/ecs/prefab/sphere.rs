@@ -77,8 +77,7 @@ impl PrefabBuilder for Sphere {
     transform.push_rotation(&axis_tilt);
     let axis = axis_tilt.rotate_vector(Vec3F::unit_y());
     let mut rigid_body = RigidBody::new_stationary();
-    let rotation = QuatF::from_axis_angle(axis, cgmath::Deg(0.25f32));
-    rigid_body.angular_velocity = rotation;
+    rigid_body.angular_velocity = axis * cgmath::Rad::from(cgmath::Deg(15f32)).0;
     api
       .entity_builder()
       .and(|ett| ett.with(material).with(transform).with(mesh).with(rigid_body))
@@ -10,6 +10,7 @@ use crate::ecs::{Guid, GuidMap, PrefabBuilder};
 use crate::events::{EventChannel, StatefulEventChannel};
 use crate::graphics::AssetLibrary;
 use crate::gui::{ControlPanel, ControlPanels};
+use crate::physics::PhysicsQueries;
 use crate::datastructures::{NTree};
 use super::EntityTreeBuilder;
 
@@ -24,6 +25,7 @@ pub struct SystemUtilities<'a> {
   asset_library: Read<'a, AssetLibrary>,
   control_panels: Read<'a, ControlPanels>,
   guid: Read<'a, GuidMap>,
+  physics_queries: Read<'a, PhysicsQueries>,
 }
 
 impl<'a> SystemUtilities<'a> {
@@ -69,6 +71,11 @@ impl<'a> SystemUtilities<'a> {
   pub fn get_guid(&self) -> Guid {
     Guid::new()
   }
+
+  // Raycasts and other scene queries, as of the last physics tick.
+  pub fn physics(&self) -> &PhysicsQueries {
+    &self.physics_queries
+  }
 }
 
 impl<'a> std::ops::Deref for SystemUtilities<'a> {
@@ -0,0 +1,185 @@
+use cgmath::prelude::*;
+use specs::prelude::*;
+
+use crate::physics::{resolve_collision, CanCollide, CollisionMode, Mass, RigidBody, TransformComponent};
+use crate::relativity::{Relativistic, SpeedOfLight};
+use crate::utils::Vec3F;
+
+const MIN_SEPARATION: f32 = 1e-6f32;
+
+// Resolves collisions between pairs of massive `CanCollide` spheres that are overlapping and
+// still approaching. Pairs where both bodies are `Relativistic` conserve four-momentum under
+// the current `SpeedOfLight`, writing any rest mass gained in an inelastic collision back to
+// their `Mass`; any other pair uses the Newtonian limit.
+pub struct CollisionResponseSystem;
+
+impl<'a> System<'a> for CollisionResponseSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, TransformComponent>,
+    WriteStorage<'a, RigidBody>,
+    WriteStorage<'a, Mass>,
+    ReadStorage<'a, CanCollide>,
+    ReadStorage<'a, Relativistic>,
+    Read<'a, CollisionMode>,
+    Read<'a, SpeedOfLight>,
+  );
+
+  fn run(
+    &mut self,
+    (entities, transform_s, mut rigid_body_s, mut mass_s, collide_s, relativistic_s, mode, speed_of_light): Self::SystemData,
+  ) {
+    let mut bodies: Vec<(Entity, Vec3F, f32, f32, bool)> = (
+      &entities,
+      &transform_s,
+      &mass_s,
+      &collide_s,
+      &rigid_body_s,
+      (&relativistic_s).maybe(),
+    )
+      .join()
+      .map(|(entity, transform, mass, collide, _rb, relativistic)| {
+        (
+          entity,
+          transform.translation,
+          mass.0,
+          collide.radius,
+          relativistic.is_some(),
+        )
+      })
+      .collect();
+
+    for i in 0..bodies.len() {
+      for j in (i + 1)..bodies.len() {
+        let (e1, x1, m1, r1, rel1) = bodies[i];
+        let (e2, x2, m2, r2, rel2) = bodies[j];
+        let offset = x2 - x1;
+        let distance = offset.magnitude();
+        if distance >= r1 + r2 || distance < MIN_SEPARATION {
+          continue;
+        }
+        let normal = offset / distance;
+        let v1 = rigid_body_s.get(e1).unwrap().velocity;
+        let v2 = rigid_body_s.get(e2).unwrap().velocity;
+        if (v1 - v2).dot(normal) <= 0f32 {
+          // Already separating
+          continue;
+        }
+        // Four-momentum only means something when both bodies move relativistically. A mixed
+        // pair is resolved in the Newtonian limit, so the classical body never picks up a
+        // velocity or mass it could not have reached by itself.
+        let c = match (rel1, rel2) {
+          (true, true) => Some(speed_of_light.get()),
+          (true, false) | (false, true) | (false, false) => None,
+        };
+        let ((n1, u1), (n2, u2)) = resolve_collision(*mode, (m1, &v1), (m2, &v2), &normal, c);
+        rigid_body_s.get_mut(e1).unwrap().velocity = u1;
+        rigid_body_s.get_mut(e2).unwrap().velocity = u2;
+        mass_s.get_mut(e1).unwrap().0 = n1;
+        mass_s.get_mut(e2).unwrap().0 = n2;
+        bodies[i].2 = n1;
+        bodies[j].2 = n2;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::relativity::FourMomentum;
+  use crate::testing::TestingEcsBuilder;
+  use crate::utils::{assert_close_within, assert_vec_close};
+
+  fn body(x: f32) -> TransformComponent {
+    let mut transform = TransformComponent::default();
+    transform.translation = Vec3F::new(x, 0f32, 0f32);
+    transform
+  }
+
+  fn moving(vx: f32) -> RigidBody {
+    let mut rigid_body = RigidBody::default();
+    rigid_body.velocity = Vec3F::new(vx, 0f32, 0f32);
+    rigid_body
+  }
+
+  // Two relativistic bodies, overlapping and approaching, after one run. Returns each body's
+  // mass and velocity along with the pair's four-momentum before the collision.
+  fn collide_pair(mode: CollisionMode, c: f32) -> (Vec<(f32, Vec3F)>, FourMomentum) {
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(CollisionResponseSystem)
+      .with_resource(SpeedOfLight::new(c))
+      .with_resource(mode)
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<Mass>()
+      .register_component::<CanCollide>()
+      .register_component::<Relativistic>()
+      .with_entity(|e| {
+        e.with(body(0f32))
+          .with(moving(8f32))
+          .with(Mass(2f32))
+          .with(CanCollide { radius: 1f32 })
+          .with(Relativistic)
+          .build()
+      })
+      .with_entity(|e| {
+        e.with(body(1.5f32))
+          .with(moving(-5f32))
+          .with(Mass(1f32))
+          .with(CanCollide { radius: 1f32 })
+          .with(Relativistic)
+          .build()
+      })
+      .build();
+    let before = FourMomentum::from_velocity(2f32, &Vec3F::new(8f32, 0f32, 0f32), c)
+      + FourMomentum::from_velocity(1f32, &Vec3F::new(-5f32, 0f32, 0f32), c);
+    ecs.run();
+
+    let world = ecs.world();
+    let rigid_body_s = world.read_storage::<RigidBody>();
+    let mass_s = world.read_storage::<Mass>();
+    let after = (&mass_s, &rigid_body_s)
+      .join()
+      .map(|(mass, rb)| (mass.0, rb.velocity))
+      .collect();
+    (after, before)
+  }
+
+  fn total(bodies: &[(f32, Vec3F)], c: f32) -> FourMomentum {
+    bodies
+      .iter()
+      .map(|(mass, velocity)| FourMomentum::from_velocity(*mass, velocity, c))
+      .fold(FourMomentum::new(0f32, Vec3F::zero()), |acc, p| acc + p)
+  }
+
+  // Energies are of order m c², so they are compared to f32 precision rather than absolutely.
+  fn assert_conserved(after: &FourMomentum, before: &FourMomentum) {
+    assert_close_within(after.energy, before.energy, 1e-6f32 * before.energy);
+    assert_vec_close(after.momentum, before.momentum);
+  }
+
+  #[test]
+  fn relativistic_pair_conserves_four_momentum() {
+    let c = 10f32;
+    let (bodies, before) = collide_pair(CollisionMode::Elastic, c);
+    let after = total(&bodies, c);
+    assert_conserved(&after, &before);
+    let velocities: Vec<f32> = bodies.iter().map(|(_, velocity)| velocity.x).collect();
+    assert!(
+      velocities[0] < velocities[1],
+      "bodies should separate: {:?}",
+      velocities
+    );
+  }
+
+  #[test]
+  fn inelastic_pair_gains_rest_mass() {
+    let c = 10f32;
+    let (bodies, before) = collide_pair(CollisionMode::PerfectlyInelastic, c);
+    let after = total(&bodies, c);
+    assert_conserved(&after, &before);
+    assert_eq!(bodies[0].1, bodies[1].1);
+    assert!(bodies[0].0 + bodies[1].0 > 3f32);
+  }
+}
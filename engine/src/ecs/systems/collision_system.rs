@@ -0,0 +1,128 @@
+use cgmath::prelude::*;
+use specs::prelude::*;
+
+use crate::events::EventChannel;
+use crate::physics::{
+  rebuild_solid_broadphase, Broadphase, CanCollide, ColliderLookup, ColliderStorages, CollisionEvent,
+  CollisionEventChannel, CollisionTopic, RigidBody, TransformComponent, Trigger,
+};
+use crate::utils::FixedTimestep;
+
+// Sweeps every moving `CanCollide` sphere against the colliders over the coming physics tick.
+// Colliders of every type are indexed in the `Broadphase` resource each frame, candidates are
+// narrowed down with `ColliderLookup::sphere_collision`, and hits are published on the
+// `CollisionEventChannel` under `CollisionTopic::All` and `CollisionTopic::Involving` for both
+// entities. Events stay readable until this system next runs. `Trigger` colliders are left out of
+// the broadphase, so nothing collides with them.
+#[derive(Default)]
+pub struct CollisionSystem;
+
+impl<'a> System<'a> for CollisionSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, TransformComponent>,
+    ReadStorage<'a, RigidBody>,
+    ReadStorage<'a, CanCollide>,
+    ColliderStorages<'a>,
+    ReadStorage<'a, Trigger>,
+    Read<'a, FixedTimestep>,
+    Write<'a, CollisionEventChannel>,
+    Write<'a, Broadphase>,
+  );
+
+  fn run(
+    &mut self,
+    (entities, transform_s, rigid_body_s, collide_s, colliders, trigger_s, timestep, mut channel, mut broadphase): Self::SystemData,
+  ) {
+    channel.clear_events();
+    rebuild_solid_broadphase(&mut broadphase, &entities, &colliders, &trigger_s);
+
+    let dt = timestep.dt_f32();
+    for (sphere, transform, rigid_body, collide) in (&entities, &transform_s, &rigid_body_s, &collide_s).join() {
+      let center = transform.translation;
+      let reach = collide.radius + rigid_body.velocity.magnitude() * dt;
+      for candidate in broadphase.candidates(&center, reach) {
+        if candidate.entity == sphere {
+          continue;
+        }
+        if let Some(summary) =
+          colliders.sphere_collision(candidate.entity, (&center, &collide.radius), &rigid_body.velocity)
+        {
+          if summary.time <= dt {
+            let event = CollisionEvent {
+              sphere,
+              collider: candidate.entity,
+              summary,
+            };
+            channel.publish((CollisionTopic::All, event));
+            channel.publish((CollisionTopic::Involving(sphere), event));
+            channel.publish((CollisionTopic::Involving(candidate.entity), event));
+          }
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::events::ReceiverId;
+  use crate::physics::{
+    AxisAlignedCubeCollision, CapsuleCollision, MeshCollision, OrientedBoxCollision, SphereCollision,
+  };
+  use crate::testing::TestingEcsBuilder;
+  use crate::utils::Vec3F;
+
+  fn at(translation: Vec3F) -> TransformComponent {
+    let mut transform = TransformComponent::default();
+    transform.translation = translation;
+    transform
+  }
+
+  #[test]
+  fn publishes_hits_within_the_timestep() {
+    let mut channel = CollisionEventChannel::default();
+    let receiver: ReceiverId = channel.register_with_subs(&[CollisionTopic::All]);
+    let wall = at(Vec3F::new(5f32, 0f32, 0f32));
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(CollisionSystem::default())
+      .with_resource(channel)
+      .with_resource(FixedTimestep::default())
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<CanCollide>()
+      .register_component::<AxisAlignedCubeCollision>()
+      .register_component::<OrientedBoxCollision>()
+      .register_component::<SphereCollision>()
+      .register_component::<CapsuleCollision>()
+      .register_component::<MeshCollision>()
+      .with_entity(|e| e.with(AxisAlignedCubeCollision::from_transform(&wall)).build())
+      .with_entity(|e| {
+        let mut rigid_body = RigidBody::default();
+        rigid_body.velocity = Vec3F::new(1000f32, 0f32, 0f32);
+        e.with(at(Vec3F::zero()))
+          .with(rigid_body)
+          .with(CanCollide { radius: 0.5f32 })
+          .build()
+      })
+      .with_entity(|e| {
+        // Moving away from the wall, never hits it.
+        let mut rigid_body = RigidBody::default();
+        rigid_body.velocity = Vec3F::new(-1000f32, 0f32, 0f32);
+        e.with(at(Vec3F::new(0f32, 3f32, 0f32)))
+          .with(rigid_body)
+          .with(CanCollide { radius: 0.5f32 })
+          .build()
+      })
+      .build();
+    ecs.run();
+
+    let channel = ecs.world().read_resource::<CollisionEventChannel>();
+    let events = channel.read(&receiver);
+    assert_eq!(events.len(), 1);
+    let (_topic, event) = events[0];
+    assert!((event.summary.position.x - 4f32).abs() < 1e-4f32);
+    assert_eq!(event.summary.surface_normal, -Vec3F::unit_x());
+  }
+}
@@ -0,0 +1,122 @@
+use specs::prelude::*;
+
+use crate::ecs::{MonoBehavior, SystemUtilities, WorldProxy};
+use crate::gui::{widgets::*, ControlPanelBuilder, SystemDebugger};
+use crate::physics::{ForceField, ForceFields};
+use crate::utils::Vec3F;
+
+const GRAVITY_FIELD: &str = "gravity";
+const ATTRACTOR_FIELD: &str = "attractor";
+
+// Edits the `ForceFields` resource live from the "Force Fields" panel: the uniform "gravity"
+// field, a point-mass "attractor" and the drag scale. The panel starts out showing whatever the
+// resource held at startup, and a field is only written back once its panel value is changed, so
+// fields set by game code in the meantime are left alone.
+pub struct ForceFieldSystem {
+  gravity: Vec3F,
+  attractor_position: Vec3F,
+  attractor_strength: f32,
+  attractor_softening: f32,
+  drag_scale: f32,
+}
+
+impl Default for ForceFieldSystem {
+  fn default() -> Self {
+    Self {
+      gravity: Vec3F::new(0f32, 0f32, 0f32),
+      attractor_position: Vec3F::new(0f32, 0f32, 0f32),
+      attractor_strength: 0f32,
+      attractor_softening: 1f32,
+      drag_scale: 1f32,
+    }
+  }
+}
+
+impl<'a> MonoBehavior<'a> for ForceFieldSystem {
+  type SystemData = Write<'a, ForceFields>;
+
+  fn run(&mut self, api: SystemUtilities<'a>, mut force_fields: Self::SystemData) {
+    let mut panel = self.get_write_panel(&api);
+    let gravity = panel.get_vec3("Gravity");
+    if gravity != self.gravity {
+      self.gravity = gravity;
+      force_fields.insert(GRAVITY_FIELD, ForceField::Uniform(gravity));
+    }
+    let position = panel.get_vec3("AttractorPosition");
+    let strength = panel.get_float("AttractorStrength");
+    let softening = panel.get_float("AttractorSoftening");
+    if position != self.attractor_position
+      || strength != self.attractor_strength
+      || softening != self.attractor_softening
+    {
+      self.attractor_position = position;
+      self.attractor_strength = strength;
+      self.attractor_softening = softening;
+      if strength > 0f32 {
+        force_fields.insert(
+          ATTRACTOR_FIELD,
+          ForceField::PointMass {
+            position,
+            strength,
+            softening,
+          },
+        );
+      } else {
+        force_fields.remove(ATTRACTOR_FIELD);
+      }
+    }
+    let drag_scale = panel.get_float("DragScale");
+    if drag_scale != self.drag_scale {
+      self.drag_scale = drag_scale;
+      force_fields.drag_scale = drag_scale;
+    }
+    let names: Vec<&str> = force_fields.names().collect();
+    panel.set_str("Fields", names.join(", "));
+  }
+
+  fn setup(&mut self, world: WorldProxy) {
+    {
+      let force_fields = world.read_resource::<ForceFields>();
+      if let Some(ForceField::Uniform(gravity)) = force_fields.get(GRAVITY_FIELD) {
+        self.gravity = *gravity;
+      }
+      if let Some(ForceField::PointMass {
+        position,
+        strength,
+        softening,
+      }) = force_fields.get(ATTRACTOR_FIELD)
+      {
+        self.attractor_position = *position;
+        self.attractor_strength = *strength;
+        self.attractor_softening = *softening;
+      }
+      self.drag_scale = force_fields.drag_scale;
+    }
+    self.register_debugger(&world);
+  }
+}
+
+impl<'a> SystemDebugger<'a> for ForceFieldSystem {
+  fn create_panel(&self) -> ControlPanelBuilder {
+    ControlPanelBuilder::default()
+      .with_title("Force Fields")
+      .push_line("Fields", LabeledText::new("", "Active Fields"))
+      .push_line("Gravity", InputVec3::new("Uniform Field", self.gravity))
+      .push_line(
+        "AttractorPosition",
+        InputVec3::new("Attractor Position", self.attractor_position),
+      )
+      .push_line(
+        "AttractorStrength",
+        InputFloat::new_with_limits("Attractor GM", self.attractor_strength, 0f32, 10000f32),
+      )
+      .push_line(
+        "AttractorSoftening",
+        InputFloat::new_with_limits("Softening", self.attractor_softening, 0f32, 10f32),
+      )
+      .push_line(
+        "DragScale",
+        InputFloat::new_with_limits("Drag Scale", self.drag_scale, 0f32, 10f32),
+      )
+  }
+}
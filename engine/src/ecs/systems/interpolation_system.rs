@@ -0,0 +1,96 @@
+use specs::prelude::*;
+
+use crate::physics::{InterpolatedTransform, PreviousTransform, RigidBody, TransformComponent};
+use crate::utils::FixedTimestep;
+
+// Runs at the start of every physics tick, remembering where each rigid body was before the tick
+// moves it.
+pub struct TransformSnapshotSystem;
+
+impl<'a> System<'a> for TransformSnapshotSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, TransformComponent>,
+    ReadStorage<'a, RigidBody>,
+    WriteStorage<'a, PreviousTransform>,
+  );
+
+  fn run(&mut self, (entities, transform_s, rigid_body_s, mut previous_s): Self::SystemData) {
+    for (entity, transform, _) in (&entities, &transform_s, &rigid_body_s).join() {
+      previous_s
+        .insert(entity, PreviousTransform(transform.clone()))
+        .expect("Could not insert PreviousTransform");
+    }
+  }
+}
+
+// Runs once per rendered frame, placing each body between its last two physics states by the
+// `FixedTimestep` alpha so that motion looks smooth at any framerate.
+pub struct TransformInterpolationSystem;
+
+impl<'a> System<'a> for TransformInterpolationSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, TransformComponent>,
+    ReadStorage<'a, PreviousTransform>,
+    WriteStorage<'a, InterpolatedTransform>,
+    Read<'a, FixedTimestep>,
+  );
+
+  fn run(&mut self, (entities, transform_s, previous_s, mut interpolated_s, fixed): Self::SystemData) {
+    for (entity, transform, previous) in (&entities, &transform_s, &previous_s).join() {
+      interpolated_s
+        .insert(entity, InterpolatedTransform(previous.0.lerp(transform, fixed.alpha())))
+        .expect("Could not insert InterpolatedTransform");
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::testing::TestingEcsBuilder;
+  use crate::utils::Vec3F;
+
+  fn at(x: f32) -> TransformComponent {
+    let mut transform = TransformComponent::default();
+    transform.translation = Vec3F::new(x, 0f32, 0f32);
+    transform
+  }
+
+  #[test]
+  fn snapshots_rigid_bodies_only() {
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(TransformSnapshotSystem)
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<PreviousTransform>()
+      .with_entity(|e| e.with(at(3f32)).with(RigidBody::default()).build())
+      .with_entity(|e| e.with(at(5f32)).build())
+      .build();
+    ecs.run();
+
+    let previous = ecs.world().read_storage::<PreviousTransform>();
+    let snapshots: Vec<f32> = previous.join().map(|p| p.0.translation.x).collect();
+    assert_eq!(snapshots, vec![3f32]);
+  }
+
+  #[test]
+  fn interpolates_between_physics_ticks() {
+    let mut fixed = FixedTimestep::new(10f32);
+    fixed.accumulate(0.125f32);
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(TransformInterpolationSystem)
+      .with_resource(fixed)
+      .register_component::<TransformComponent>()
+      .register_component::<PreviousTransform>()
+      .register_component::<InterpolatedTransform>()
+      .with_entity(|e| e.with(at(4f32)).with(PreviousTransform(at(0f32))).build())
+      .build();
+    ecs.run();
+
+    let interpolated = ecs.world().read_storage::<InterpolatedTransform>();
+    let drawn = interpolated.join().next().unwrap();
+    assert!((drawn.0.translation.x - 1f32).abs() < 1e-4f32);
+  }
+}
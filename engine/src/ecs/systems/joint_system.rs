@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use cgmath::prelude::*;
+use specs::prelude::*;
+
+use crate::ecs::EntityTree;
+use crate::physics::{
+  apply_solved_body, solve_joints, Inertia, Joint, JointSolver, Mass, RigidBody, SolverBody, TransformComponent,
+};
+use crate::utils::{FixedTimestep, QuatF, Vec3F};
+
+// Enforces every `Joint` once per physics tick, after the bodies have been moved. A joint without
+// an explicit `connected` entity links to its parent in the `EntityTree`, so an articulated
+// model can be built with `EntityTreeBuilder::spawn_child` and a `Joint` on each child.
+#[derive(Default)]
+pub struct JointSystem;
+
+impl<'a> System<'a> for JointSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, EntityTree>,
+    WriteStorage<'a, Joint>,
+    WriteStorage<'a, TransformComponent>,
+    WriteStorage<'a, RigidBody>,
+    ReadStorage<'a, Mass>,
+    ReadStorage<'a, Inertia>,
+    Read<'a, JointSolver>,
+    Read<'a, FixedTimestep>,
+  );
+
+  fn run(
+    &mut self,
+    (entities, tree_s, mut joint_s, mut transform_s, mut rigid_body_s, mass_s, inertia_s, solver, timestep): Self::SystemData,
+  ) {
+    if joint_s.is_empty() {
+      return;
+    }
+    let mut parents = HashMap::new();
+    for (parent, tree) in (&entities, &tree_s).join() {
+      for (child, _) in (&entities, tree.clone()).join() {
+        parents.insert(child, parent);
+      }
+    }
+
+    let mut bodies: Vec<SolverBody> = Vec::new();
+    let mut indices: HashMap<Entity, usize> = HashMap::new();
+    let mut world_index = None;
+    let mut index_of = |entity: Option<Entity>, bodies: &mut Vec<SolverBody>| -> usize {
+      match entity {
+        Some(entity) => *indices.entry(entity).or_insert_with(|| {
+          let transform = transform_s.get(entity).unwrap();
+          bodies.push(match rigid_body_s.get(entity) {
+            Some(rigid_body) => SolverBody::dynamic(transform, rigid_body, mass_s.get(entity), inertia_s.get(entity)),
+            None => SolverBody::fixed(transform.translation, transform.rotation),
+          });
+          bodies.len() - 1
+        }),
+        None => *world_index.get_or_insert_with(|| {
+          bodies.push(SolverBody::fixed(Vec3F::zero(), QuatF::one()));
+          bodies.len() - 1
+        }),
+      }
+    };
+
+    let mut linked = Vec::new();
+    for (entity, joint) in (&entities, &mut joint_s).join() {
+      let connected = joint.connected.or_else(|| parents.get(&entity).copied());
+      // Only a joint with nothing to connect to is anchored to the world. One whose connected
+      // entity is gone or has no transform is skipped, rather than pulled towards the origin.
+      let attached = |entity: Entity| entities.is_alive(entity) && transform_s.contains(entity);
+      if !attached(entity) || !connected.is_none_or(attached) {
+        continue;
+      }
+      let body = index_of(Some(entity), &mut bodies);
+      let other = index_of(connected, &mut bodies);
+      joint.capture_reference(&bodies[body].rotation, &bodies[other].rotation);
+      linked.push((body, other, joint.clone()));
+    }
+    let joints: Vec<(usize, usize, &Joint)> = linked.iter().map(|(a, b, joint)| (*a, *b, joint)).collect();
+    solve_joints(&mut bodies, &joints, &solver, timestep.dt_f32());
+
+    for (entity, index) in indices {
+      if let (Some(transform), Some(rigid_body)) = (transform_s.get_mut(entity), rigid_body_s.get_mut(entity)) {
+        apply_solved_body(&bodies[index], transform, rigid_body, timestep.dt_f32());
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::testing::TestingEcsBuilder;
+  use specs::hibitset::BitSet;
+
+  #[test]
+  fn child_swings_on_its_parent() {
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(JointSystem)
+      .with_resource(FixedTimestep::new(60f32))
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<Joint>()
+      .register_component::<EntityTree>()
+      .with_entity(|e| {
+        let transform = TransformComponent {
+          translation: Vec3F::new(0f32, -2f32, 0f32),
+          ..Default::default()
+        };
+        let rigid_body = RigidBody::new(
+          Vec3F::new(1f32, -3f32, 0f32),
+          Vec3F::zero(),
+          Vec3F::zero(),
+          Vec3F::zero(),
+        );
+        e.with(transform)
+          .with(rigid_body)
+          .with(Joint::distance(2f32, Vec3F::zero(), Vec3F::zero()))
+          .build()
+      })
+      .with_entity(|e| {
+        let mut children = BitSet::new();
+        children.add(0);
+        let transform = TransformComponent {
+          translation: Vec3F::zero(),
+          ..Default::default()
+        };
+        e.with(transform).with(EntityTree::new(children)).build()
+      })
+      .build();
+    ecs.run();
+    let world = ecs.world();
+    let child = ecs.entities()[0];
+    let rigid_body = world.read_storage::<RigidBody>().get(child).unwrap().clone();
+    // Only the swing survives; the parent has no `RigidBody` so it stays put.
+    assert!((rigid_body.velocity - Vec3F::new(1f32, 0f32, 0f32)).magnitude() < 1e-4f32);
+    let parent = ecs.entities()[1];
+    assert_eq!(
+      world
+        .read_storage::<TransformComponent>()
+        .get(parent)
+        .unwrap()
+        .translation,
+      Vec3F::zero()
+    );
+  }
+
+  #[test]
+  fn joint_to_a_deleted_entity_is_skipped() {
+    let velocity = Vec3F::new(1f32, -3f32, 0f32);
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(JointSystem)
+      .with_resource(FixedTimestep::new(60f32))
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<Joint>()
+      .register_component::<EntityTree>()
+      .with_entity(|e| e.with(TransformComponent::default()).build())
+      .with_entity(|e| {
+        let transform = TransformComponent {
+          translation: Vec3F::new(0f32, -2f32, 0f32),
+          ..Default::default()
+        };
+        let rigid_body = RigidBody::new(velocity, Vec3F::zero(), Vec3F::zero(), Vec3F::zero());
+        e.with(transform).with(rigid_body).build()
+      })
+      .build();
+    let (connected, body) = (ecs.entities()[0], ecs.entities()[1]);
+    // The deletion only lands at the end of a run.
+    ecs.world().entities().delete(connected).unwrap();
+    ecs.run();
+    let joint = Joint::distance(1f32, Vec3F::zero(), Vec3F::zero()).connected_to(connected);
+    ecs.world().write_storage::<Joint>().insert(body, joint).unwrap();
+    ecs.run();
+    let world = ecs.world();
+    let rigid_body = world.read_storage::<RigidBody>().get(body).unwrap().clone();
+    assert_eq!(rigid_body.velocity, velocity);
+    let transform = world.read_storage::<TransformComponent>().get(body).unwrap().clone();
+    assert_eq!(transform.translation, Vec3F::new(0f32, -2f32, 0f32));
+  }
+}
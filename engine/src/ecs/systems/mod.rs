@@ -1,7 +1,33 @@
 pub mod render_system;
+pub mod collision_response_system;
+pub mod collision_system;
+pub mod force_field_system;
+pub mod interpolation_system;
+pub mod joint_system;
 pub mod motion_system;
+pub mod n_body_system;
 pub mod particle_system;
+pub mod physics_query_system;
+pub mod proper_time_system;
+pub mod relativistic_controller;
+pub mod relativity_system;
+pub mod retarded_position_system;
+pub mod trigger_system;
+pub mod worldline_system;
 
+pub use self::collision_response_system::*;
+pub use self::collision_system::*;
+pub use self::force_field_system::*;
+pub use self::interpolation_system::*;
+pub use self::joint_system::*;
 pub use self::motion_system::*;
+pub use self::n_body_system::*;
 pub use self::particle_system::*;
+pub use self::physics_query_system::*;
+pub use self::proper_time_system::*;
+pub use self::relativistic_controller::*;
+pub use self::relativity_system::*;
+pub use self::retarded_position_system::*;
 pub use self::render_system::*;
+pub use self::trigger_system::*;
+pub use self::worldline_system::*;
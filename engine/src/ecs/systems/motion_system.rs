@@ -1,78 +1,348 @@
-use specs::{Join, Read, ReadStorage, System, WriteStorage};
+use specs::{Entities, Entity, Join, Read, ReadStorage, System, Write, WriteStorage};
 
-use crate::physics::{CanCollide, Collision, CollisionQueue, CollisionSummary};
+use crate::physics::{
+  angular_displacement, rebuild_solid_broadphase, sweep_sphere, Broadphase, CanCollide, ColliderLookup,
+  ColliderStorages, ForceFields, Inertia, Mass, NBody, PhysicsMaterial, Trigger,
+};
 use crate::utils::*;
 use cgmath::prelude::*;
 
-use crate::physics::{AxisAlignedCubeCollision, Drag, Gravity, RigidBody, TransformComponent};
-
-const MAX_ACCELERATION: f32 = 6f32;
-
-const DRAG: f32 = MAX_ACCELERATION / 36f32 / 36f32;
-const GRAVITY: f32 = 14f32;
+use crate::physics::{Drag, Gravity, RigidBody, TransformComponent};
+use crate::relativity::{integrate_proper_velocity, Relativistic, SpeedOfLight};
 
 const ACCELERATION_MIN_MAGNITUDE: f32 = 0.00000001;
 
+// Integrates rigid bodies over one fixed physics tick. Accelerations set on a `RigidBody` are left
+// in place, so they apply to every tick of the frame; the game loop clears them once the frame's
+// ticks have run. `NBody` entities are left to `NBodySystem`. The `Broadphase` is rebuilt before
+// `CanCollide` bodies are swept through it, so it never lags behind the colliders.
 pub struct MotionSystem;
 
 impl<'a> System<'a> for MotionSystem {
   type SystemData = (
+    Entities<'a>,
     WriteStorage<'a, TransformComponent>,
     WriteStorage<'a, RigidBody>,
     ReadStorage<'a, CanCollide>,
     ReadStorage<'a, Gravity>,
     ReadStorage<'a, Drag>,
-    ReadStorage<'a, AxisAlignedCubeCollision>,
-    Read<'a, Timestep>,
+    ReadStorage<'a, Relativistic>,
+    ReadStorage<'a, Mass>,
+    ReadStorage<'a, Inertia>,
+    ColliderStorages<'a>,
+    ReadStorage<'a, PhysicsMaterial>,
+    ReadStorage<'a, NBody>,
+    ReadStorage<'a, Trigger>,
+    Write<'a, Broadphase>,
+    Read<'a, ForceFields>,
+    Read<'a, SpeedOfLight>,
+    Read<'a, FixedTimestep>,
   );
 
   fn run(
     &mut self,
-    (mut transform_s, mut rigid_storage, collidable_storage, gravity_storage, drag_storage, _colliders_storage, dt): Self::SystemData,
+    (
+      entities,
+      mut transform_s,
+      mut rigid_storage,
+      collidable_storage,
+      gravity_storage,
+      drag_storage,
+      relativistic_storage,
+      mass_storage,
+      inertia_storage,
+      colliders_storage,
+      material_storage,
+      n_body_storage,
+      trigger_storage,
+      mut broadphase,
+      force_fields,
+      speed_of_light,
+      dt,
+    ): Self::SystemData,
   ) {
-    for (transform, _collidable, rigid_body, gravity, drag) in (
+    rebuild_solid_broadphase(&mut broadphase, &entities, &colliders_storage, &trigger_storage);
+    let default_material = PhysicsMaterial::default();
+    let default_mass = Mass::default();
+    for (entity, transform, collidable, rigid_body, gravity, drag, relativistic, mass, inertia, material, _) in (
+      &entities,
       &mut transform_s,
       (&collidable_storage).maybe(),
       &mut rigid_storage,
       (&gravity_storage).maybe(),
       (&drag_storage).maybe(),
+      (&relativistic_storage).maybe(),
+      (&mass_storage).maybe(),
+      (&inertia_storage).maybe(),
+      (&material_storage).maybe(),
+      !&n_body_storage,
     )
       .join()
     {
-      self.compute_kinematics(rigid_body, gravity, drag, dt.dt_f32());
-      self.push_frame_update(rigid_body, transform, dt.dt_f32());
-      rigid_body.reset_acceleration();
+      let c = relativistic.map(|_| speed_of_light.get());
+      let mass = mass.unwrap_or(&default_mass);
+      let field_acceleration = self.field_acceleration(&force_fields, &transform.translation, rigid_body, (gravity, drag));
+      self.compute_kinematics(rigid_body, &field_acceleration, mass, c, dt.dt_f32());
+      self.compute_angular_kinematics(rigid_body, inertia, &transform.rotation, dt.dt_f32());
+      match collidable {
+        Some(collidable) => self.push_swept_frame_update(
+          entity,
+          rigid_body,
+          transform,
+          (collidable, material.unwrap_or(&default_material)),
+          (&broadphase, &colliders_storage),
+          dt.dt_f32(),
+        ),
+        None => self.push_frame_update(rigid_body, transform, dt.dt_f32()),
+      }
     }
   }
 }
 
 impl MotionSystem {
-  fn compute_kinematics(&self, rigid_body: &mut RigidBody, gravity: Option<&Gravity>, drag: Option<&Drag>, dt: f32) {
-    if let Some(_g) = gravity {
-      rigid_body.acceleration -= Vec3F::unit_y() * GRAVITY;
+  // `speed_of_light` is only provided for entities marked `Relativistic`.
+  fn compute_kinematics(
+    &self,
+    rigid_body: &mut RigidBody,
+    field_acceleration: &Vec3F,
+    mass: &Mass,
+    speed_of_light: Option<f32>,
+    dt: f32,
+  ) {
+    let acceleration = rigid_body.acceleration + rigid_body.force * mass.inverse() + field_acceleration;
+    self.integrate_rigid_body(rigid_body, &acceleration, speed_of_light, dt);
+  }
+
+  // Fields only act on bodies marked with `Gravity`, and drag only on bodies with a `Drag`.
+  fn field_acceleration(
+    &self,
+    force_fields: &ForceFields,
+    position: &Vec3F,
+    rigid_body: &RigidBody,
+    (gravity, drag): (Option<&Gravity>, Option<&Drag>),
+  ) -> Vec3F {
+    let mut acceleration = Vec3F::zero();
+    if gravity.is_some() {
+      acceleration += force_fields.acceleration_at(position, &rigid_body.velocity);
     }
-    if let Some(_d) = drag {
-      if rigid_body.velocity.magnitude2() > 0.1 {
-        rigid_body.acceleration -= DRAG * rigid_body.velocity.magnitude2() * rigid_body.velocity.normalize();
-      }
+    if let Some(drag) = drag {
+      acceleration += force_fields.drag_acceleration(drag.coefficient, &rigid_body.velocity);
     }
-    self.integrate_rigid_body(rigid_body, dt);
+    acceleration
   }
 
-  fn integrate_rigid_body(&self, rigid_body: &mut RigidBody, dt: f32) {
-    // Linear first
-    if rigid_body.acceleration.magnitude2() > ACCELERATION_MIN_MAGNITUDE {
-      rigid_body.velocity += rigid_body.acceleration * dt;
+  fn integrate_rigid_body(
+    &self,
+    rigid_body: &mut RigidBody,
+    acceleration: &Vec3F,
+    speed_of_light: Option<f32>,
+    dt: f32,
+  ) {
+    if acceleration.magnitude2() > ACCELERATION_MIN_MAGNITUDE {
+      match speed_of_light {
+        Some(c) => {
+          rigid_body.velocity = integrate_proper_velocity(&rigid_body.velocity, acceleration, dt, c);
+        }
+        None => rigid_body.velocity += acceleration * dt,
+      }
     }
+  }
 
-    // Angular
-    if rigid_body.angular_acceleration.magnitude2() > ACCELERATION_MIN_MAGNITUDE {
-      rigid_body.angular_velocity += rigid_body.angular_acceleration * dt;
+  // Torque goes through the inertia tensor at the body's current orientation; without an
+  // `Inertia` it is taken as an angular acceleration directly.
+  fn compute_angular_kinematics(
+    &self,
+    rigid_body: &mut RigidBody,
+    inertia: Option<&Inertia>,
+    rotation: &QuatF,
+    dt: f32,
+  ) {
+    let torque_acceleration = match inertia {
+      Some(inertia) => inertia.angular_acceleration(rotation, &rigid_body.angular_velocity, &rigid_body.torque),
+      None => rigid_body.torque,
+    };
+    let angular_acceleration = rigid_body.angular_acceleration + torque_acceleration;
+    if angular_acceleration.magnitude2() > ACCELERATION_MIN_MAGNITUDE {
+      rigid_body.angular_velocity += angular_acceleration * dt;
     }
   }
 
   fn push_frame_update(&self, rigid_body: &RigidBody, transform: &mut TransformComponent, dt: f32) {
     transform.push_translation(rigid_body.velocity * dt);
-    transform.push_rotation(&(rigid_body.angular_velocity * dt));
+    transform.push_rotation(&angular_displacement(&rigid_body.angular_velocity, dt));
+  }
+
+  // Like `push_frame_update`, but the motion stops at (and bounces off) any collider in the way.
+  fn push_swept_frame_update(
+    &self,
+    entity: Entity,
+    rigid_body: &mut RigidBody,
+    transform: &mut TransformComponent,
+    (collidable, material): (&CanCollide, &PhysicsMaterial),
+    (broadphase, colliders): (&Broadphase, &ColliderStorages),
+    dt: f32,
+  ) {
+    let result = sweep_sphere(
+      broadphase,
+      |e, sphere, velocity| colliders.sphere_collision(e, sphere, velocity),
+      Some(entity),
+      (&transform.translation, collidable.radius),
+      &rigid_body.velocity,
+      dt,
+      material,
+    );
+    transform.translation = result.position;
+    rigid_body.velocity = result.velocity;
+    transform.push_rotation(&angular_displacement(&rigid_body.angular_velocity, dt));
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::physics::{AxisAlignedCubeCollision, ForceField};
+  use crate::testing::TestingEcsBuilder;
+  use specs::prelude::*;
+
+  fn spin_up(inertia: Option<Inertia>, torque: Vec3F) -> (RigidBody, TransformComponent) {
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(MotionSystem)
+      .with_resource(FixedTimestep::new(10f32))
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<Inertia>()
+      .with_entity(|e| {
+        let mut rigid_body = RigidBody::default();
+        rigid_body.apply_torque(torque);
+        let e = e.with(rigid_body).with(TransformComponent::default());
+        match inertia {
+          Some(inertia) => e.with(inertia).build(),
+          None => e.build(),
+        }
+      })
+      .build();
+    ecs.run();
+    let world = ecs.world();
+    let rigid_body = world.read_storage::<RigidBody>().join().next().unwrap().clone();
+    let transform = world
+      .read_storage::<TransformComponent>()
+      .join()
+      .next()
+      .unwrap()
+      .clone();
+    (rigid_body, transform)
+  }
+
+  #[test]
+  fn torque_spins_through_the_inertia_tensor() {
+    let inertia = Inertia::solid_box(12f32, &Vec3F::new(1f32, 2f32, 3f32));
+    let (rigid_body, transform) = spin_up(Some(inertia), Vec3F::new(0f32, 0f32, 50f32));
+    // I_zz = 5, so 50 units of torque for 0.1s gives 1 rad/s about z.
+    assert!((rigid_body.angular_velocity - Vec3F::new(0f32, 0f32, 1f32)).magnitude() < 1e-4f32);
+    let turned = transform.rotation.rotate_vector(Vec3F::unit_x());
+    assert!((turned - Vec3F::new(0.1f32.cos(), 0.1f32.sin(), 0f32)).magnitude() < 1e-4f32);
+  }
+
+  #[test]
+  fn off_centre_force_produces_torque() {
+    let mut rigid_body = RigidBody::default();
+    rigid_body.apply_force_at_point(Vec3F::unit_y(), &Vec3F::new(2f32, 0f32, 0f32), &Vec3F::zero());
+    assert_eq!(rigid_body.force, Vec3F::unit_y());
+    assert_eq!(rigid_body.torque, Vec3F::new(0f32, 0f32, 2f32));
+    rigid_body.reset_acceleration();
+    assert_eq!(rigid_body.torque, Vec3F::zero());
+
+    let (spun, _) = spin_up(None, Vec3F::new(0f32, 3f32, 0f32));
+    assert!((spun.angular_velocity - Vec3F::new(0f32, 0.3f32, 0f32)).magnitude() < 1e-4f32);
+  }
+
+  #[test]
+  fn point_mass_field_holds_a_circular_orbit() {
+    let mut force_fields = ForceFields::empty();
+    force_fields.insert("sun", ForceField::point_mass(Vec3F::zero(), 100f32));
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(MotionSystem)
+      .with_resource(FixedTimestep::new(60f32))
+      .with_resource(force_fields)
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<Gravity>()
+      .with_entity(|e| {
+        let transform = TransformComponent {
+          translation: Vec3F::new(10f32, 0f32, 0f32),
+          ..Default::default()
+        };
+        // Circular speed sqrt(GM / r).
+        let velocity = Vec3F::new(0f32, 0f32, 10f32.sqrt());
+        let rigid_body = RigidBody::new(velocity, Vec3F::zero(), Vec3F::zero(), Vec3F::zero());
+        e.with(rigid_body).with(transform).with(Gravity).build()
+      })
+      .build();
+    // Roughly a quarter of the orbit.
+    for _ in 0..300 {
+      ecs.run();
+    }
+    let world = ecs.world();
+    let transform = world
+      .read_storage::<TransformComponent>()
+      .join()
+      .next()
+      .unwrap()
+      .clone();
+    assert!((transform.translation.magnitude() - 10f32).abs() < 0.1f32);
+    assert!(transform.translation.z > 5f32);
+  }
+
+  #[test]
+  fn forces_do_not_move_a_massless_body() {
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(MotionSystem)
+      .with_resource(FixedTimestep::new(10f32))
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<Mass>()
+      .with_entity(|e| {
+        let mut rigid_body = RigidBody::default();
+        rigid_body.apply_force(Vec3F::new(5f32, 0f32, 0f32));
+        e.with(rigid_body).with(TransformComponent::default()).with(Mass(0f32)).build()
+      })
+      .build();
+    ecs.run();
+    let world = ecs.world();
+    let transform = world.read_storage::<TransformComponent>().join().next().unwrap().clone();
+    assert_eq!(transform.translation, Vec3F::zero());
+  }
+
+  #[test]
+  fn sweeps_against_colliders_without_a_collision_system() {
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(MotionSystem)
+      .with_resource(FixedTimestep::new(1f32))
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<CanCollide>()
+      .register_component::<AxisAlignedCubeCollision>()
+      .with_entity(|e| {
+        let velocity = Vec3F::new(20f32, 0f32, 0f32);
+        let rigid_body = RigidBody::new(velocity, Vec3F::zero(), Vec3F::zero(), Vec3F::zero());
+        e.with(rigid_body)
+          .with(TransformComponent::default())
+          .with(CanCollide { radius: 0.5f32 })
+          .build()
+      })
+      .with_entity(|e| {
+        let transform = TransformComponent {
+          translation: Vec3F::new(5f32, 0f32, 0f32),
+          ..Default::default()
+        };
+        e.with(AxisAlignedCubeCollision::from_transform(&transform)).build()
+      })
+      .build();
+    ecs.run();
+    let world = ecs.world();
+    let sphere = ecs.entities()[0];
+    let translation = world.read_storage::<TransformComponent>().get(sphere).unwrap().translation;
+    // Stopped at the cube's face instead of tunnelling through it.
+    assert!(translation.x < 4f32, "{:?}", translation);
   }
 }
@@ -0,0 +1,146 @@
+use specs::{Join, Read, ReadStorage, System, Write, WriteStorage};
+
+use crate::debug::DebugMetrics;
+use crate::physics::{
+  angular_displacement, GravitatingBody, Mass, NBody, NBodySettings, RigidBody, TransformComponent,
+};
+use crate::utils::*;
+
+// Integrates every `NBody` under the gravity of all the others with a leapfrog step per physics
+// tick, then reports the total energy to `DebugMetrics` so drift can be watched. Accelerations
+// and forces set on the `RigidBody` still apply on top of gravity.
+#[derive(Default)]
+pub struct NBodySystem {
+  // What the energy baseline was measured with; the baseline is thrown out when this changes.
+  measured: Option<(usize, NBodySettings)>,
+}
+
+impl<'a> System<'a> for NBodySystem {
+  type SystemData = (
+    WriteStorage<'a, TransformComponent>,
+    WriteStorage<'a, RigidBody>,
+    ReadStorage<'a, Mass>,
+    ReadStorage<'a, NBody>,
+    Read<'a, NBodySettings>,
+    Read<'a, FixedTimestep>,
+    Write<'a, DebugMetrics>,
+  );
+
+  fn run(
+    &mut self,
+    (mut transform_s, mut rigid_storage, mass_storage, n_body_storage, settings, dt, mut metrics): Self::SystemData,
+  ) {
+    let default_mass = Mass::default();
+    let mut members: Vec<_> = (
+      &mut transform_s,
+      &mut rigid_storage,
+      (&mass_storage).maybe(),
+      &n_body_storage,
+    )
+      .join()
+      .map(|(transform, rigid_body, mass, _)| (transform, rigid_body, mass.unwrap_or(&default_mass)))
+      .collect();
+    if members.is_empty() {
+      if self.measured.take().is_some() {
+        metrics.energy.reset();
+      }
+      return;
+    }
+
+    let mut bodies: Vec<GravitatingBody> = members
+      .iter()
+      .map(|(transform, _, mass)| GravitatingBody {
+        position: transform.translation,
+        mass: mass.0,
+      })
+      .collect();
+    let mut velocities: Vec<Vec3F> = members.iter().map(|(_, rigid_body, _)| rigid_body.velocity).collect();
+    let external: Vec<Vec3F> = members
+      .iter()
+      .map(|(_, rigid_body, mass)| rigid_body.acceleration + rigid_body.force * mass.inverse())
+      .collect();
+    settings.leapfrog_step(&mut bodies, &mut velocities, &external, dt.dt_f32());
+
+    for ((transform, rigid_body, _), (body, velocity)) in members.iter_mut().zip(bodies.iter().zip(velocities.iter())) {
+      transform.translation = body.position;
+      rigid_body.velocity = *velocity;
+      transform.push_rotation(&angular_displacement(&rigid_body.angular_velocity, dt.dt_f32()));
+    }
+
+    let measured = Some((bodies.len(), *settings));
+    if self.measured != measured {
+      self.measured = measured;
+      metrics.energy.reset();
+    }
+    metrics.energy.record(settings.total_energy(&bodies, &velocities));
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::physics::NBodyMode;
+  use crate::testing::TestingEcsBuilder;
+  use cgmath::prelude::*;
+  use specs::prelude::*;
+
+  #[test]
+  fn bodies_fall_towards_each_other_and_report_energy() {
+    let settings = NBodySettings {
+      mode: NBodyMode::Exact,
+      gravitational_constant: 1f32,
+      softening: 0f32,
+    };
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(NBodySystem::default())
+      .with_resource(FixedTimestep::new(60f32))
+      .with_resource(settings)
+      .register_component::<TransformComponent>()
+      .register_component::<RigidBody>()
+      .register_component::<Mass>()
+      .register_component::<NBody>()
+      .with_entity(|e| {
+        let transform = TransformComponent {
+          translation: Vec3F::new(-2f32, 0f32, 0f32),
+          ..Default::default()
+        };
+        e.with(transform)
+          .with(RigidBody::default())
+          .with(Mass(1f32))
+          .with(NBody)
+          .build()
+      })
+      .with_entity(|e| {
+        let transform = TransformComponent {
+          translation: Vec3F::new(2f32, 0f32, 0f32),
+          ..Default::default()
+        };
+        e.with(transform)
+          .with(RigidBody::default())
+          .with(Mass(3f32))
+          .with(NBody)
+          .build()
+      })
+      .build();
+    for _ in 0..10 {
+      ecs.run();
+    }
+    let world = ecs.world();
+    let transforms = world.read_storage::<TransformComponent>();
+    let masses = world.read_storage::<Mass>();
+    let (moment, separation) =
+      (&transforms, &masses)
+        .join()
+        .fold((Vec3F::zero(), 0f32), |(moment, separation), (transform, mass)| {
+          (
+            moment + transform.translation * mass.0,
+            separation + transform.translation.x.abs(),
+          )
+        });
+    // Both move inwards while the centre of mass, at (1, 0, 0), stays put.
+    assert!(separation < 4f32);
+    assert!((moment / 4f32 - Vec3F::new(1f32, 0f32, 0f32)).magnitude() < 1e-4f32);
+    let metrics = world.read_resource::<DebugMetrics>();
+    assert!(metrics.energy.drift().unwrap().abs() < 1e-4f64);
+  }
+}
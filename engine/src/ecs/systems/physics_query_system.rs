@@ -0,0 +1,94 @@
+use specs::prelude::*;
+
+use crate::physics::{query_shapes, ColliderStorages, PhysicsQueries, Trigger};
+
+// Copies every solid collider into the `PhysicsQueries` resource. It runs last in each physics tick, so
+// queries made during a frame see the colliders as the most recent tick left them.
+pub struct PhysicsQuerySystem;
+
+impl<'a> System<'a> for PhysicsQuerySystem {
+  type SystemData = (
+    Entities<'a>,
+    ColliderStorages<'a>,
+    ReadStorage<'a, Trigger>,
+    Write<'a, PhysicsQueries>,
+  );
+
+  fn run(&mut self, (entities, colliders, triggers, mut queries): Self::SystemData) {
+    queries.rebuild(query_shapes(&entities, &colliders, &triggers));
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::physics::{
+    AxisAlignedCubeCollision, CapsuleCollision, MeshCollision, OrientedBoxCollision, SphereCollision,
+  };
+  use crate::testing::TestingEcsBuilder;
+  use crate::utils::Vec3F;
+  use cgmath::prelude::*;
+
+  #[test]
+  fn queries_see_colliders_of_every_type() {
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(PhysicsQuerySystem)
+      .register_component::<AxisAlignedCubeCollision>()
+      .register_component::<OrientedBoxCollision>()
+      .register_component::<SphereCollision>()
+      .register_component::<CapsuleCollision>()
+      .register_component::<MeshCollision>()
+      .with_entity(|e| {
+        e.with(SphereCollision::new(Vec3F::new(0f32, 0f32, -10f32), 1f32))
+          .build()
+      })
+      .with_entity(|e| {
+        e.with(CapsuleCollision::new(
+          Vec3F::new(-1f32, 0f32, 10f32),
+          Vec3F::new(1f32, 0f32, 10f32),
+          1f32,
+        ))
+        .build()
+      })
+      .build();
+    ecs.run();
+    let world = ecs.world();
+    let queries = world.read_resource::<PhysicsQueries>();
+    assert_eq!(queries.count(), 2);
+    let forward = queries.raycast(&Vec3F::zero(), &-Vec3F::unit_z(), 50f32).unwrap();
+    assert!((forward.distance - 9f32).abs() < 1e-4f32);
+    let behind = queries.raycast(&Vec3F::zero(), &Vec3F::unit_z(), 50f32).unwrap();
+    assert!((behind.distance - 9f32).abs() < 1e-4f32);
+    assert_ne!(forward.entity, behind.entity);
+    assert!((behind.normal + Vec3F::unit_z()).magnitude() < 1e-4f32);
+  }
+
+  #[test]
+  fn queries_pass_through_triggers() {
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(PhysicsQuerySystem)
+      .register_component::<AxisAlignedCubeCollision>()
+      .register_component::<OrientedBoxCollision>()
+      .register_component::<SphereCollision>()
+      .register_component::<CapsuleCollision>()
+      .register_component::<MeshCollision>()
+      .register_component::<Trigger>()
+      .with_entity(|e| {
+        e.with(SphereCollision::new(Vec3F::new(0f32, 0f32, -5f32), 1f32))
+          .with(Trigger)
+          .build()
+      })
+      .with_entity(|e| {
+        e.with(SphereCollision::new(Vec3F::new(0f32, 0f32, -10f32), 1f32))
+          .build()
+      })
+      .build();
+    ecs.run();
+    let world = ecs.world();
+    let queries = world.read_resource::<PhysicsQueries>();
+    assert_eq!(queries.count(), 1);
+    let hit = queries.raycast(&Vec3F::zero(), &-Vec3F::unit_z(), 50f32).unwrap();
+    assert!((hit.distance - 9f32).abs() < 1e-4f32);
+    assert!(queries.overlap_sphere(&Vec3F::new(0f32, 0f32, -5f32), 1f32).is_empty());
+  }
+}
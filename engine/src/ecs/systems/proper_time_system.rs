@@ -0,0 +1,64 @@
+use specs::prelude::*;
+
+use crate::ecs::components::Player;
+use crate::ecs::{MonoBehavior, SystemUtilities, WorldProxy};
+use crate::gui::{widgets::*, ControlPanelBuilder, SystemDebugger};
+use crate::physics::RigidBody;
+use crate::relativity::{velocity_lorentz_factor, ProperClock, RelativisticTime, SpeedOfLight};
+use crate::utils::{Timestep, Vec3F};
+
+// Advances every `ProperClock` and keeps `RelativisticTime` in sync with the player's frame.
+#[derive(Default)]
+pub struct ProperTimeSystem;
+
+impl<'a> MonoBehavior<'a> for ProperTimeSystem {
+  type SystemData = (
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, RigidBody>,
+    WriteStorage<'a, ProperClock>,
+    Read<'a, SpeedOfLight>,
+    Read<'a, Timestep>,
+    Write<'a, RelativisticTime>,
+  );
+
+  fn run(
+    &mut self,
+    api: SystemUtilities<'a>,
+    (player_s, rigid_body_s, mut clock_s, speed_of_light, timestep, mut time): Self::SystemData,
+  ) {
+    let dt = timestep.dt_f32();
+    let c = speed_of_light.get();
+    let at_rest = Vec3F::new(0f32, 0f32, 0f32);
+    for (clock, rigid_body) in (&mut clock_s, (&rigid_body_s).maybe()).join() {
+      clock.tick(dt, rigid_body.map_or(&at_rest, |rb| &rb.velocity), c);
+    }
+
+    let mut player_dt = dt;
+    for (_player, rigid_body) in (&player_s, &rigid_body_s).join() {
+      player_dt = dt / velocity_lorentz_factor(&rigid_body.velocity, c);
+    }
+    time.world_dt = dt;
+    time.world_time += dt as f64;
+    time.player_dt = player_dt;
+    time.player_time += player_dt as f64;
+
+    let mut panel = self.get_write_panel(&api);
+    panel.set_str("WorldTime", format!("{:.3} s", time.world_time));
+    panel.set_str("PlayerTime", format!("{:.3} s", time.player_time));
+    panel.set_str("TwinGap", format!("{:.6} s", time.twin_gap()));
+  }
+
+  fn setup(&mut self, world: WorldProxy) {
+    self.register_debugger(&world);
+  }
+}
+
+impl<'a> SystemDebugger<'a> for ProperTimeSystem {
+  fn create_panel(&self) -> ControlPanelBuilder {
+    ControlPanelBuilder::default()
+      .with_title("Proper Time")
+      .push_line("WorldTime", LabeledText::new("0.000 s", "World Time"))
+      .push_line("PlayerTime", LabeledText::new("0.000 s", "Player Time"))
+      .push_line("TwinGap", LabeledText::new("0.000000 s", "Twin Paradox Gap"))
+  }
+}
@@ -0,0 +1,206 @@
+use cgmath::prelude::*;
+use specs::prelude::*;
+
+use crate::ecs::components::{Camera, EventReceiver, Player};
+use crate::ecs::{MonoBehavior, SystemUtilities, WorldProxy};
+use crate::events::{Event, EventChannel, EventPayload, KeyCode, StatelessEventChannel, WindowEvent};
+use crate::gui::{widgets::*, ControlPanelBuilder, SystemDebugger};
+use crate::physics::{CanCollide, RigidBody, TransformComponent};
+use crate::relativity::{rapidity, LorentzFrame, ProperClock, Relativistic, SpeedOfLight};
+use crate::utils::{FixedTimestep, Vec3F};
+
+// Radius of the sphere the ship collides as.
+const SHIP_RADIUS: f32 = 0.5f32;
+
+#[derive(SystemData)]
+pub struct RelativisticControllerSystemData<'a> {
+  player: ReadStorage<'a, Player>,
+  camera: WriteStorage<'a, Camera>,
+  transform: ReadStorage<'a, TransformComponent>,
+  rigid_body: WriteStorage<'a, RigidBody>,
+  event_receiver: ReadStorage<'a, EventReceiver>,
+  event_channel: Write<'a, StatelessEventChannel<WindowEvent>>,
+  speed_of_light: Read<'a, SpeedOfLight>,
+  timestep: Read<'a, FixedTimestep>,
+}
+
+// Spaceship-style player controller. WASD/Space/Shift apply thrust as a proper acceleration
+// along the camera axes and `I` toggles inertial dampening, which brakes the ship whenever no
+// thrust is being applied.
+pub struct RelativisticController {
+  sensitivity_scalar: f32,
+  dampening: bool,
+}
+
+impl Default for RelativisticController {
+  fn default() -> Self {
+    Self {
+      sensitivity_scalar: 0.001f32,
+      dampening: true,
+    }
+  }
+}
+
+impl<'a> MonoBehavior<'a> for RelativisticController {
+  type SystemData = RelativisticControllerSystemData<'a>;
+
+  fn run(&mut self, api: SystemUtilities<'a>, mut s: Self::SystemData) {
+    let thrust = {
+      let panel = self.get_panel(&api);
+      self.sensitivity_scalar = panel.get_float("Mouse Sensitivity");
+      panel.get_float("Thrust")
+    };
+    let c = s.speed_of_light.get();
+    let dt = s.timestep.dt_f32();
+    for (_p, camera, events, transform, rigid_body) in (
+      &s.player,
+      &mut s.camera,
+      &s.event_receiver,
+      &s.transform,
+      &mut s.rigid_body,
+    )
+      .join()
+    {
+      // The camera rides along with wherever the MotionSystem moved the ship last frame.
+      camera.push_translation(transform.translation - camera.position());
+      let mut direction = Vec3F::zero();
+      s.event_channel.for_each(&events.0, |evt| match evt.code {
+        Event::KeyDown(KeyCode::W) => direction += camera.front(),
+        Event::KeyDown(KeyCode::A) => direction -= camera.right(),
+        Event::KeyDown(KeyCode::S) => direction -= camera.front(),
+        Event::KeyDown(KeyCode::D) => direction += camera.right(),
+        Event::KeyDown(KeyCode::LeftShift) => direction -= Vec3F::unit_y(),
+        Event::KeyDown(KeyCode::Space) => direction += Vec3F::unit_y(),
+        Event::KeyPressed(KeyCode::I) => self.dampening = !self.dampening,
+        Event::MouseMoved => {
+          if let Some(payload) = &evt.payload {
+            match payload {
+              EventPayload::MouseMove(vec) => {
+                let dx = -cgmath::Rad(-vec.x * self.sensitivity_scalar);
+                let dy = cgmath::Rad(vec.y * self.sensitivity_scalar);
+                let euler_angles = cgmath::Euler::new(dy, dx, cgmath::Rad(0f32));
+                camera.push_rotation(euler_angles);
+              }
+              _ => panic!("Received a payload of {:?} on MouseMoved event!", payload),
+            }
+          }
+        }
+        _ => panic!(
+          "Received an event that the relativistic controller does not listen for! {:?}",
+          evt
+        ),
+      });
+      let proper_acceleration = if direction.magnitude2() > 0f32 {
+        direction.normalize_to(thrust)
+      } else if self.dampening {
+        self.dampening_acceleration(rigid_body, thrust, dt)
+      } else {
+        Vec3F::zero()
+      };
+      rigid_body.acceleration += proper_acceleration;
+
+      let frame = LorentzFrame::from_velocity(&rigid_body.velocity, c);
+      let mut panel = self.get_write_panel(&api);
+      panel.set_str("Beta", format!("{:.4}", frame.beta));
+      panel.set_str("Gamma", format!("{:.4}", frame.gamma));
+      panel.set_str("Rapidity", format!("{:.4}", rapidity(&rigid_body.velocity, c)));
+      panel.set_str("Proper Acceleration", format!("{:.3}", proper_acceleration.magnitude()));
+      panel.set_str("Dampening", if self.dampening { "On" } else { "Off" }.to_string());
+      panel.set_str("Player Position", to_string!(camera.position()));
+    }
+  }
+
+  fn setup(&mut self, mut world: WorldProxy) {
+    Self::SystemData::setup(&mut world);
+    world.register::<Relativistic>();
+    world.register::<ProperClock>();
+    world.register::<CanCollide>();
+    self.register_debugger(&world);
+    let receiver = {
+      let mut listener = world.write_resource::<StatelessEventChannel<WindowEvent>>();
+      EventReceiver(listener.register_with_subs(&[
+        WindowEvent::new(Event::KeyDown(KeyCode::W)),
+        WindowEvent::new(Event::KeyDown(KeyCode::A)),
+        WindowEvent::new(Event::KeyDown(KeyCode::S)),
+        WindowEvent::new(Event::KeyDown(KeyCode::D)),
+        WindowEvent::new(Event::KeyDown(KeyCode::LeftShift)),
+        WindowEvent::new(Event::KeyDown(KeyCode::Space)),
+        WindowEvent::new(Event::KeyPressed(KeyCode::I)),
+        WindowEvent::new(Event::MouseMoved),
+      ]))
+    };
+    let camera = Camera::new(Vec3F::new(4f32, 4f32, 2f32), Vec3F::new(0f32, 0f32, 1f32));
+    let mut transform = TransformComponent::identity();
+    let guid = world.utilities().get_guid();
+    transform.push_translation(camera.position());
+    world
+      .create_entity()
+      .with(Player)
+      .with(camera)
+      .with(receiver)
+      .with(transform)
+      .with(RigidBody::new_stationary())
+      .with(CanCollide { radius: SHIP_RADIUS })
+      .with(Relativistic)
+      .with(ProperClock::new())
+      .with(guid)
+      .build();
+  }
+}
+
+impl RelativisticController {
+  // Thrust against the current velocity, never harder than the engines allow. Once a physics
+  // tick of braking would overshoot rest, the ship is stopped outright instead.
+  fn dampening_acceleration(&self, rigid_body: &mut RigidBody, thrust: f32, dt: f32) -> Vec3F {
+    let speed = rigid_body.velocity.magnitude();
+    if speed <= thrust * dt {
+      rigid_body.velocity = Vec3F::zero();
+      Vec3F::zero()
+    } else {
+      -rigid_body.velocity.normalize_to(thrust)
+    }
+  }
+}
+
+impl<'a> SystemDebugger<'a> for RelativisticController {
+  fn create_panel(&self) -> ControlPanelBuilder {
+    ControlPanelBuilder::default()
+      .with_title("Relativistic Controller")
+      .push_line(
+        "Player Position",
+        LabeledText::new("<0.0, 0.0, 0.0>", "Player Position"),
+      )
+      .push_line("Beta", LabeledText::new("0.0000", "Beta (v/c)"))
+      .push_line("Gamma", LabeledText::new("1.0000", "Gamma"))
+      .push_line("Rapidity", LabeledText::new("0.0000", "Rapidity"))
+      .push_line("Proper Acceleration", LabeledText::new("0.000", "Proper Acceleration"))
+      .push_line("Dampening", LabeledText::new("On", "Inertial Dampening (I)"))
+      .push_line("Thrust", InputFloat::new_with_limits("Thrust", 5f32, 0f32, 50f32))
+      .push_line(
+        "Mouse Sensitivity",
+        InputFloat::new_with_limits("Mouse Sensitivity", 0.001, 0.001, 0.01),
+      )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::relativity::{integrate_proper_velocity, DEFAULT_SPEED_OF_LIGHT};
+
+  #[test]
+  fn dampening_brings_the_ship_to_rest() {
+    let controller = RelativisticController::default();
+    let initial = Vec3F::new(3f32, 0f32, -4f32);
+    let mut rigid_body = RigidBody::new_stationary();
+    rigid_body.velocity = initial;
+    let (thrust, dt) = (50f32, FixedTimestep::default().dt_f32());
+    for _ in 0..100 {
+      let acceleration = controller.dampening_acceleration(&mut rigid_body, thrust, dt);
+      rigid_body.velocity = integrate_proper_velocity(&rigid_body.velocity, &acceleration, dt, DEFAULT_SPEED_OF_LIGHT);
+      // Braking never pushes the ship backwards.
+      assert!(rigid_body.velocity.dot(initial) >= 0f32);
+    }
+    assert_eq!(rigid_body.velocity, Vec3F::zero());
+  }
+}
@@ -0,0 +1,46 @@
+use specs::prelude::*;
+
+use crate::ecs::components::{Camera, Player};
+use crate::graphics::Uniform;
+use crate::physics::RigidBody;
+use crate::relativity::{LorentzFrame, RelativisticObserver, SpeedOfLight};
+use crate::renderer::Renderer;
+use crate::utils::Vec3F;
+
+// Computes the observer's Lorentz frame from the player's velocity and pushes it to
+// every shader that includes `shaders/lorentz_helper.glsl`. The same frame is published as the
+// `RelativisticObserver` resource so CPU-side queries agree with what is rendered.
+pub struct RelativitySystem;
+
+impl<'a> System<'a> for RelativitySystem {
+  type SystemData = (
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, Camera>,
+    ReadStorage<'a, RigidBody>,
+    Read<'a, SpeedOfLight>,
+    Write<'a, Renderer>,
+    Write<'a, RelativisticObserver>,
+  );
+
+  fn run(&mut self, (player_s, camera_s, rigid_body_s, speed_of_light, mut renderer, mut observer): Self::SystemData) {
+    let mut frame = LorentzFrame::at_rest();
+    let mut camera_pos = Vec3F::new(0f32, 0f32, 0f32);
+    for (_player, camera, rigid_body) in (&player_s, &camera_s, (&rigid_body_s).maybe()).join() {
+      camera_pos = camera.position();
+      if let Some(rigid_body) = rigid_body {
+        frame = LorentzFrame::from_velocity(&rigid_body.velocity, speed_of_light.get());
+      }
+    }
+    let lorentz_flag = renderer.config().relativity_mode();
+    let overlay = renderer.config().simultaneity_overlay;
+    renderer.submit_env_uniform("beta", Uniform::Float(frame.beta));
+    renderer.submit_env_uniform("gamma", Uniform::Float(frame.gamma));
+    renderer.submit_env_uniform("lorentzFlag", Uniform::Int(lorentz_flag));
+    renderer.submit_env_uniform("cameraPos", Uniform::Vec3(camera_pos));
+    renderer.submit_env_uniform("changeOfBasis", Uniform::Mat3(frame.change_of_basis));
+    renderer.submit_env_uniform("changeOfBasisInverse", Uniform::Mat3(frame.change_of_basis_inverse));
+    renderer.submit_env_uniform("speedOfLight", Uniform::Float(speed_of_light.get()));
+    renderer.submit_env_uniform("simultaneityOverlay", Uniform::Int(overlay as i32));
+    *observer = RelativisticObserver::new(frame, camera_pos, lorentz_flag);
+  }
+}
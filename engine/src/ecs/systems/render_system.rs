@@ -12,14 +12,16 @@ use crate::events::{
   Event, EventChannel, KeyCode, ReceiverId, StatelessEventChannel, WindowEvent, WindowEventDispatcher,
 };
 use crate::graphics::{
-  AssetLibrary, Assets, MaterialComponent, MeshComponent, Uniform, VertexArray, VertexArrayBuilder,
+  AssetLibrary, Assets, LightBlock, LightStorages, MaterialComponent, MeshComponent, ShadowCaster, ShadowReceiver,
+  Uniform, VertexArray, VertexArrayBuilder,
 };
 use crate::gui::{widgets::*, ControlPanel, ControlPanelBuilder, SystemDebugger};
-use crate::physics::TransformComponent;
+use crate::physics::{InterpolatedTransform, TransformComponent};
 use crate::platform::Window;
+use crate::relativity::RetardedTransform;
 use crate::renderer::render_pipeline::*;
-use crate::renderer::{DrawCall, RenderCommand, RenderQueue, Renderer};
-use crate::utils::{CompoundStopwatch, Counter, Mat4F, MutRef, RunningEnum, RunningState, StopwatchLike};
+use crate::renderer::{DrawCall, RenderCommand, RenderQueue, Renderer, ToneMapper};
+use crate::utils::{CompoundStopwatch, Counter, Mat4F, MutRef, RunningEnum, RunningState, StopwatchLike, Vec3F};
 
 pub struct StartFrameSystem {
   pub window: MutRef<Window>,
@@ -33,12 +35,13 @@ impl<'a> MonoBehavior<'a> for StartFrameSystem {
     Write<'a, WindowEventDispatcher>,
     Write<'a, RunningState>,
     ReadStorage<'a, Camera>,
+    LightStorages<'a>,
   );
 
   fn run(
     &mut self,
     api: SystemUtilities<'a>,
-    (mut renderer, mut events, mut window_events, mut running, camera_storage): Self::SystemData,
+    (mut renderer, mut events, mut window_events, mut running, camera_storage, lights): Self::SystemData,
   ) {
     let mut window = self.window.borrow_mut();
     window.poll_events();
@@ -72,6 +75,9 @@ impl<'a> MonoBehavior<'a> for StartFrameSystem {
     for camera in (&camera_storage).join() {
       renderer.start_scene(&camera);
     }
+    // Directional shadows follow the camera.
+    let focus = (&camera_storage).join().last().map_or(Vec3F::zero(), |camera| camera.position());
+    renderer.submit_lights(&LightBlock::gather(&lights, &focus));
     let panel = self.get_panel(&api);
     renderer.submit_env_uniform("ambient_strength", Uniform::Float(panel.get_float("ambient_strength")));
     renderer.submit_env_uniform("diffuse_strength", Uniform::Float(panel.get_float("diffuse_strength")));
@@ -80,7 +86,21 @@ impl<'a> MonoBehavior<'a> for StartFrameSystem {
       Uniform::Float(panel.get_float("specular_strength")),
     );
     renderer.submit_env_uniform("specular_power", Uniform::Float(panel.get_float("specular_power")));
-    renderer.submit_env_uniform("gamma", Uniform::Float(panel.get_float("gamma")));
+    let mut post_process = renderer.config().post_process.clone();
+    post_process.bloom.enabled = panel.get_bool("bloom");
+    post_process.bloom.threshold = panel.get_float("bloom_threshold");
+    post_process.bloom.intensity = panel.get_float("bloom_intensity");
+    post_process.bloom.blur_passes = panel.get_int("bloom_passes");
+    post_process.tone_map.enabled = panel.get_bool("tone_mapping");
+    post_process.tone_map.mapper = if panel.get_bool("aces") {
+      ToneMapper::ACES
+    } else {
+      ToneMapper::REINHARD
+    };
+    post_process.tone_map.exposure = panel.get_float("exposure");
+    post_process.gamma.enabled = panel.get_bool("gamma");
+    post_process.gamma.gamma = panel.get_float("gamma_correction");
+    renderer.submit_post_process(post_process);
   }
 
   fn setup(&mut self, world: WorldProxy) {
@@ -99,7 +119,18 @@ impl<'a> SystemDebugger<'a> for StartFrameSystem {
         "specular_power",
         InputFloat::new_with_limits("Specular Power", 32f32, 4f32, 64f32),
       )
-      .push_line("gamma", InputFloat::new_with_limits("Gamma", 1.8f32, 0.5f32, 3.0f32))
+      .push_line("bloom", Checkbox::new("Bloom", true))
+      .push_line(
+        "bloom_threshold",
+        InputFloat::new_with_limits("Bloom Threshold", 1f32, 0f32, 4f32),
+      )
+      .push_line("bloom_intensity", InputFloat::new("Bloom Intensity", 0.5))
+      .push_line("bloom_passes", InputInt::new_with_limits("Bloom Blur Passes", 5, 0, 10))
+      .push_line("tone_mapping", Checkbox::new("Tone Mapping", true))
+      .push_line("aces", Checkbox::new("ACES (else Reinhard)", true))
+      .push_line("exposure", InputFloat::new_with_limits("Exposure", 1f32, 0.1f32, 4f32))
+      .push_line("gamma", Checkbox::new("Gamma Correction", true))
+      .push_line("gamma_correction", InputFloat::new_with_limits("Gamma", 1.8f32, 0.5f32, 3.0f32))
   }
 }
 
@@ -165,7 +196,11 @@ pub struct RenderSystemData<'a> {
   entities: Entities<'a>,
   drawable_s: ReadStorage<'a, MeshComponent>,
   transform_s: ReadStorage<'a, TransformComponent>,
+  interpolated_s: ReadStorage<'a, InterpolatedTransform>,
+  retarded_s: ReadStorage<'a, RetardedTransform>,
   material_s: ReadStorage<'a, MaterialComponent>,
+  caster_s: ReadStorage<'a, ShadowCaster>,
+  receiver_s: ReadStorage<'a, ShadowReceiver>,
   renderer: Write<'a, Renderer>,
   render_queue: Write<'a, RenderQueue>,
   assets: Write<'a, AssetLibrary>,
@@ -231,7 +266,8 @@ impl RenderPipelineSystem {
     system_data.renderer.render_scene(
       system_data.render_queue.iter(),
       &system_data.material_s,
-      &system_data.transform_s,
+      (&system_data.caster_s, &system_data.receiver_s),
+      (&system_data.transform_s, &system_data.interpolated_s, &system_data.retarded_s),
       &mut system_data.assets,
       &system_data.debug_metrics,
     );
@@ -0,0 +1,43 @@
+use specs::prelude::*;
+
+use crate::physics::TransformComponent;
+use crate::relativity::{PositionHistory, RelativisticObserver, RelativisticTime, RetardedTransform, SpeedOfLight};
+
+// Records the worldline of every entity with a `PositionHistory` and, while light travel time
+// is enabled, solves for where the observer currently sees it.
+pub struct RetardedPositionSystem;
+
+impl<'a> System<'a> for RetardedPositionSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, TransformComponent>,
+    WriteStorage<'a, PositionHistory>,
+    WriteStorage<'a, RetardedTransform>,
+    Read<'a, RelativisticObserver>,
+    Read<'a, RelativisticTime>,
+    Read<'a, SpeedOfLight>,
+  );
+
+  fn run(
+    &mut self,
+    (entities, transform_s, mut history_s, mut retarded_s, observer, time, speed_of_light): Self::SystemData,
+  ) {
+    for (entity, transform, history) in (&entities, &transform_s, &mut history_s).join() {
+      history.record(time.world_time, transform.translation);
+      let retarded_pos = if observer.lorentz_flag >= 2 {
+        history.retarded_position(&observer.position, time.world_time, speed_of_light.get())
+      } else {
+        None
+      };
+      if let Some(translation) = retarded_pos {
+        let mut retarded = transform.clone();
+        retarded.translation = translation;
+        retarded_s
+          .insert(entity, RetardedTransform(retarded))
+          .expect("Could not insert RetardedTransform");
+      } else {
+        retarded_s.remove(entity);
+      }
+    }
+  }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use specs::prelude::*;
+
+use crate::ecs::Guid;
+use crate::events::EventChannel;
+use crate::physics::{
+  Broadphase, CanCollide, ColliderLookup, ColliderStorages, TransformComponent, Trigger, TriggerEvent,
+  TriggerEventChannel, TriggerPhase, TriggerTopic,
+};
+
+// Tracks which `CanCollide` bodies overlap which `Trigger` colliders from one frame to the next
+// and publishes the changes on the `TriggerEventChannel`, under `TriggerTopic::All` and
+// `TriggerTopic::Involving` for both participants. Events are keyed by `Guid`, so entities
+// without one are left out. Events stay readable until this system next runs.
+#[derive(Default)]
+pub struct TriggerSystem {
+  // Overlapping (trigger, body) pairs as of the last run, with their guids so that an exit can
+  // still be reported after an entity is deleted.
+  overlaps: HashMap<(Entity, Entity), (Guid, Guid)>,
+  triggers: Broadphase,
+}
+
+impl<'a> System<'a> for TriggerSystem {
+  type SystemData = (
+    Entities<'a>,
+    ReadStorage<'a, Trigger>,
+    ReadStorage<'a, Guid>,
+    ReadStorage<'a, TransformComponent>,
+    ReadStorage<'a, CanCollide>,
+    ColliderStorages<'a>,
+    Write<'a, TriggerEventChannel>,
+  );
+
+  fn run(&mut self, (entities, trigger_s, guid_s, transform_s, collide_s, colliders, mut channel): Self::SystemData) {
+    channel.clear_events();
+    self.triggers.rebuild(
+      colliders
+        .entries(&entities)
+        .into_iter()
+        .filter(|entry| trigger_s.contains(entry.entity) && guid_s.contains(entry.entity))
+        .collect(),
+    );
+
+    let mut overlaps = HashMap::new();
+    for (body, body_guid, transform, collide) in (&entities, &guid_s, &transform_s, &collide_s).join() {
+      let center = transform.translation;
+      for candidate in self.triggers.candidates(&center, collide.radius) {
+        if candidate.entity == body {
+          continue;
+        }
+        let distance = colliders.distance_to(candidate.entity, &center);
+        if let (Some(distance), Some(trigger_guid)) = (distance, guid_s.get(candidate.entity)) {
+          if distance <= collide.radius {
+            overlaps.insert((candidate.entity, body), (*trigger_guid, *body_guid));
+          }
+        }
+      }
+    }
+
+    for (pair, (trigger, other)) in overlaps.iter() {
+      let phase = if self.overlaps.contains_key(pair) {
+        TriggerPhase::Stay
+      } else {
+        TriggerPhase::Enter
+      };
+      publish(
+        &mut channel,
+        TriggerEvent {
+          phase,
+          trigger: *trigger,
+          other: *other,
+        },
+      );
+    }
+    for (pair, (trigger, other)) in self.overlaps.iter() {
+      if !overlaps.contains_key(pair) {
+        let event = TriggerEvent {
+          phase: TriggerPhase::Exit,
+          trigger: *trigger,
+          other: *other,
+        };
+        publish(&mut channel, event);
+      }
+    }
+    self.overlaps = overlaps;
+  }
+}
+
+fn publish(channel: &mut TriggerEventChannel, event: TriggerEvent) {
+  channel.publish((TriggerTopic::All, event));
+  channel.publish((TriggerTopic::Involving(event.trigger), event));
+  channel.publish((TriggerTopic::Involving(event.other), event));
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::events::ReceiverId;
+  use crate::physics::{
+    AxisAlignedCubeCollision, CapsuleCollision, MeshCollision, OrientedBoxCollision, SphereCollision,
+  };
+  use crate::testing::{TestingEcs, TestingEcsBuilder};
+  use crate::utils::Vec3F;
+
+  fn phases(ecs: &TestingEcs, receiver: &ReceiverId) -> Vec<TriggerPhase> {
+    let channel = ecs.world().read_resource::<TriggerEventChannel>();
+    channel.read(receiver).iter().map(|(_, event)| event.phase).collect()
+  }
+
+  // Moves the body to `x` and runs a frame.
+  fn step_to(ecs: &mut TestingEcs, x: f32) {
+    let body = ecs.entities()[1];
+    {
+      let mut transforms = ecs.world().write_storage::<TransformComponent>();
+      transforms.get_mut(body).unwrap().translation = Vec3F::new(x, 0f32, 0f32);
+    }
+    ecs.run();
+  }
+
+  #[test]
+  fn reports_enter_stay_and_exit() {
+    let zone = Guid::new();
+    let mut channel = TriggerEventChannel::default();
+    let receiver: ReceiverId = channel.register_with_subs(&[TriggerTopic::Involving(zone)]);
+    let mut ecs = TestingEcsBuilder::new()
+      .with_system(TriggerSystem::default())
+      .with_resource(channel)
+      .register_component::<Guid>()
+      .register_component::<Trigger>()
+      .register_component::<TransformComponent>()
+      .register_component::<CanCollide>()
+      .register_component::<AxisAlignedCubeCollision>()
+      .register_component::<OrientedBoxCollision>()
+      .register_component::<SphereCollision>()
+      .register_component::<CapsuleCollision>()
+      .register_component::<MeshCollision>()
+      .with_entity(|e| {
+        e.with(SphereCollision::new(Vec3F::new(5f32, 0f32, 0f32), 2f32))
+          .with(Trigger)
+          .with(zone)
+          .build()
+      })
+      .with_entity(|e| {
+        e.with(TransformComponent::default())
+          .with(CanCollide { radius: 0.5f32 })
+          .with(Guid::new())
+          .build()
+      })
+      .build();
+
+    ecs.run();
+    assert!(phases(&ecs, &receiver).is_empty());
+    step_to(&mut ecs, 3f32);
+    assert_eq!(phases(&ecs, &receiver), vec![TriggerPhase::Enter]);
+    step_to(&mut ecs, 5f32);
+    assert_eq!(phases(&ecs, &receiver), vec![TriggerPhase::Stay]);
+    step_to(&mut ecs, 8f32);
+    assert_eq!(phases(&ecs, &receiver), vec![TriggerPhase::Exit]);
+    step_to(&mut ecs, 9f32);
+    assert!(phases(&ecs, &receiver).is_empty());
+  }
+}
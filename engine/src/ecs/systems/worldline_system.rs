@@ -0,0 +1,150 @@
+use cgmath::prelude::*;
+use specs::prelude::*;
+
+use crate::ecs::components::Player;
+use crate::ecs::{MonoBehavior, SystemUtilities, WorldProxy};
+use crate::gui::{widgets::*, ControlPanelBuilder, SystemDebugger};
+use crate::physics::{RigidBody, TransformComponent};
+use crate::relativity::{
+  RelativisticTime, SpeedOfLight, WorldlineEvent, WorldlineRecording, WorldlineTag, PLAYER_WORLDLINE,
+};
+use crate::utils::{Color, Vec2F, Vec3F};
+
+const DIAGRAM_SIZE: [f32; 2] = [240f32, 240f32];
+const WORLDLINE_COLORS: [Color; 4] = [
+  Color::new(0.3f32, 0.6f32, 1f32),
+  Color::new(1f32, 0.5f32, 1f32),
+  Color::new(0.3f32, 1f32, 1f32),
+  Color::new(1f32, 0.6f32, 0.2f32),
+];
+
+// Records the worldlines of the player and every `WorldlineTag`ged entity into the
+// `WorldlineRecording` resource, and plots them on a 1+1D Minkowski diagram centred on the
+// player's current event.
+#[derive(Default)]
+pub struct WorldlineSystem {
+  last_sample: Option<f64>,
+}
+
+impl<'a> MonoBehavior<'a> for WorldlineSystem {
+  type SystemData = (
+    ReadStorage<'a, Player>,
+    ReadStorage<'a, WorldlineTag>,
+    ReadStorage<'a, TransformComponent>,
+    ReadStorage<'a, RigidBody>,
+    Read<'a, RelativisticTime>,
+    Read<'a, SpeedOfLight>,
+    Write<'a, WorldlineRecording>,
+  );
+
+  fn run(
+    &mut self,
+    api: SystemUtilities<'a>,
+    (player_s, tag_s, transform_s, rigid_body_s, time, speed_of_light, mut recording): Self::SystemData,
+  ) {
+    let mut panel = self.get_write_panel(&api);
+    // Ctrl-clicking the slider lets any value be typed in.
+    let axis = panel.get_int("Axis").clamp(0, 2) as usize;
+    let window = panel.get_float("Window") as f64;
+    let sample_interval = panel.get_float("SampleInterval") as f64;
+    let c = speed_of_light.get();
+
+    let now = time.world_time;
+    if self.last_sample.is_none_or(|last| now - last >= sample_interval) {
+      self.last_sample = Some(now);
+      for (_player, transform) in (&player_s, &transform_s).join() {
+        recording.record(PLAYER_WORLDLINE, now, &transform.translation);
+      }
+      for (tag, transform) in (&tag_s, &transform_s).join() {
+        recording.record(&tag.label, now, &transform.translation);
+      }
+    }
+
+    if panel.get_bool("Clear") {
+      recording.clear();
+      self.last_sample = None;
+    }
+    let path = panel.get_str("ExportPath");
+    if panel.get_bool("ExportCsv") {
+      match recording.export_csv(format!("{}.csv", path)) {
+        Ok(()) => info!("Exported worldlines to {}.csv", path),
+        Err(e) => warn!("Could not export worldlines to {}.csv: {}", path, e),
+      }
+    }
+    if panel.get_bool("ExportJson") {
+      match recording.export_json(format!("{}.json", path)) {
+        Ok(()) => info!("Exported worldlines to {}.json", path),
+        Err(e) => warn!("Could not export worldlines to {}.json: {}", path, e),
+      }
+    }
+
+    let mut beta = 0f32;
+    for (_player, rigid_body) in (&player_s, &rigid_body_s).join() {
+      beta = rigid_body.velocity[axis] / c;
+    }
+    let origin = recording
+      .player()
+      .and_then(|w| w.last().cloned())
+      .unwrap_or_else(|| WorldlineEvent::new(now, &Vec3F::zero()));
+    let extent = c * window as f32;
+    let mut lines = minkowski_axes(extent, beta);
+    for (i, worldline) in recording.worldlines.iter().enumerate() {
+      let color = if worldline.label == PLAYER_WORLDLINE {
+        Color::new(0.3f32, 1f32, 0.3f32)
+      } else {
+        WORLDLINE_COLORS[i % WORLDLINE_COLORS.len()]
+      };
+      let points = worldline.minkowski_projection(axis, &origin, window, c);
+      lines.push(PlotLine::new(points, color, 2f32));
+    }
+    panel.set_vec2("Diagram", Vec2F::new(extent, extent));
+    panel.set_plot("Diagram", lines);
+    panel.set_str("Recording", format!("{} events", recording.event_count()));
+  }
+
+  fn setup(&mut self, world: WorldProxy) {
+    self.register_debugger(&world);
+  }
+}
+
+// Space and time axes, the player's light cone (slope +-1 in units where ct is vertical) and
+// the player's line of simultaneity (ct = beta x).
+fn minkowski_axes(extent: f32, beta: f32) -> Vec<PlotLine> {
+  let grey = Color::new(0.5f32, 0.5f32, 0.5f32);
+  let yellow = Color::new(1f32, 0.9f32, 0.3f32);
+  let red = Color::new(1f32, 0.35f32, 0.2f32);
+  vec![
+    PlotLine::segment(Vec2F::new(-extent, 0f32), Vec2F::new(extent, 0f32), grey, 1f32),
+    PlotLine::segment(Vec2F::new(0f32, -extent), Vec2F::new(0f32, extent), grey, 1f32),
+    PlotLine::segment(Vec2F::new(-extent, -extent), Vec2F::new(extent, extent), yellow, 1f32),
+    PlotLine::segment(Vec2F::new(-extent, extent), Vec2F::new(extent, -extent), yellow, 1f32),
+    PlotLine::segment(
+      Vec2F::new(-extent, -beta * extent),
+      Vec2F::new(extent, beta * extent),
+      red,
+      1.5f32,
+    ),
+  ]
+}
+
+impl<'a> SystemDebugger<'a> for WorldlineSystem {
+  fn create_panel(&self) -> ControlPanelBuilder {
+    ControlPanelBuilder::default()
+      .with_title("Worldlines")
+      .push_line("Recording", LabeledText::new("0 events", "Recording"))
+      .push_line("Axis", InputInt::new_with_limits("Axis (x/y/z)", 0, 0, 2))
+      .push_line("Window", InputFloat::new_with_limits("Window (s)", 5f32, 1f32, 30f32))
+      .push_line(
+        "SampleInterval",
+        InputFloat::new_with_limits("Sample Interval (s)", 0.05f32, 0.01f32, 1f32),
+      )
+      .push_line(
+        "Diagram",
+        LinePlot::new("Minkowski Diagram", DIAGRAM_SIZE, Vec2F::new(1f32, 1f32)),
+      )
+      .push_line("ExportPath", InputText::new("worldlines", "Export Path"))
+      .push_line("ExportCsv", Button::new("Export CSV"))
+      .push_line("ExportJson", Button::new("Export JSON"))
+      .push_line("Clear", Button::new("Clear"))
+  }
+}
@@ -9,13 +9,15 @@ use crate::ecs::{systems::*, GuidMap, GuidRegistrySystem, Guid, EntityTree};
 use crate::ecs::{EntityManager, PrefabBuilder, Sys, SystemUtilities, WorldProxy};
 use crate::events::{Event, EventChannel, KeyCode, ReceiverId, StatelessEventChannel, WindowEvent};
 use crate::game_loop::GameLoop;
-use crate::graphics::{AssetLibrary, Assets, ShaderBuilder, ShaderDepthFunction};
-use crate::graphics::{MaterialComponent, MeshComponent};
+use crate::graphics::{AssetLibrary, Assets, AttributeType, ShaderBuilder, ShaderDepthFunction};
+use crate::graphics::{BufferConfig, BufferLayout, DataBufferBuilder, IndexBufferBuilder, VertexArrayBuilder};
+use crate::graphics::{DirectionalLight, MaterialComponent, MeshComponent, PointLight, SpotLight};
+use crate::graphics::{ShadowCaster, ShadowReceiver};
 use crate::gui::{ControlPanel, ControlPanels, GuiRenderer};
 use crate::physics::TransformComponent;
 use crate::platform::Window;
 use crate::renderer::Renderer;
-use crate::utils::{GetMutRef, MutRef, RunningState, Timestep, Vec2F};
+use crate::utils::{FixedTimestep, GetMutRef, MutRef, RunningState, Timestep, Vec2F};
 
 struct RendererBuilder {
   dims: Vec2F,
@@ -41,6 +43,8 @@ impl RendererBuilder {
       WindowEvent::new(Event::KeyPressed(KeyCode::Tab)),
       WindowEvent::new(Event::KeyPressed(KeyCode::Q)),
       WindowEvent::new(Event::KeyPressed(KeyCode::One)),
+      WindowEvent::new(Event::KeyPressed(KeyCode::Two)),
+      WindowEvent::new(Event::KeyPressed(KeyCode::Three)),
     ]);
     self
   }
@@ -56,6 +60,8 @@ pub struct GameBuilder<'a, 'b> {
   render_builder: RendererBuilder,
   shaders: Vec<ShaderBuilder>,
   dispatcher_builder: DispatcherBuilder<'a, 'b>,
+  physics_dispatcher_builder: DispatcherBuilder<'a, 'b>,
+  fixed_timestep: FixedTimestep,
   world: World,
   window: Window,
 }
@@ -69,6 +75,11 @@ impl<'a, 'b> GameBuilder<'a, 'b> {
       render_builder: RendererBuilder::empty(),
       shaders: Vec::new(),
       dispatcher_builder: DispatcherBuilder::new(),
+      // Every physics tick starts by recording where bodies were, for interpolation.
+      physics_dispatcher_builder: DispatcherBuilder::new()
+        .with(TransformSnapshotSystem, "transform_snapshot", &[])
+        .with_barrier(),
+      fixed_timestep: FixedTimestep::default(),
       world,
       window,
     };
@@ -109,6 +120,21 @@ impl<'a, 'b> GameBuilder<'a, 'b> {
     self
   }
 
+  // Physics systems run on a fixed tick, zero or more times per frame, before the frame's other
+  // systems. Their dependencies can only name other physics systems.
+  pub fn with_physics_system<T>(mut self, system: T, name: &str, dep: &[&str]) -> Self
+  where
+    T: for<'c> System<'c> + Send + 'a,
+  {
+    self.physics_dispatcher_builder.add(system, name, dep);
+    self
+  }
+
+  pub fn with_physics_rate(mut self, hz: f32) -> Self {
+    self.fixed_timestep.set_hz(hz);
+    self
+  }
+
   pub fn with_local_system<T>(mut self, system: T) -> Self
   where
     T: for<'c> RunNow<'c> + 'b,
@@ -158,6 +184,7 @@ impl<'a, 'b> GameBuilder<'a, 'b> {
     // Bind the remaining resources to the world
     self.world.insert(window_channel);
     self.world.insert(Timestep::default());
+    self.world.insert(self.fixed_timestep);
     self.world.insert(RunningState::default());
     self.world.insert(ControlPanels::default());
     self.world.insert(GuidMap::default());
@@ -185,13 +212,25 @@ impl<'a, 'b> GameBuilder<'a, 'b> {
       .dispatcher_builder
       .with(Sys::<DebugMetricsSystem>::default(), "debug", &[])
       .with(GuidRegistrySystem::default(), "guid_registry", &[])
+      .with(RelativitySystem, "relativity", &[])
+      .with(Sys::<ProperTimeSystem>::default(), "proper_time", &[])
+      .with(RetardedPositionSystem, "retarded_position", &["relativity", "proper_time"])
+      .with(Sys::<WorldlineSystem>::default(), "worldlines", &["proper_time"])
+      .with(TransformInterpolationSystem, "transform_interpolation", &[])
+      .with(Sys::<ForceFieldSystem>::default(), "force_fields", &[])
+      .with(TriggerSystem::default(), "triggers", &[])
       .with_thread_local(start_system)
       .with_thread_local(RegisterDrawableSystem::default())
       .with_thread_local(RenderPipelineSystem::new(MutRef::clone(&window_ref), world_id))
       .with_thread_local(gui_renderer)
       .with_thread_local(end_system)
       .build();
-    GameLoop::new(self.world, dispatcher, window_ref)
+    let physics_dispatcher = self
+      .physics_dispatcher_builder
+      .with_barrier()
+      .with(PhysicsQuerySystem, "physics_queries", &[])
+      .build();
+    GameLoop::new(self.world, dispatcher, physics_dispatcher, window_ref)
   }
 
   fn add_default_shaders(&mut self) {
@@ -204,8 +243,44 @@ impl<'a, 'b> GameBuilder<'a, 'b> {
     assets.get_or_create("debug_normals", || {
       ShaderBuilder::default().with_source_file("shaders/debug/normals.glsl")
     });
+    let instance_layout = vec![
+      ("model".to_string(), AttributeType::Mat4),
+      ("diffuse".to_string(), AttributeType::Float3),
+    ];
     assets.get_or_create("instanced", || {
-      ShaderBuilder::default().with_source_file("shaders/simple_instanced.glsl")
+      ShaderBuilder::default()
+        .with_instance_layout(instance_layout.clone())
+        .with_source_file("shaders/simple_instanced.glsl")
+    });
+    assets.get_or_create("shadow_depth", || {
+      ShaderBuilder::default().with_source_file("shaders/shadow_depth.glsl")
+    });
+    assets.get_or_create("g_buffer", || {
+      ShaderBuilder::default().with_source_file("shaders/g_buffer.glsl")
+    });
+    // The "instanced" shader's counterpart in the G-buffer, so both share the mesh's instances.
+    assets.get_or_create("g_buffer_instanced", || {
+      ShaderBuilder::default()
+        .with_instance_layout(instance_layout.clone())
+        .with_source_file("shaders/g_buffer_instanced.glsl")
+    });
+    assets.get_or_create("deferred_lighting", || {
+      ShaderBuilder::default().with_source_file("shaders/deferred_lighting.glsl")
+    });
+    // Covers the screen, for passes that shade every pixel.
+    assets.get_or_create("screen_quad", || {
+      let verts = vec![
+        // Positions  // uv
+        -1f32, 1f32, 0f32, 1f32, -1f32, -1f32, 0f32, 0f32, 1f32, -1f32, 1f32, 0f32, 1f32, 1f32, 1f32, 1f32,
+      ];
+      VertexArrayBuilder::default()
+        .with_vertex_buffer(
+          DataBufferBuilder::default()
+            .with_layout(BufferLayout::new(vec![AttributeType::Float2, AttributeType::Float2]))
+            .with_data(verts)
+            .with_config(BufferConfig::static_vbo()),
+        )
+        .with_index_buffer(IndexBufferBuilder::default().with_data(vec![0, 1, 2, 0, 2, 3]))
     });
     assets.get_or_create("skybox", || {
       ShaderBuilder::default()
@@ -229,6 +304,11 @@ impl WorldBuilder {
     world.register::<EntityManager>();
     world.register::<EntityTree>();
     world.register::<Guid>();
+    world.register::<DirectionalLight>();
+    world.register::<PointLight>();
+    world.register::<SpotLight>();
+    world.register::<ShadowCaster>();
+    world.register::<ShadowReceiver>();
     world.insert(AssetLibrary::default());
     SystemUtilities::setup(&mut world);
     // SystemUtilities::setup(world);
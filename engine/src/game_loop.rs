@@ -7,22 +7,30 @@ use crate::ecs::systems::*;
 use crate::events::ReceiverId;
 use crate::graphics::AssetLibrary;
 use crate::gui::GuiRenderer;
+use crate::physics::RigidBody;
 use crate::platform::Window;
-use crate::utils::{GetMutRef, MutRef, RunningEnum, RunningState, StopwatchLike, Timestep};
+use crate::utils::{FixedTimestep, GetMutRef, MutRef, RunningEnum, RunningState, StopwatchLike, Timestep};
 
 pub type SystemsRegistration<'a, 'b> = dyn Fn(DispatcherBuilder<'a, 'b>) -> DispatcherBuilder<'a, 'b>;
 
 pub struct GameLoop<'a, 'b> {
   world: World,
   dispatcher: Dispatcher<'a, 'b>,
+  physics_dispatcher: Dispatcher<'a, 'b>,
   window: MutRef<Window>,
 }
 
 impl<'a, 'b> GameLoop<'a, 'b> {
-  pub fn new(world: World, dispatcher: Dispatcher<'a, 'b>, window: MutRef<Window>) -> Self {
+  pub fn new(
+    world: World,
+    dispatcher: Dispatcher<'a, 'b>,
+    physics_dispatcher: Dispatcher<'a, 'b>,
+    window: MutRef<Window>,
+  ) -> Self {
     Self {
       world,
       dispatcher,
+      physics_dispatcher,
       window,
     }
   }
@@ -32,6 +40,7 @@ impl<'a, 'b> GameLoop<'a, 'b> {
   pub fn run(&mut self) {
     let mut running = true;
     self.dispatcher.setup(&mut self.world);
+    self.physics_dispatcher.setup(&mut self.world);
     self.maintain();
     {
       self.world.write_resource::<DebugMetrics>().frame_time.start();
@@ -47,12 +56,16 @@ impl<'a, 'b> GameLoop<'a, 'b> {
     sync_running_state(&running_state);
     let ret = match running_state {
       RunningEnum::Running => {
+        self.step_physics();
         self.dispatcher.dispatch(&self.world);
         gl_check_error!("FRAME MESSAGE");
         window_open
       }
       RunningEnum::Stopped => false,
       RunningEnum::StepFrame => {
+        // Stepping advances physics by exactly one tick.
+        self.tick_physics();
+        self.clear_accelerations();
         self.dispatcher.dispatch(&self.world);
         let mut running = self.world.write_resource::<RunningState>();
         running.state = RunningEnum::StepFrameWait;
@@ -68,6 +81,33 @@ impl<'a, 'b> GameLoop<'a, 'b> {
     ret
   }
 
+  // Runs as many fixed physics ticks as the last frame's time covers, then clears the
+  // accelerations gathered for them. A frame too short for any tick keeps its accelerations for
+  // the next one.
+  fn step_physics(&mut self) {
+    let steps = {
+      let frame_dt = self.world.read_resource::<Timestep>().dt_f32();
+      self.world.write_resource::<FixedTimestep>().accumulate(frame_dt)
+    };
+    for _ in 0..steps {
+      self.tick_physics();
+    }
+    if steps > 0 {
+      self.clear_accelerations();
+    }
+  }
+
+  fn tick_physics(&mut self) {
+    self.physics_dispatcher.dispatch(&self.world);
+    self.world.maintain();
+  }
+
+  fn clear_accelerations(&mut self) {
+    for rigid_body in (&mut self.world.write_storage::<RigidBody>()).join() {
+      rigid_body.reset_acceleration();
+    }
+  }
+
   fn maintain(&mut self) {
     {
       let mut asset_library = self.world.write_resource::<AssetLibrary>();
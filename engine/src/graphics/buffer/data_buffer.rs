@@ -7,6 +7,7 @@ use super::BufferConfig;
 use super::BufferLayout;
 use super::Bufferable;
 use super::{buff_ptr, buff_sz, unsafe_cast, unsafe_cast_mut};
+use crate::utils::Vec3F;
 
 const FLOAT_SIZE: usize = 4usize;
 
@@ -17,6 +18,7 @@ pub struct DataBuffer {
   data: Vec<f32>,
   config: BufferConfig,
   delta_range: Option<(usize, usize)>, // bound: bool,
+  allocated: usize,                    // floats in the GPU-side store
 }
 
 impl DataBuffer {
@@ -28,6 +30,7 @@ impl DataBuffer {
       layout,
       config,
       delta_range: None,
+      allocated: 0,
     }
   }
 
@@ -41,6 +44,8 @@ impl DataBuffer {
         self.config.storage_type.to_gl_enum(),
       );
     }
+    self.allocated = self.data.len();
+    self.delta_range = None;
     let stride = self.layout.stride();
     for &(i, offset, attrib) in self.layout.ind_offset_attrib().iter() {
       let attrib_length = attrib.width() / attrib.num_calls();
@@ -62,10 +67,46 @@ impl DataBuffer {
     }
   }
 
+  // The first three floats of every vertex, which is where the position attribute lives in
+  // all of our vertex layouts.
+  pub fn positions(&self) -> Vec<Vec3F> {
+    let stride = self.layout.stride() as usize / FLOAT_SIZE;
+    self
+      .data
+      .chunks_exact(stride)
+      .map(|vertex| Vec3F::new(vertex[0], vertex[1], vertex[2]))
+      .collect()
+  }
+
   pub fn num_attributes(&self) -> u32 {
     self.layout.ind_offset_attrib().len() as u32
   }
 
+  pub fn len(&self) -> usize {
+    self.data.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn is_initialized(&self) -> bool {
+    self.id != u32::MAX
+  }
+
+  // Overwrites the floats from `offset` on, growing the buffer if they run past its end.
+  pub fn write(&mut self, offset: usize, values: &[f32]) {
+    let end = offset + values.len();
+    if end > self.data.len() {
+      self.data.resize(end, 0f32);
+    }
+    self.data[offset..end].copy_from_slice(values);
+    self.delta_range = match self.delta_range {
+      Some((start, old_end)) => Some((start.min(offset), old_end.max(end))),
+      None => Some((offset, end)),
+    };
+  }
+
   pub fn init(&mut self) {
     if self.id == u32::MAX {
       unsafe {
@@ -76,7 +117,18 @@ impl DataBuffer {
 
   pub fn sync_gpu(&mut self) {
     self.bind();
-    if let Some((start, end)) = self.delta_range {
+    if self.data.len() > self.allocated {
+      // The store is too small for the data now, so it's re-allocated instead of patched.
+      unsafe {
+        gl::BufferData(
+          self.config.buffer_type.to_gl_enum(),
+          buff_sz(&self.data),
+          buff_ptr(&self.data),
+          self.config.storage_type.to_gl_enum(),
+        );
+      }
+      self.allocated = self.data.len();
+    } else if let Some((start, end)) = self.delta_range.filter(|(start, end)| start < end) {
       unsafe {
         let slice_ref = self.data.get_unchecked(start..end);
         gl::BufferSubData(
@@ -87,6 +139,7 @@ impl DataBuffer {
         )
       }
     }
+    self.delta_range = None;
   }
 
   pub fn bind(&self) {
@@ -98,6 +151,13 @@ impl DataBuffer {
     }
   }
 
+  // Attaches the buffer to an indexed binding point, which is where uniform blocks read from.
+  pub fn bind_base(&self, binding: u32) {
+    unsafe {
+      gl::BindBufferBase(self.config.buffer_type.to_gl_enum(), binding, self.id);
+    }
+  }
+
   pub fn unbind(&self) {
     unsafe {
       gl::BindBuffer(self.config.buffer_type.to_gl_enum(), 0);
@@ -53,6 +53,10 @@ impl IndexBuffer {
   pub fn len(&self) -> usize {
     self.data.len()
   }
+
+  pub fn data(&self) -> &[u32] {
+    &self.data
+  }
 }
 
 #[derive(Default)]
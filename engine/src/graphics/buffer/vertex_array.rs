@@ -3,8 +3,15 @@ use gl;
 use std::os::raw::c_void;
 use std::ptr;
 
-use super::{BufferView, Bufferable, DataBuffer, IndexBuffer, Vertex};
-use crate::utils::RwAssetRef;
+use specs::Entity;
+
+use super::{BufferConfig, BufferLayout, BufferView, Bufferable, DataBuffer, DataBufferBuilder, IndexBuffer, Vertex};
+use crate::graphics::InstancingTable;
+use crate::utils::{RwAssetRef, Vec3F};
+
+// Per-instance attributes start after the five a `Vertex` has, whatever the mesh's own layout, so
+// that instanced shaders can declare fixed locations for them.
+pub const INSTANCE_ATTRIBUTE_START: u32 = 5;
 
 #[derive(Debug, Clone)]
 pub struct VertexArray {
@@ -12,6 +19,7 @@ pub struct VertexArray {
   index_buffer: IndexBuffer,
   vertex_buffer: DataBuffer,
   pub instancing_buffer: Option<DataBuffer>,
+  instancing_table: Option<InstancingTable>,
 }
 
 impl VertexArray {
@@ -21,6 +29,7 @@ impl VertexArray {
       vertex_buffer: vertex_buffer,
       index_buffer,
       instancing_buffer: None,
+      instancing_table: None,
     }
   }
 
@@ -36,9 +45,9 @@ impl VertexArray {
     }
     self.vertex_buffer.init();
     self.vertex_buffer.refresh(0);
-    if let Some(instancing_buffer) = &mut self.instancing_buffer {
+    if let Some(instancing_buffer) = self.instancing_buffer.as_mut().filter(|buffer| !buffer.is_empty()) {
       instancing_buffer.init();
-      instancing_buffer.refresh(self.vertex_buffer.num_attributes());
+      instancing_buffer.refresh(instance_attribute_start(&self.vertex_buffer));
     }
     self.index_buffer.refresh();
     self.unbind();
@@ -67,6 +76,64 @@ impl VertexArray {
     }
   }
 
+  // Gives this vertex array an empty per-instance buffer laid out like `table`, replacing the
+  // previous one. The buffer is only created on the GPU by the first `sync_instances`.
+  pub fn enable_instancing(&mut self, table: InstancingTable) {
+    let buffer = DataBufferBuilder::default()
+      .with_config(BufferConfig::instancing_buffer())
+      .with_layout(BufferLayout::from(&table.attribute_offsets))
+      .build();
+    if let Some(old_vbo) = self.instancing_buffer.as_mut().filter(|buffer| buffer.is_initialized()) {
+      old_vbo.destroy();
+    }
+    self.instancing_buffer = Some(buffer);
+    self.instancing_table = Some(table);
+  }
+
+  pub fn instancing_table(&self) -> Option<&InstancingTable> {
+    self.instancing_table.as_ref()
+  }
+
+  // Writes `entity`'s packed instance data into its slot, claiming a slot if it has none yet.
+  pub fn upsert_instance(&mut self, entity: &Entity, values: &[f32]) {
+    if let (Some(table), Some(buffer)) = (&mut self.instancing_table, &mut self.instancing_buffer) {
+      let offset = table.upsert_instance(entity);
+      buffer.write(offset, values);
+    }
+  }
+
+  // Frees `entity`'s slot for reuse. The slot is zeroed, so until then it is drawn as a degenerate
+  // instance that covers no pixels.
+  pub fn remove_instance(&mut self, entity: &Entity) {
+    if let (Some(table), Some(buffer)) = (&mut self.instancing_table, &mut self.instancing_buffer) {
+      let offset = table.remove_instance(entity);
+      buffer.write(offset, &vec![0f32; table.stride()]);
+    }
+  }
+
+  // Number of instance slots to draw, holes included.
+  pub fn instance_count(&self) -> usize {
+    match &self.instancing_table {
+      Some(table) if table.len() > 0 => table.num_instances(),
+      _ => 0,
+    }
+  }
+
+  pub fn sync_instances(&mut self) {
+    let attrib_start = instance_attribute_start(&self.vertex_buffer);
+    if let Some(buffer) = self.instancing_buffer.as_mut().filter(|buffer| !buffer.is_empty()) {
+      unsafe {
+        gl::BindVertexArray(*self.id.get());
+      }
+      if buffer.is_initialized() {
+        buffer.sync_gpu();
+      } else {
+        buffer.init();
+        buffer.refresh(attrib_start);
+      }
+    }
+  }
+
   pub fn draw(&self, elem_type: &gl::types::GLenum) {
     unsafe {
       gl::DrawElements(
@@ -90,12 +157,19 @@ impl VertexArray {
     }
   }
 
+  // Polygons in a single instance; instanced draws multiply this by `instance_count`.
   pub fn poly_count(&self) -> usize {
-    // TODO: Once instancing is supported again I need to multiply this
-    // by the number of instances
     self.index_buffer.len() / 3usize
   }
 
+  pub fn positions(&self) -> Vec<Vec3F> {
+    self.vertex_buffer.positions()
+  }
+
+  pub fn indices(&self) -> &[u32] {
+    self.index_buffer.data()
+  }
+
   pub fn update_dynamic_buffers(&mut self) {
     self.vertex_buffer.sync_gpu();
   }
@@ -108,3 +182,13 @@ impl VertexArray {
     self.vertex_buffer.as_view::<Vertex>()
   }
 }
+
+fn instance_attribute_start(vertex_buffer: &DataBuffer) -> u32 {
+  assert!(
+    vertex_buffer.num_attributes() <= INSTANCE_ATTRIBUTE_START,
+    "A mesh with {} vertex attributes overlaps the instance attributes at location {}",
+    vertex_buffer.num_attributes(),
+    INSTANCE_ATTRIBUTE_START
+  );
+  INSTANCE_ATTRIBUTE_START
+}
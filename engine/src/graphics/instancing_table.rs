@@ -5,7 +5,7 @@ use std::num::NonZeroI32;
 
 use specs::prelude::*;
 
-use crate::graphics::{AttributeType, DataBuffer, ShaderId, VertexArray};
+use crate::graphics::{AttributeType, DataBuffer, ShaderId, Uniform, VertexArray};
 
 #[derive(Debug, Clone)]
 pub struct InstancingTable {
@@ -66,7 +66,37 @@ impl InstancingTable {
   }
 
   pub fn len(&self) -> usize {
-    self.instances_table.len() - self.holes.len()
+    self.instances_table.len()
+  }
+
+  pub fn entities(&self) -> impl Iterator<Item = &Entity> {
+    self.instances_table.keys()
+  }
+
+  // Packs one instance into `stride()` floats in attribute order, fetching each attribute's value
+  // by name. Attributes that `lookup` can't provide are left zeroed.
+  pub fn pack_instance<F: Fn(&str) -> Option<Uniform>>(&self, lookup: F) -> Vec<f32> {
+    let mut packed = vec![0f32; self.stride_cache];
+    let mut offset = 0;
+    for (name, attribute) in self.attribute_offsets.iter() {
+      let width = attribute.width() as usize;
+      let values: Vec<f32> = match lookup(name) {
+        Some(Uniform::Float(v)) => vec![v],
+        Some(Uniform::Int(v)) => vec![v as f32],
+        Some(Uniform::Uint(v)) => vec![v as f32],
+        Some(Uniform::Bool(v)) => vec![v as i32 as f32],
+        Some(Uniform::Vec2(v)) => vec![v.x, v.y],
+        Some(Uniform::Vec3(v)) => vec![v.x, v.y, v.z],
+        Some(Uniform::Vec4(v)) => vec![v.x, v.y, v.z, v.w],
+        Some(Uniform::Mat3(m)) => AsRef::<[f32; 9]>::as_ref(&m).to_vec(),
+        Some(Uniform::Mat4(m)) => AsRef::<[f32; 16]>::as_ref(&m).to_vec(),
+        _ => vec![],
+      };
+      let count = width.min(values.len());
+      packed[offset..offset + count].copy_from_slice(&values[..count]);
+      offset += width;
+    }
+    packed
   }
 
   fn calc_offset(&self, ind: &usize) -> usize {
@@ -160,6 +190,19 @@ mod tests {
     assert_eq!(offset, table.remove_instance(&entities[0]));
   }
 
+  #[test]
+  fn test_packs_instance_by_attribute_name() {
+    let (table, _, _) = _create_test_table();
+    let packed = table.pack_instance(|name| match name {
+      "diffuse_index" => Some(Uniform::Int(3)),
+      "diffuse" => Some(Uniform::Vec3(crate::utils::Vec3F::new(0.25, 0.5, 1.0))),
+      _ => None,
+    });
+    assert_eq!(packed.len(), table.stride());
+    assert!(packed[0..16].iter().all(|v| *v == 0f32));
+    assert_eq!(&packed[16..], &[3f32, 0.25, 0.5, 1.0]);
+  }
+
   #[test]
   fn test_popping_entity_frees_position_for_next_insertion() {
     let (mut table, entities, mut world) = _create_test_table();
@@ -171,4 +214,16 @@ mod tests {
     let next_o = table.upsert_instance(&next_e);
     assert_eq!(next_o, offset);
   }
+
+  #[test]
+  fn test_len_counts_live_instances() {
+    let (mut table, entities, _) = _create_test_table();
+    entities.iter().for_each(|e| {
+      table.upsert_instance(e);
+    });
+    table.remove_instance(&entities[0]);
+    table.remove_instance(&entities[1]);
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.num_instances(), 4);
+  }
 }
@@ -0,0 +1,412 @@
+use cgmath::prelude::*;
+use specs::prelude::*;
+use specs::{Component, VecStorage};
+
+use crate::graphics::MAX_SHADOW_MAPS;
+use crate::physics::TransformComponent;
+use crate::utils::{DegF, Mat4F, Vec3F};
+
+// Keep in sync with MAX_LIGHTS in shaders/lights.glsl.
+pub const MAX_LIGHTS: usize = 16;
+// Binding point of the `Lights` uniform block.
+pub const LIGHTS_BINDING: u32 = 0;
+// Four vec4s per light: position, direction, color and attenuation.
+const LIGHT_FLOATS: usize = 16;
+// How far a light reaches when its attenuation never drops off.
+const MAX_RANGE: f32 = 1000f32;
+
+const DIRECTIONAL: f32 = 0f32;
+const POINT: f32 = 1f32;
+const SPOT: f32 = 2f32;
+
+// Intensity falls off as 1 / (constant + linear * d + quadratic * d^2).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Attenuation {
+  pub constant: f32,
+  pub linear: f32,
+  pub quadratic: f32,
+}
+
+impl Attenuation {
+  pub fn new(constant: f32, linear: f32, quadratic: f32) -> Self {
+    Self {
+      constant,
+      linear,
+      quadratic,
+    }
+  }
+
+  pub fn none() -> Self {
+    Self::new(1f32, 0f32, 0f32)
+  }
+
+  pub fn at(&self, distance: f32) -> f32 {
+    1f32 / (self.constant + self.linear * distance + self.quadratic * distance * distance)
+  }
+
+  // Distance at which the light drops to 1/256, below what 8 bit color can show.
+  pub fn range(&self) -> f32 {
+    let (a, b, c) = (self.quadratic, self.linear, self.constant - 256f32);
+    let range = if a > 0f32 {
+      (-b + (b * b - 4f32 * a * c).sqrt()) / (2f32 * a)
+    } else if b > 0f32 {
+      -c / b
+    } else {
+      MAX_RANGE
+    };
+    range.clamp(1f32, MAX_RANGE)
+  }
+}
+
+impl Default for Attenuation {
+  // Fades out over roughly 90 units.
+  fn default() -> Self {
+    Self::new(1f32, 0.09f32, 0.032f32)
+  }
+}
+
+// Parallel light, like the sun. Doesn't need a transform.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct DirectionalLight {
+  pub direction: Vec3F,
+  pub color: Vec3F,
+  pub intensity: f32,
+  // Half the width of the area around the camera that this light casts shadows over, if it does.
+  pub shadow_extent: Option<f32>,
+}
+
+impl DirectionalLight {
+  pub fn new(direction: Vec3F, color: Vec3F, intensity: f32) -> Self {
+    Self {
+      direction: direction.normalize(),
+      color,
+      intensity,
+      shadow_extent: None,
+    }
+  }
+
+  pub fn with_shadows(mut self, extent: f32) -> Self {
+    self.shadow_extent = Some(extent);
+    self
+  }
+
+  // Orthographic projection along the light of a cube with half-width `extent` around `focus`.
+  pub fn light_space(&self, focus: &Vec3F, extent: f32) -> Mat4F {
+    let eye = focus - self.direction * 2f32 * extent;
+    let view = Mat4F::look_at_dir(cgmath::Point3::from_vec(eye), self.direction, up_for(&self.direction));
+    cgmath::ortho(-extent, extent, -extent, extent, 0f32, 4f32 * extent) * view
+  }
+}
+
+// Shines in every direction from the entity's translation.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct PointLight {
+  pub color: Vec3F,
+  pub intensity: f32,
+  pub attenuation: Attenuation,
+}
+
+impl PointLight {
+  pub fn new(color: Vec3F, intensity: f32) -> Self {
+    Self {
+      color,
+      intensity,
+      attenuation: Attenuation::default(),
+    }
+  }
+
+  pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+    self.attenuation = attenuation;
+    self
+  }
+}
+
+// A cone of light from the entity's translation. `direction` is in the entity's local space, so
+// the cone turns with it. Intensity fades from full at `inner_angle` to zero at `outer_angle`.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct SpotLight {
+  pub direction: Vec3F,
+  pub color: Vec3F,
+  pub intensity: f32,
+  pub attenuation: Attenuation,
+  pub inner_angle: DegF,
+  pub outer_angle: DegF,
+  pub casts_shadows: bool,
+}
+
+impl SpotLight {
+  pub fn new(direction: Vec3F, color: Vec3F, intensity: f32) -> Self {
+    Self {
+      direction: direction.normalize(),
+      color,
+      intensity,
+      attenuation: Attenuation::default(),
+      inner_angle: cgmath::Deg(12.5f32),
+      outer_angle: cgmath::Deg(17.5f32),
+      casts_shadows: false,
+    }
+  }
+
+  pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+    self.attenuation = attenuation;
+    self
+  }
+
+  pub fn with_cone(mut self, inner_angle: DegF, outer_angle: DegF) -> Self {
+    self.inner_angle = inner_angle;
+    self.outer_angle = outer_angle;
+    self
+  }
+
+  pub fn with_shadows(mut self) -> Self {
+    self.casts_shadows = true;
+    self
+  }
+
+  // Perspective projection from `position` down `direction` that just covers the cone, out to
+  // where the light fades away.
+  pub fn light_space(&self, position: &Vec3F, direction: &Vec3F) -> Mat4F {
+    let view = Mat4F::look_at_dir(cgmath::Point3::from_vec(*position), *direction, up_for(direction));
+    let fovy = cgmath::Rad::from(self.outer_angle) * 2f32;
+    cgmath::perspective(fovy, 1f32, 0.1f32, self.attenuation.range()) * view
+  }
+}
+
+// Any up vector works for a light's view, as long as it isn't parallel to where it looks.
+fn up_for(direction: &Vec3F) -> Vec3F {
+  if direction.normalize().y.abs() > 0.99f32 {
+    Vec3F::unit_z()
+  } else {
+    Vec3F::unit_y()
+  }
+}
+
+pub type LightStorages<'a> = (
+  ReadStorage<'a, TransformComponent>,
+  ReadStorage<'a, DirectionalLight>,
+  ReadStorage<'a, PointLight>,
+  ReadStorage<'a, SpotLight>,
+);
+
+// Every light in the scene, packed for the `Lights` uniform block, along with the light-space
+// matrices of those that cast shadows. Lights past MAX_LIGHTS and shadows past MAX_SHADOW_MAPS
+// are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct LightBlock {
+  lights: Vec<[f32; LIGHT_FLOATS]>,
+  shadow_views: Vec<Mat4F>,
+}
+
+impl LightBlock {
+  // Directional shadows are centered on `focus`, usually the camera.
+  pub fn gather((transform_s, directional_s, point_s, spot_s): &LightStorages, focus: &Vec3F) -> Self {
+    let mut block = Self::default();
+    for light in directional_s.join() {
+      let shadow = light.shadow_extent.map(|extent| light.light_space(focus, extent));
+      block.push_directional(light, shadow);
+    }
+    for (transform, light) in (transform_s, point_s).join() {
+      block.push_point(&transform.translation, light);
+    }
+    for (transform, light) in (transform_s, spot_s).join() {
+      let direction = transform.rotation * light.direction;
+      let shadow = if light.casts_shadows {
+        Some(light.light_space(&transform.translation, &direction))
+      } else {
+        None
+      };
+      block.push_spot(&transform.translation, &direction, light, shadow);
+    }
+    block
+  }
+
+  pub fn push_directional(&mut self, light: &DirectionalLight, shadow: Option<Mat4F>) {
+    let d = light.direction;
+    let c = light.color * light.intensity;
+    let s = self.shadow_slot(shadow);
+    self.push(
+      [0f32, 0f32, 0f32, DIRECTIONAL],
+      [d.x, d.y, d.z, 0f32],
+      [c.x, c.y, c.z, 0f32],
+      [1f32, 0f32, 0f32, s],
+    );
+  }
+
+  pub fn push_point(&mut self, position: &Vec3F, light: &PointLight) {
+    let p = position;
+    let c = light.color * light.intensity;
+    let a = light.attenuation;
+    self.push(
+      [p.x, p.y, p.z, POINT],
+      [0f32, 0f32, 0f32, 0f32],
+      [c.x, c.y, c.z, 0f32],
+      [a.constant, a.linear, a.quadratic, 0f32],
+    );
+  }
+
+  // `direction` is the cone's axis in world space. The cone's cosines ride in the spare w's.
+  pub fn push_spot(&mut self, position: &Vec3F, direction: &Vec3F, light: &SpotLight, shadow: Option<Mat4F>) {
+    let p = position;
+    let d = direction.normalize();
+    let c = light.color * light.intensity;
+    let a = light.attenuation;
+    let s = self.shadow_slot(shadow);
+    let inner = cgmath::Rad::from(light.inner_angle).0.cos();
+    let outer = cgmath::Rad::from(light.outer_angle).0.cos();
+    self.push(
+      [p.x, p.y, p.z, SPOT],
+      [d.x, d.y, d.z, inner],
+      [c.x, c.y, c.z, outer],
+      [a.constant, a.linear, a.quadratic, s],
+    );
+  }
+
+  pub fn len(&self) -> usize {
+    self.lights.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.lights.is_empty()
+  }
+
+  // Indexed by the shadow slot of each shadowed light.
+  pub fn shadow_views(&self) -> &[Mat4F] {
+    &self.shadow_views
+  }
+
+  // The std140 layout of the block: an ivec4 holding the light count, then MAX_LIGHTS lights.
+  // Unused lights are zeroed so that the block always has the same size.
+  pub fn data(&self) -> Vec<f32> {
+    let mut data = vec![0f32; 4 + MAX_LIGHTS * LIGHT_FLOATS];
+    data[0] = f32::from_bits(self.lights.len() as u32);
+    for (i, light) in self.lights.iter().enumerate() {
+      let start = 4 + i * LIGHT_FLOATS;
+      data[start..start + LIGHT_FLOATS].copy_from_slice(light);
+    }
+    data
+  }
+
+  // Claims a shadow map for the light about to be pushed. The light stores its slot plus one, so
+  // that zero means unshadowed.
+  fn shadow_slot(&mut self, shadow: Option<Mat4F>) -> f32 {
+    match shadow {
+      Some(view) if self.lights.len() < MAX_LIGHTS && self.shadow_views.len() < MAX_SHADOW_MAPS => {
+        self.shadow_views.push(view);
+        self.shadow_views.len() as f32
+      }
+      _ => 0f32,
+    }
+  }
+
+  fn push(&mut self, position: [f32; 4], direction: [f32; 4], color: [f32; 4], attenuation: [f32; 4]) {
+    if self.lights.len() < MAX_LIGHTS {
+      let mut light = [0f32; LIGHT_FLOATS];
+      for (i, row) in [position, direction, color, attenuation].iter().enumerate() {
+        light[i * 4..i * 4 + 4].copy_from_slice(row);
+      }
+      self.lights.push(light);
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn packs_lights_for_the_uniform_block() {
+    let mut block = LightBlock::default();
+    block.push_directional(
+      &DirectionalLight::new(Vec3F::new(0f32, -2f32, 0f32), Vec3F::new(1f32, 1f32, 1f32), 0.5f32),
+      None,
+    );
+    block.push_point(
+      &Vec3F::new(1f32, 2f32, 3f32),
+      &PointLight::new(Vec3F::new(1f32, 0f32, 0f32), 2f32),
+    );
+    let data = block.data();
+    assert_eq!(data.len(), 4 + MAX_LIGHTS * LIGHT_FLOATS);
+    assert_eq!(data[0].to_bits(), 2);
+    // Directional: normalized direction and color scaled by intensity.
+    assert_eq!(&data[8..11], &[0f32, -1f32, 0f32]);
+    assert_eq!(&data[12..15], &[0.5f32, 0.5f32, 0.5f32]);
+    // Point: position tagged with its kind, then the default attenuation.
+    assert_eq!(&data[20..24], &[1f32, 2f32, 3f32, POINT]);
+    assert_eq!(&data[32..35], &[1f32, 0.09f32, 0.032f32]);
+    assert!(data[36..].iter().all(|v| *v == 0f32));
+  }
+
+  #[test]
+  fn drops_lights_past_the_limit() {
+    let mut block = LightBlock::default();
+    for _ in 0..MAX_LIGHTS + 3 {
+      block.push_point(&Vec3F::zero(), &PointLight::new(Vec3F::new(1f32, 1f32, 1f32), 1f32));
+    }
+    assert_eq!(block.len(), MAX_LIGHTS);
+  }
+
+  #[test]
+  fn attenuation_falls_off_with_distance() {
+    let attenuation = Attenuation::default();
+    assert_eq!(attenuation.at(0f32), 1f32);
+    assert!(attenuation.at(10f32) < attenuation.at(5f32));
+    assert_eq!(Attenuation::none().at(100f32), 1f32);
+    assert!((attenuation.at(attenuation.range()) - 1f32 / 256f32).abs() < 1e-6f32);
+    assert_eq!(Attenuation::none().range(), MAX_RANGE);
+  }
+
+  fn project(light_space: &Mat4F, point: Vec3F) -> Vec3F {
+    let clip = light_space * point.extend(1f32);
+    clip.truncate() / clip.w
+  }
+
+  #[test]
+  fn directional_shadow_view_is_centered_on_the_focus() {
+    let light = DirectionalLight::new(Vec3F::new(-1f32, -1f32, 1f32), Vec3F::new(1f32, 1f32, 1f32), 1f32);
+    let focus = Vec3F::new(5f32, 0f32, 3f32);
+    let light_space = light.light_space(&focus, 10f32);
+    let center = project(&light_space, focus);
+    assert!(center.x.abs() < 1e-5f32 && center.y.abs() < 1e-5f32);
+    assert!(center.z.abs() < 1f32);
+    // Whatever is closer to the light is shallower.
+    assert!(project(&light_space, focus - light.direction).z < center.z);
+    assert!(project(&light_space, focus + Vec3F::new(20f32, 0f32, 0f32)).x.abs() > 1f32);
+  }
+
+  #[test]
+  fn spot_shadow_view_covers_the_cone() {
+    let light = SpotLight::new(Vec3F::unit_x(), Vec3F::new(1f32, 1f32, 1f32), 1f32)
+      .with_cone(cgmath::Deg(20f32), cgmath::Deg(30f32));
+    let light_space = light.light_space(&Vec3F::zero(), &Vec3F::unit_x());
+    let on_axis = project(&light_space, Vec3F::new(10f32, 0f32, 0f32));
+    assert!(on_axis.x.abs() < 1e-5f32 && on_axis.y.abs() < 1e-5f32);
+    let inside = cgmath::Deg(25f32);
+    let edge = project(
+      &light_space,
+      Vec3F::new(10f32, 10f32 * cgmath::Rad::from(inside).0.tan(), 0f32),
+    );
+    assert!(edge.y.abs() < 1f32);
+    let outside = project(&light_space, Vec3F::new(10f32, 10f32, 0f32));
+    assert!(outside.y.abs() > 1f32);
+  }
+
+  #[test]
+  fn shadowed_lights_claim_shadow_slots() {
+    let mut block = LightBlock::default();
+    let light = DirectionalLight::new(Vec3F::new(0f32, -1f32, 0f32), Vec3F::new(1f32, 1f32, 1f32), 1f32);
+    block.push_directional(&light, None);
+    for _ in 0..MAX_SHADOW_MAPS + 1 {
+      block.push_directional(&light, Some(light.light_space(&Vec3F::zero(), 10f32)));
+    }
+    assert_eq!(block.shadow_views().len(), MAX_SHADOW_MAPS);
+    let data = block.data();
+    let slot = |i: usize| data[4 + i * LIGHT_FLOATS + 15];
+    assert_eq!(slot(0), 0f32);
+    assert_eq!(slot(1), 1f32);
+    assert_eq!(slot(MAX_SHADOW_MAPS), MAX_SHADOW_MAPS as f32);
+    assert_eq!(slot(MAX_SHADOW_MAPS + 1), 0f32);
+  }
+}
@@ -1,17 +1,21 @@
 mod asset_library;
 mod buffer;
 mod instancing_table;
+mod light;
 mod material;
 mod mesh;
 mod shader;
+mod shadow;
 mod texture;
 mod uniform;
 
 pub use self::asset_library::*;
 pub use self::buffer::*;
 pub use self::instancing_table::*;
+pub use self::light::*;
 pub use self::material::*;
 pub use self::mesh::*;
 pub use self::shader::*;
+pub use self::shadow::*;
 pub use self::texture::*;
 pub use self::uniform::*;
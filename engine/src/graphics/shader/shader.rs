@@ -4,6 +4,7 @@ use std::ptr;
 
 use cgmath::prelude::*;
 
+use crate::graphics::AttributeType;
 use crate::graphics::TextureId;
 use crate::graphics::Uniform;
 use crate::utils::RwAssetRef;
@@ -16,6 +17,7 @@ pub struct Shader {
   id: RwAssetRef<u32>,
   element_type: gl::types::GLenum,
   uniform_slots: UniformSlots,
+  instance_layout: Option<Vec<(String, AttributeType)>>,
 }
 
 impl Shader {
@@ -24,12 +26,14 @@ impl Shader {
     id: RwAssetRef<u32>,
     element_type: gl::types::GLenum,
     uniform_slots: UniformSlots,
+    instance_layout: Option<Vec<(String, AttributeType)>>,
   ) -> Self {
     Self {
       binder,
       id,
       element_type,
       uniform_slots,
+      instance_layout,
     }
   }
 
@@ -41,10 +45,25 @@ impl Shader {
   }
 
   pub fn set_uniform(&self, name: &str, unif: &Uniform) {
+    if let Uniform::UniformBuffer(buffer) = unif {
+      self.bind_uniform_block(name, buffer.binding());
+      return;
+    }
     let uniform_slot = self.uniform_slots.get_slot(name, *self.id.get());
     set_unif_helper(unif, uniform_slot);
   }
 
+  // Blocks this shader doesn't declare are ignored, like unknown uniforms are.
+  fn bind_uniform_block(&self, name: &str, binding: u32) {
+    let c_name = CString::new(name).unwrap();
+    unsafe {
+      let index = gl::GetUniformBlockIndex(self.id(), c_name.as_ptr());
+      if index != gl::INVALID_INDEX {
+        gl::UniformBlockBinding(self.id(), index, binding);
+      }
+    }
+  }
+
   pub fn set_texture(&self, slot: u32, name: &str, texture: &TextureId) {
     texture.bind(slot);
     let unif = Uniform::Int(slot as i32);
@@ -63,6 +82,11 @@ impl Shader {
     &self.element_type
   }
 
+  // The per-instance attributes of an instanced shader, named after the uniforms they replace.
+  pub fn instance_layout(&self) -> Option<&Vec<(String, AttributeType)>> {
+    self.instance_layout.as_ref()
+  }
+
   pub fn id(&self) -> u32 {
     *self.id.get()
   }
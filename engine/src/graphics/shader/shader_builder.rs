@@ -1,6 +1,8 @@
 use crate::datastructures::RegistryItem;
+use crate::graphics::{AttributeType, INSTANCE_ATTRIBUTE_START};
 use crate::utils::{ReadAssetRef, RwAssetRef};
 use gl;
+use std::ffi::CString;
 
 use super::shader_delegates::{
   DepthFuncShaderBinder, ShaderBinder, ShaderDepthFunction, StdShaderBinder, UniformSlots,
@@ -13,6 +15,7 @@ pub struct ShaderBuilder {
   filename: Option<String>,
   depth_function: gl::types::GLenum,
   shader_id: RwAssetRef<u32>,
+  instance_layout: Option<Vec<(String, AttributeType)>>,
 }
 
 impl Default for ShaderBuilder {
@@ -21,6 +24,7 @@ impl Default for ShaderBuilder {
       filename: None,
       shader_id: RwAssetRef::new(std::u32::MAX),
       depth_function: gl::LESS,
+      instance_layout: None,
     }
   }
 }
@@ -35,6 +39,15 @@ impl ShaderBuilder {
     self.depth_function = depth_func.get_gl_enum();
     self
   }
+
+  // Draws every entity sharing a mesh with this shader in one instanced call. The attributes are
+  // read per instance, in order, from `INSTANCE_ATTRIBUTE_START`, and the shader declares each as
+  // an input named after it, so "model" is `aModel`. "model" is filled with the entity's transform
+  // and anything else from its material.
+  pub fn with_instance_layout(mut self, layout: Vec<(String, AttributeType)>) -> Self {
+    self.instance_layout = Some(layout);
+    self
+  }
 }
 
 impl RegistryItem for ShaderBuilder {
@@ -59,9 +72,55 @@ impl RegistryItem for ShaderBuilder {
     let shader_steps = shader_preprocessor::decompress(shader_body);
     let element_type = shader_preprocessor::get_element_type(&shader_steps);
     let program_id = shader_preprocessor::compile_program(shader_steps);
+    if let Some(layout) = &self.instance_layout {
+      check_instance_locations(program_id, layout);
+    }
     let uniform_slots = UniformSlots::default();
     self.shader_id.set(program_id);
-    Shader::new(binder, self.shader_id, element_type, uniform_slots)
+    Shader::new(
+      binder,
+      self.shader_id,
+      element_type,
+      uniform_slots,
+      self.instance_layout,
+    )
+  }
+}
+
+// Where each per-instance attribute is read from. A Mat4 takes one location per column.
+fn instance_locations(layout: &[(String, AttributeType)]) -> Vec<(String, u32)> {
+  let mut location = INSTANCE_ATTRIBUTE_START;
+  layout
+    .iter()
+    .map(|(name, attribute)| {
+      let ret = (instance_input_name(name), location);
+      location += attribute.num_calls();
+      ret
+    })
+    .collect()
+}
+
+fn instance_input_name(name: &str) -> String {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(first) => format!("a{}{}", first.to_uppercase(), chars.as_str()),
+    None => String::new(),
+  }
+}
+
+// Catches a shader whose layout qualifiers have drifted from its instance layout, which would
+// otherwise read one attribute's data as another's. Inputs the shader doesn't use are skipped.
+fn check_instance_locations(program_id: u32, layout: &[(String, AttributeType)]) {
+  for (input, location) in instance_locations(layout) {
+    let c_name = CString::new(input.as_str()).unwrap();
+    let found = unsafe { gl::GetAttribLocation(program_id, c_name.as_ptr()) };
+    assert!(
+      found == -1 || found == location as i32,
+      "Instance attribute {} is at location {} but the instance layout puts it at {}",
+      input,
+      found,
+      location
+    );
   }
 }
 
@@ -90,4 +149,16 @@ mod test {
     assert_ne!(shader_id.get(), std::u32::MAX);
     assert_eq!(shader_id.get(), shader.id());
   }
+
+  #[test]
+  fn instance_attributes_follow_the_vertex_attributes() {
+    let layout = vec![
+      ("model".to_string(), AttributeType::Mat4),
+      ("diffuse".to_string(), AttributeType::Float3),
+    ];
+    assert_eq!(
+      instance_locations(&layout),
+      vec![("aModel".to_string(), 5), ("aDiffuse".to_string(), 9)]
+    );
+  }
 }
@@ -0,0 +1,17 @@
+use specs::prelude::*;
+use specs::{Component, NullStorage};
+
+// Keep in sync with MAX_SHADOW_MAPS in shaders/lights.glsl.
+pub const MAX_SHADOW_MAPS: usize = 4;
+// Shadow maps are bound from here upwards, clear of material textures and the screen.
+pub const SHADOW_MAP_SLOT: u32 = 33;
+
+// Drawn into the shadow maps, so that it blocks light.
+#[derive(Component, Debug, Clone, Default)]
+#[storage(NullStorage)]
+pub struct ShadowCaster;
+
+// Darkened where a shadow caster stands between it and a shadowed light.
+#[derive(Component, Debug, Clone, Default)]
+#[storage(NullStorage)]
+pub struct ShadowReceiver;
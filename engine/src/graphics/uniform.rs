@@ -116,7 +116,19 @@ pub enum UniformLifecycle {
   Runtime,
 }
 
+// A uniform block, read from whichever buffer is attached to `binding` (see
+// `DataBuffer::bind_base`). Setting it on a shader points the block of the same name there.
 #[derive(Clone, Debug)]
-pub struct UniformBuffer {}
+pub struct UniformBuffer {
+  binding: u32,
+}
+
+impl UniformBuffer {
+  pub fn new(binding: u32) -> Self {
+    Self { binding }
+  }
 
-impl UniformBuffer {}
+  pub fn binding(&self) -> u32 {
+    self.binding
+  }
+}
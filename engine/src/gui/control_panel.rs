@@ -4,7 +4,7 @@ use specs::Read;
 use std::collections::HashMap;
 use std::sync::RwLock;
 
-use super::widgets::Widget;
+use super::widgets::{PlotLine, Widget};
 use crate::datastructures::{GenericRegistry, Registry, RegistryItem};
 use crate::utils::{Vec2F, Vec3F};
 use std::any::TypeId;
@@ -23,6 +23,10 @@ impl ControlPanel {
     self.get_by_name(name).get_int()
   }
 
+  pub fn get_bool(&self, name: &str) -> bool {
+    self.get_by_name(name).get_bool()
+  }
+
   pub fn get_vec2(&self, name: &str) -> Vec2F {
     self.get_by_name(name).get_vec2()
   }
@@ -55,6 +59,10 @@ impl ControlPanel {
     self.get_by_name_mut(name).set_string(value);
   }
 
+  pub fn set_plot(&mut self, name: &str, value: Vec<PlotLine>) {
+    self.get_by_name_mut(name).set_plot(value);
+  }
+
   pub fn render<'ui>(&mut self, ui: &imgui::Ui<'ui>, pos: &[f32; 2]) {
     let pos_f32 = [pos[0] as f32, pos[1] as f32];
     imgui::Window::new(ui, &self.title)
@@ -73,7 +81,7 @@ impl ControlPanel {
   }
 
   pub fn height(&self) -> u32 {
-    10u32 + self.lines.len() as u32 * 40u32
+    10u32 + self.lines.iter().map(|(_name, line)| line.height()).sum::<u32>()
   }
 
   fn get_by_name(&self, name: &str) -> &Box<dyn Widget> {
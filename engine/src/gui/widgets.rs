@@ -1,5 +1,5 @@
 use crate::utils::{getSyncMutRef, Color, SyncMutRef, Vec2F, Vec3F};
-use imgui::{ImString, SliderFloat, Ui};
+use imgui::{ImString, SliderFloat, SliderInt, Ui};
 use specs::prelude::*;
 use specs::{Component, VecStorage};
 
@@ -9,6 +9,11 @@ use specs::{Component, VecStorage};
 pub trait Widget {
   fn render<'ui>(&mut self, ui: &imgui::Ui<'ui>);
 
+  // Vertical space reserved for the widget when stacking panels.
+  fn height(&self) -> u32 {
+    40u32
+  }
+
   fn get_float(&self) -> f32 {
     panic!(
       "Widget::get_float not implemented for {}",
@@ -57,6 +62,9 @@ pub trait Widget {
       std::any::type_name::<Self>()
     );
   }
+  fn set_plot(&mut self, _value: Vec<PlotLine>) {
+    panic!("Widget::set_plot not implemented for {}", std::any::type_name::<Self>());
+  }
 }
 
 /******************************
@@ -85,6 +93,10 @@ impl Widget for InputText {
   fn render<'ui>(&mut self, ui: &Ui<'ui>) {
     ui.input_text(&self.label, &mut self.text).build();
   }
+
+  fn get_string(&self) -> String {
+    self.text.to_str().to_string()
+  }
 }
 
 pub struct LabeledText {
@@ -173,11 +185,58 @@ impl Widget for InputFloat {
     self.value as f32
   }
 }
-// OverlayLine::IntInput(label, value) => {
-//   let v = ImString::from(label.clone());
-//   let slider = SliderInt::new(ui, &v, value, 0, 100);
-//   slider.build();
-// }
+
+pub struct InputInt {
+  value: i32,
+  label: ImString,
+  low_value: i32,
+  high_value: i32,
+}
+impl InputInt {
+  pub fn new_with_limits(label: &str, value: i32, low_value: i32, high_value: i32) -> Self {
+    Self {
+      value,
+      label: ImString::from(label.to_string()),
+      low_value,
+      high_value,
+    }
+  }
+}
+impl Widget for InputInt {
+  fn render<'ui>(&mut self, ui: &Ui<'ui>) {
+    let slider = SliderInt::new(ui, &self.label, &mut self.value, self.low_value, self.high_value);
+    slider.build();
+  }
+  fn get_int(&self) -> i32 {
+    self.value
+  }
+}
+
+pub struct Checkbox {
+  label: ImString,
+  value: bool,
+}
+impl Checkbox {
+  pub fn new(label: &str, value: bool) -> Self {
+    Self {
+      label: ImString::from(label.to_string()),
+      value,
+    }
+  }
+}
+impl Widget for Checkbox {
+  fn render<'ui>(&mut self, ui: &Ui<'ui>) {
+    ui.checkbox(&self.label, &mut self.value);
+  }
+
+  fn get_bool(&self) -> bool {
+    self.value
+  }
+
+  fn set_bool(&mut self, value: bool) {
+    self.value = value;
+  }
+}
 
 pub struct InputColor {
   label: ImString,
@@ -201,6 +260,32 @@ impl Widget for InputColor {
   }
 }
 
+pub struct InputVec3 {
+  label: ImString,
+  value: [f32; 3],
+}
+impl InputVec3 {
+  pub fn new(label: &str, value: Vec3F) -> Self {
+    Self {
+      label: ImString::from(label.to_string()),
+      value: [value.x, value.y, value.z],
+    }
+  }
+}
+impl Widget for InputVec3 {
+  fn render<'ui>(&mut self, ui: &Ui<'ui>) {
+    ui.input_float3(&self.label, &mut self.value).build();
+  }
+
+  fn get_vec3(&self) -> Vec3F {
+    Vec3F::new(self.value[0], self.value[1], self.value[2])
+  }
+
+  fn set_vec3(&mut self, value: Vec3F) {
+    self.value = [value.x, value.y, value.z];
+  }
+}
+
 pub struct Button {
   label: ImString,
   clicked: bool,
@@ -224,3 +309,84 @@ impl Button {
     }
   }
 }
+
+// A polyline in plot coordinates.
+pub struct PlotLine {
+  pub points: Vec<Vec2F>,
+  pub color: Color,
+  pub thickness: f32,
+}
+impl PlotLine {
+  pub fn new(points: Vec<Vec2F>, color: Color, thickness: f32) -> Self {
+    Self {
+      points,
+      color,
+      thickness,
+    }
+  }
+
+  pub fn segment(from: Vec2F, to: Vec2F, color: Color, thickness: f32) -> Self {
+    Self::new(vec![from, to], color, thickness)
+  }
+}
+
+// Draws polylines into a fixed-size box spanning [-extent, extent] on both axes, y up.
+pub struct LinePlot {
+  label: ImString,
+  size: [f32; 2],
+  extent: Vec2F,
+  lines: Vec<PlotLine>,
+}
+impl LinePlot {
+  pub fn new(label: &str, size: [f32; 2], extent: Vec2F) -> Self {
+    Self {
+      label: ImString::from(label.to_string()),
+      size,
+      extent,
+      lines: Vec::new(),
+    }
+  }
+
+  fn to_screen(&self, origin: [f32; 2], point: &Vec2F) -> [f32; 2] {
+    let u = (point.x / self.extent.x).clamp(-1f32, 1f32) * 0.5f32 + 0.5f32;
+    let v = (point.y / self.extent.y).clamp(-1f32, 1f32) * 0.5f32 + 0.5f32;
+    [origin[0] + u * self.size[0], origin[1] + (1f32 - v) * self.size[1]]
+  }
+}
+impl Widget for LinePlot {
+  fn render<'ui>(&mut self, ui: &Ui<'ui>) {
+    ui.text(&self.label);
+    let origin = ui.get_cursor_screen_pos();
+    let corner = [origin[0] + self.size[0], origin[1] + self.size[1]];
+    let draw_list = ui.get_window_draw_list();
+    draw_list
+      .add_rect(origin, corner, [1f32, 1f32, 1f32, 0.5f32])
+      .build();
+    for line in self.lines.iter() {
+      let color = [line.color.x, line.color.y, line.color.z, 1f32];
+      for pair in line.points.windows(2) {
+        draw_list
+          .add_line(self.to_screen(origin, &pair[0]), self.to_screen(origin, &pair[1]), color)
+          .thickness(line.thickness)
+          .build();
+      }
+    }
+    ui.dummy(self.size);
+  }
+
+  fn height(&self) -> u32 {
+    40u32 + self.size[1] as u32
+  }
+
+  fn get_vec2(&self) -> Vec2F {
+    self.extent
+  }
+
+  fn set_vec2(&mut self, extent: Vec2F) {
+    self.extent = extent;
+  }
+
+  fn set_plot(&mut self, lines: Vec<PlotLine>) {
+    self.lines = lines;
+  }
+}
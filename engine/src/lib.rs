@@ -43,6 +43,7 @@ pub mod gui;
 pub mod net;
 pub mod physics;
 mod platform;
+pub mod relativity;
 pub mod renderer;
 pub mod testing;
 pub mod utils;
@@ -0,0 +1,110 @@
+use cgmath::prelude::*;
+use specs::Entity;
+
+use crate::datastructures::{HasPosition, KdTree, SpatialIndex};
+use crate::utils::Vec3F;
+
+// A collider as seen by the broadphase: just the sphere bounding it.
+#[derive(Debug, Clone)]
+pub struct ColliderEntry {
+  pub entity: Entity,
+  pub center: Vec3F,
+  pub bounding_radius: f32,
+}
+
+impl HasPosition for ColliderEntry {
+  fn position(&self) -> &Vec3F {
+    &self.center
+  }
+}
+
+// Indexes collider bounding spheres in a `KdTree` so that a moving sphere only has to run the
+// narrowphase against colliders it could plausibly touch this frame.
+pub struct Broadphase {
+  index: KdTree<ColliderEntry>,
+  max_radius: f32,
+}
+
+impl Default for Broadphase {
+  fn default() -> Self {
+    Self {
+      index: KdTree::default(),
+      max_radius: 0f32,
+    }
+  }
+}
+
+impl Broadphase {
+  pub fn rebuild(&mut self, colliders: Vec<ColliderEntry>) {
+    self.max_radius = colliders.iter().fold(0f32, |acc, c| acc.max(c.bounding_radius));
+    self.index.set_data(colliders);
+  }
+
+  pub fn count(&self) -> usize {
+    self.index.count()
+  }
+
+  pub fn entries(&self) -> &Vec<ColliderEntry> {
+    self.index.data()
+  }
+
+  // Colliders whose bounding sphere overlaps the sphere at `center` with `radius`. The tree is
+  // queried with the largest collider radius added on, then filtered per collider.
+  pub fn candidates(&self, center: &Vec3F, radius: f32) -> Vec<&ColliderEntry> {
+    let data = self.index.data();
+    self
+      .candidate_indices(center, radius)
+      .into_iter()
+      .map(|i| &data[i])
+      .collect()
+  }
+
+  // Same as `candidates`, but as positions in `entries`.
+  pub fn candidate_indices(&self, center: &Vec3F, radius: f32) -> Vec<usize> {
+    let data = self.index.data();
+    self
+      .index
+      .query_near(center, radius + self.max_radius)
+      .into_iter()
+      .filter(|&i| {
+        let entry = &data[i];
+        let reach = radius + entry.bounding_radius;
+        (entry.center - center).magnitude2() <= reach * reach
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use specs::prelude::*;
+
+  #[test]
+  fn candidates_respect_each_collider_radius() {
+    let mut world = World::new();
+    let entities: Vec<Entity> = (0..20).map(|_| world.create_entity().build()).collect();
+    let colliders = entities
+      .iter()
+      .enumerate()
+      .map(|(i, e)| ColliderEntry {
+        entity: *e,
+        center: Vec3F::new(i as f32 * 4f32, 0f32, 0f32),
+        bounding_radius: if i == 10 { 6f32 } else { 1f32 },
+      })
+      .collect();
+    let mut broadphase = Broadphase::default();
+    broadphase.rebuild(colliders);
+    assert_eq!(broadphase.count(), 20);
+
+    let mut found: Vec<Entity> = broadphase
+      .candidates(&Vec3F::new(35f32, 0f32, 0f32), 0.5f32)
+      .iter()
+      .map(|c| c.entity)
+      .collect();
+    found.sort();
+    // Only the neighbour at x = 36 and the large collider at x = 40 are in reach.
+    assert_eq!(found, vec![entities[9], entities[10]]);
+    assert!(broadphase.candidates(&Vec3F::new(0f32, 50f32, 0f32), 1f32).is_empty());
+  }
+}
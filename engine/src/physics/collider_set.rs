@@ -0,0 +1,94 @@
+use specs::prelude::*;
+
+use super::{
+  AxisAlignedCubeCollision, Broadphase, CapsuleCollision, ColliderEntry, Collision, CollisionSummary, MeshCollision,
+  OrientedBoxCollision, SphereCollision, Trigger,
+};
+use crate::utils::Vec3F;
+
+// Every collider storage, for systems that collide against all collider types. Fetch it as part of
+// a system's `SystemData` and query it through `ColliderLookup`.
+pub type ColliderStorages<'a> = (
+  ReadStorage<'a, AxisAlignedCubeCollision>,
+  ReadStorage<'a, OrientedBoxCollision>,
+  ReadStorage<'a, SphereCollision>,
+  ReadStorage<'a, CapsuleCollision>,
+  ReadStorage<'a, MeshCollision>,
+);
+
+pub trait ColliderLookup {
+  // Bounding spheres of every collider, ready for `Broadphase::rebuild`.
+  fn entries(&self, entities: &Entities) -> Vec<ColliderEntry>;
+
+  // Earliest hit against any collider attached to `entity`.
+  fn sphere_collision(&self, entity: Entity, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary>;
+
+  // Signed distance to the nearest collider attached to `entity`, if it has any.
+  fn distance_to(&self, entity: Entity, pt: &Vec3F) -> Option<f32>;
+}
+
+// Indexes every collider except `Trigger` volumes, which nothing collides with. Systems rebuild the
+// broadphase before reading it, so it never lags behind the colliders.
+pub fn rebuild_solid_broadphase(
+  broadphase: &mut Broadphase,
+  entities: &Entities,
+  colliders: &ColliderStorages,
+  triggers: &ReadStorage<Trigger>,
+) {
+  broadphase.rebuild(
+    colliders
+      .entries(entities)
+      .into_iter()
+      .filter(|entry| !triggers.contains(entry.entity))
+      .collect(),
+  );
+}
+
+fn push_entries<C: Collision>(entities: &Entities, storage: &ReadStorage<C>, entries: &mut Vec<ColliderEntry>) {
+  for (entity, collider) in (entities, storage).join() {
+    let (center, bounding_radius) = collider.bounding_sphere();
+    entries.push(ColliderEntry {
+      entity,
+      center,
+      bounding_radius,
+    });
+  }
+}
+
+impl<'a> ColliderLookup for ColliderStorages<'a> {
+  fn entries(&self, entities: &Entities) -> Vec<ColliderEntry> {
+    let mut entries = Vec::new();
+    push_entries(entities, &self.0, &mut entries);
+    push_entries(entities, &self.1, &mut entries);
+    push_entries(entities, &self.2, &mut entries);
+    push_entries(entities, &self.3, &mut entries);
+    push_entries(entities, &self.4, &mut entries);
+    entries
+  }
+
+  fn sphere_collision(&self, entity: Entity, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary> {
+    let hits = [
+      self.0.get(entity).and_then(|c| c.sphere_collision(sphere, velocity)),
+      self.1.get(entity).and_then(|c| c.sphere_collision(sphere, velocity)),
+      self.2.get(entity).and_then(|c| c.sphere_collision(sphere, velocity)),
+      self.3.get(entity).and_then(|c| c.sphere_collision(sphere, velocity)),
+      self.4.get(entity).and_then(|c| c.sphere_collision(sphere, velocity)),
+    ];
+    hits.iter().flatten().min().copied()
+  }
+
+  fn distance_to(&self, entity: Entity, pt: &Vec3F) -> Option<f32> {
+    let distances = [
+      self.0.get(entity).map(|c| c.distance_to(pt)),
+      self.1.get(entity).map(|c| c.distance_to(pt)),
+      self.2.get(entity).map(|c| c.distance_to(pt)),
+      self.3.get(entity).map(|c| c.distance_to(pt)),
+      self.4.get(entity).map(|c| c.distance_to(pt)),
+    ];
+    distances
+      .iter()
+      .flatten()
+      .copied()
+      .fold(None, |best: Option<f32>, d| Some(best.map_or(d, |b| b.min(d))))
+  }
+}
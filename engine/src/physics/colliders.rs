@@ -1,7 +1,9 @@
+use cgmath::prelude::*;
 use specs::{Component, Join, Read, ReadStorage, System, VecStorage, WriteStorage};
 
+use super::geometry::{closest_point_on_segment, sweep_point_capsule, sweep_point_sphere};
 use crate::physics::{Collision, CollisionSummary, TransformComponent};
-use crate::utils::{swizzle_down, swizzle_up, Vec3F};
+use crate::utils::{swizzle_down, swizzle_up, QuatF, Vec3F};
 
 #[derive(Component, Debug, Clone)]
 #[storage(VecStorage)]
@@ -11,20 +13,37 @@ pub struct AxisAlignedCubeCollision {
 }
 
 impl AxisAlignedCubeCollision {
+  // The smallest axis-aligned box containing all eight corners of the transformed unit cube,
+  // so a rotated transform gets a looser (but still enclosing) box.
   pub fn from_transform(transform: &TransformComponent) -> Self {
-    let c1 = Vec3F::new(-0.5f32, -0.5f32, -0.5f32);
-    let c2 = Vec3F::new(0.5f32, 0.5f32, 0.5f32);
     let matrix = transform.matrix();
-    let p1 = matrix * swizzle_up(&c1);
-    let p2 = matrix * swizzle_up(&c2);
-    let center = (p1 + p2) / 2f32;
-    let dims = p2 - p1;
+    let mut lows = Vec3F::new(f32::MAX, f32::MAX, f32::MAX);
+    let mut highs = Vec3F::new(f32::MIN, f32::MIN, f32::MIN);
+    for i in 0..8 {
+      let corner = Vec3F::new(
+        if i & 1 == 0 { -0.5f32 } else { 0.5f32 },
+        if i & 2 == 0 { -0.5f32 } else { 0.5f32 },
+        if i & 4 == 0 { -0.5f32 } else { 0.5f32 },
+      );
+      let pt = swizzle_down(&(matrix * swizzle_up(&corner)));
+      lows = Vec3F::new(lows.x.min(pt.x), lows.y.min(pt.y), lows.z.min(pt.z));
+      highs = Vec3F::new(highs.x.max(pt.x), highs.y.max(pt.y), highs.z.max(pt.z));
+    }
     Self {
-      center: swizzle_down(&center),
-      dims: swizzle_down(&dims),
+      center: (lows + highs) / 2f32,
+      dims: highs - lows,
     }
   }
 
+  pub fn center(&self) -> &Vec3F {
+    &self.center
+  }
+
+  // Radius of the sphere enclosing the box, used by the broadphase.
+  pub fn bounding_radius(&self) -> f32 {
+    self.dims.magnitude() / 2f32
+  }
+
   fn within_box(&self, pt: &Vec3F, bl: &Vec3F, tr: &Vec3F) -> bool {
     self.approx_between(&bl.x, &tr.x, &pt.x)
       && self.approx_between(&bl.y, &tr.y, &pt.y)
@@ -33,8 +52,12 @@ impl AxisAlignedCubeCollision {
 }
 
 impl Collision for AxisAlignedCubeCollision {
-  fn distance_to(&self, _pt: &Vec3F) -> f32 {
-    0f32
+  fn distance_to(&self, pt: &Vec3F) -> f32 {
+    box_distance(&(pt - self.center), &(self.dims / 2f32))
+  }
+
+  fn bounding_sphere(&self) -> (Vec3F, f32) {
+    (self.center, self.bounding_radius())
   }
 
   fn sphere_collision(&self, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary> {
@@ -74,3 +97,258 @@ impl Collision for AxisAlignedCubeCollision {
     })
   }
 }
+
+// Signed distance from `offset` (relative to the box centre) to a box with `half_extents`,
+// negative inside.
+fn box_distance(offset: &Vec3F, half_extents: &Vec3F) -> f32 {
+  let q = Vec3F::new(offset.x.abs(), offset.y.abs(), offset.z.abs()) - half_extents;
+  let outside = Vec3F::new(q.x.max(0f32), q.y.max(0f32), q.z.max(0f32)).magnitude();
+  let inside = q.x.max(q.y).max(q.z).min(0f32);
+  outside + inside
+}
+
+// A box that rotates with its transform. Collisions are run against an axis-aligned box in the
+// box's local frame and mapped back out.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct OrientedBoxCollision {
+  center: Vec3F,
+  half_extents: Vec3F,
+  rotation: QuatF,
+}
+
+impl OrientedBoxCollision {
+  pub fn new(center: Vec3F, half_extents: Vec3F, rotation: QuatF) -> Self {
+    Self {
+      center,
+      half_extents,
+      rotation,
+    }
+  }
+
+  pub fn from_transform(transform: &TransformComponent) -> Self {
+    let scale = transform.scale;
+    Self::new(
+      transform.translation,
+      Vec3F::new(scale.x.abs(), scale.y.abs(), scale.z.abs()) / 2f32,
+      transform.rotation,
+    )
+  }
+
+  pub fn center(&self) -> &Vec3F {
+    &self.center
+  }
+
+  fn to_local(&self, v: &Vec3F) -> Vec3F {
+    self.rotation.invert().rotate_vector(*v)
+  }
+
+  fn local_box(&self) -> AxisAlignedCubeCollision {
+    AxisAlignedCubeCollision {
+      center: Vec3F::zero(),
+      dims: self.half_extents * 2f32,
+    }
+  }
+}
+
+impl Collision for OrientedBoxCollision {
+  fn distance_to(&self, pt: &Vec3F) -> f32 {
+    box_distance(&self.to_local(&(pt - self.center)), &self.half_extents)
+  }
+
+  fn bounding_sphere(&self) -> (Vec3F, f32) {
+    (self.center, self.half_extents.magnitude())
+  }
+
+  fn sphere_collision(&self, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary> {
+    let local_center = self.to_local(&(sphere.0 - self.center));
+    let local_velocity = self.to_local(velocity);
+    self
+      .local_box()
+      .sphere_collision((&local_center, sphere.1), &local_velocity)
+      .map(|summary| CollisionSummary {
+        time: summary.time,
+        position: self.center + self.rotation.rotate_vector(summary.position),
+        surface_normal: self.rotation.rotate_vector(summary.surface_normal),
+      })
+  }
+}
+
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct SphereCollision {
+  center: Vec3F,
+  radius: f32,
+}
+
+impl SphereCollision {
+  pub fn new(center: Vec3F, radius: f32) -> Self {
+    Self { center, radius }
+  }
+
+  // The sphere enclosing the transform's unit cube along its longest axis.
+  pub fn from_transform(transform: &TransformComponent) -> Self {
+    let scale = transform.scale;
+    let radius = scale.x.abs().max(scale.y.abs()).max(scale.z.abs()) / 2f32;
+    Self::new(transform.translation, radius)
+  }
+
+  pub fn center(&self) -> &Vec3F {
+    &self.center
+  }
+
+  pub fn radius(&self) -> f32 {
+    self.radius
+  }
+}
+
+impl Collision for SphereCollision {
+  fn distance_to(&self, pt: &Vec3F) -> f32 {
+    (pt - self.center).magnitude() - self.radius
+  }
+
+  fn bounding_sphere(&self) -> (Vec3F, f32) {
+    (self.center, self.radius)
+  }
+
+  fn sphere_collision(&self, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary> {
+    sweep_point_sphere(sphere.0, velocity, &self.center, self.radius + sphere.1).map(|time| {
+      let position = sphere.0 + velocity * time;
+      CollisionSummary {
+        time,
+        position,
+        surface_normal: (position - self.center).normalize(),
+      }
+    })
+  }
+}
+
+// Every point within `radius` of the segment from `start` to `end`.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct CapsuleCollision {
+  start: Vec3F,
+  end: Vec3F,
+  radius: f32,
+}
+
+impl CapsuleCollision {
+  pub fn new(start: Vec3F, end: Vec3F, radius: f32) -> Self {
+    Self { start, end, radius }
+  }
+
+  // An upright capsule filling the transform's unit cube: the radius comes from the wider of the
+  // x and z scales and the segment runs along the transform's up axis.
+  pub fn from_transform(transform: &TransformComponent) -> Self {
+    let scale = transform.scale;
+    let radius = scale.x.abs().max(scale.z.abs()) / 2f32;
+    let half_length = (scale.y.abs() / 2f32 - radius).max(0f32);
+    let axis = transform.up() * half_length;
+    Self::new(transform.translation - axis, transform.translation + axis, radius)
+  }
+
+  pub fn segment(&self) -> (&Vec3F, &Vec3F) {
+    (&self.start, &self.end)
+  }
+
+  pub fn radius(&self) -> f32 {
+    self.radius
+  }
+}
+
+impl Collision for CapsuleCollision {
+  fn distance_to(&self, pt: &Vec3F) -> f32 {
+    (pt - closest_point_on_segment(pt, &self.start, &self.end)).magnitude() - self.radius
+  }
+
+  fn bounding_sphere(&self) -> (Vec3F, f32) {
+    (
+      (self.start + self.end) / 2f32,
+      (self.end - self.start).magnitude() / 2f32 + self.radius,
+    )
+  }
+
+  fn sphere_collision(&self, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary> {
+    sweep_point_capsule(sphere.0, velocity, (&self.start, &self.end), self.radius + sphere.1).map(
+      |(time, surface_normal)| CollisionSummary {
+        time,
+        position: sphere.0 + velocity * time,
+        surface_normal,
+      },
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::assert_close;
+  use cgmath::{Deg, Rotation3};
+
+  fn transform(translation: Vec3F, scale: Vec3F, rotation: QuatF) -> TransformComponent {
+    TransformComponent::new(translation, scale, rotation)
+  }
+
+  #[test]
+  fn aabb_encloses_rotated_transform() {
+    let rotation = QuatF::from_angle_z(Deg(45f32));
+    let aabb = AxisAlignedCubeCollision::from_transform(&transform(
+      Vec3F::new(1f32, 0f32, 0f32),
+      Vec3F::new(2f32, 2f32, 2f32),
+      rotation,
+    ));
+    assert_close(aabb.dims.x, 2f32 * 2f32.sqrt());
+    assert_close(aabb.dims.y, 2f32 * 2f32.sqrt());
+    assert_close(aabb.dims.z, 2f32);
+    assert_close(aabb.center.x, 1f32);
+    assert_close(aabb.distance_to(&Vec3F::new(1f32, 0f32, 3f32)), 2f32);
+    assert_close(aabb.distance_to(&Vec3F::new(1f32, 0f32, 0.5f32)), -0.5f32);
+  }
+
+  #[test]
+  fn oriented_box_follows_rotation() {
+    let obb = OrientedBoxCollision::from_transform(&transform(
+      Vec3F::zero(),
+      Vec3F::new(2f32, 2f32, 2f32),
+      QuatF::from_angle_z(Deg(45f32)),
+    ));
+    // The corner points along +x after the rotation.
+    assert_close(obb.distance_to(&Vec3F::new(3f32, 0f32, 0f32)), 3f32 - 2f32.sqrt());
+    assert_close(obb.distance_to(&Vec3F::zero()), -1f32);
+    let summary = obb
+      .sphere_collision(
+        (&Vec3F::new(5f32, 5f32, 0f32), &0.5f32),
+        &Vec3F::new(-1f32, -1f32, 0f32),
+      )
+      .unwrap();
+    let normal = Vec3F::new(1f32, 1f32, 0f32).normalize();
+    assert_close(summary.surface_normal.dot(normal), 1f32);
+    assert_close(summary.position.dot(normal), 1.5f32);
+  }
+
+  #[test]
+  fn sphere_distance_and_contact() {
+    let sphere = SphereCollision::new(Vec3F::new(0f32, 4f32, 0f32), 1f32);
+    assert_close(sphere.distance_to(&Vec3F::zero()), 3f32);
+    assert_close(sphere.distance_to(&Vec3F::new(0f32, 4f32, 0f32)), -1f32);
+    let summary = sphere
+      .sphere_collision((&Vec3F::zero(), &0.5f32), &Vec3F::unit_y())
+      .unwrap();
+    assert_close(summary.time, 2.5f32);
+    assert_eq!(summary.surface_normal, -Vec3F::unit_y());
+  }
+
+  #[test]
+  fn capsule_distance_and_contact() {
+    let capsule =
+      CapsuleCollision::from_transform(&transform(Vec3F::zero(), Vec3F::new(1f32, 4f32, 1f32), QuatF::one()));
+    assert_close(capsule.radius(), 0.5f32);
+    assert_close(capsule.distance_to(&Vec3F::new(2f32, 1f32, 0f32)), 1.5f32);
+    assert_close(capsule.distance_to(&Vec3F::new(0f32, 4f32, 0f32)), 2f32);
+    let summary = capsule
+      .sphere_collision((&Vec3F::new(0f32, 10f32, 0f32), &0.5f32), &-Vec3F::unit_y())
+      .unwrap();
+    assert_close(summary.position.y, 2.5f32);
+    assert_eq!(summary.surface_normal, Vec3F::unit_y());
+  }
+}
@@ -6,7 +6,9 @@ use specs::{Component, VecStorage};
 
 use cgmath::prelude::*;
 
+use crate::events::StatefulEventChannel;
 use crate::physics::TransformComponent;
+use crate::relativity::RelativisticObserver;
 use crate::utils::Vec3F;
 
 pub type CollisionQueue = BinaryHeap<CollisionSummary>;
@@ -14,8 +16,29 @@ pub type CollisionQueue = BinaryHeap<CollisionSummary>;
 pub trait Collision: Component {
   fn sphere_collision(&self, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary>;
 
+  // Signed distance from `pt` to the collider's surface, negative inside.
   fn distance_to(&self, pt: &Vec3F) -> f32;
 
+  // Centre and radius of a sphere enclosing the collider, used by the broadphase.
+  fn bounding_sphere(&self) -> (Vec3F, f32);
+
+  // Collides against where the observer sees this collider rather than where it is. The sphere
+  // is given in apparent space and the returned contact point is mapped back into it.
+  fn apparent_sphere_collision(
+    &self,
+    sphere: (&Vec3F, &f32),
+    velocity: &Vec3F,
+    observer: &RelativisticObserver,
+  ) -> Option<CollisionSummary> {
+    let true_center = observer.true_position(sphere.0);
+    self
+      .sphere_collision((&true_center, sphere.1), velocity)
+      .map(|mut summary| {
+        summary.position = observer.apparent_position(&summary.position);
+        summary
+      })
+  }
+
   // Some helper functions for traits
   fn between(&self, left: &f32, right: &f32, center: &f32) -> bool {
     // println!("{} {} {}", left, center, right);
@@ -83,6 +106,23 @@ impl PartialOrd for CollisionSummary {
   }
 }
 
+// Gameplay systems subscribe either to every collision or to those involving one entity.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum CollisionTopic {
+  All,
+  Involving(Entity),
+}
+
+// A `CanCollide` sphere hitting a collider within the next timestep.
+#[derive(Debug, Clone, Copy)]
+pub struct CollisionEvent {
+  pub sphere: Entity,
+  pub collider: Entity,
+  pub summary: CollisionSummary,
+}
+
+pub type CollisionEventChannel = StatefulEventChannel<CollisionTopic, CollisionEvent>;
+
 #[derive(Component, Debug, Clone)]
 #[storage(VecStorage)]
 pub struct CanCollide {
@@ -1,3 +1,4 @@
+use crate::physics::DEFAULT_DRAG_COEFFICIENT;
 use crate::utils::*;
 use cgmath::{prelude::*, Euler, Basis3};
 use cgmath::{Deg, Rad, Rotation3};
@@ -82,6 +83,20 @@ impl TransformComponent {
     Vec3F::unit_y()
   }
 
+  // Blends towards `other` by `alpha` in [0, 1]; rotation follows the shortest arc.
+  pub fn lerp(&self, other: &Self, alpha: f32) -> Self {
+    let target = if self.rotation.dot(other.rotation) < 0f32 {
+      -other.rotation
+    } else {
+      other.rotation
+    };
+    Self {
+      translation: self.translation.lerp(other.translation, alpha),
+      scale: self.scale.lerp(other.scale, alpha),
+      rotation: self.rotation.nlerp(target, alpha),
+    }
+  }
+
   pub fn matrix_buffer(&self) -> [f32; 10] {
     [
       self.translation.x,
@@ -104,55 +119,94 @@ impl Default for TransformComponent {
   }
 }
 
-#[derive(Component, Debug, Clone, Default)]
-#[storage(NullStorage)]
-pub struct Drag;
+// The transform as of the previous physics tick, recorded for bodies moved by the fixed-timestep
+// physics loop.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct PreviousTransform(pub TransformComponent);
 
+// Where a body is drawn this frame, between its `PreviousTransform` and `TransformComponent`.
+// Used by the renderer in place of the `TransformComponent`.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct InterpolatedTransform(pub TransformComponent);
+
+// Quadratic air resistance, scaled by the `ForceFields` drag scale.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct Drag {
+  pub coefficient: f32,
+}
+
+impl Default for Drag {
+  fn default() -> Self {
+    Self {
+      coefficient: DEFAULT_DRAG_COEFFICIENT,
+    }
+  }
+}
+
+// Opts a body into the fields of the `ForceFields` resource.
 #[derive(Component, Debug, Clone, Default)]
 #[storage(NullStorage)]
 pub struct Gravity;
 
+// Angular quantities are world-space axis-angle vectors: the direction is the rotation axis and
+// the magnitude the rate in radians per second (or per second squared). `force` and `torque`
+// accumulate over a frame alongside `acceleration`, and are turned into accelerations using the
+// body's `Mass` and `Inertia`.
 #[derive(Component, Debug, Clone)]
 #[storage(VecStorage)]
 pub struct RigidBody {
   pub velocity: Vec3F,
   pub acceleration: Vec3F,
-  pub angular_velocity: QuatF,     // Euler Angles
-  pub angular_acceleration: QuatF, // Euler Angles
+  pub angular_velocity: Vec3F,
+  pub angular_acceleration: Vec3F,
+  pub force: Vec3F,
+  pub torque: Vec3F,
 }
 
 impl Default for RigidBody {
   fn default() -> Self {
-    Self {
-      velocity: Vec3F::zero(),
-      acceleration: Vec3F::zero(),
-      angular_velocity: QuatF::one(),
-      angular_acceleration: QuatF::one(),
-    }
+    Self::new_stationary()
   }
 }
 
 impl RigidBody {
   pub fn new_stationary() -> Self {
-    Self {
-      velocity: Vec3F::zero(),
-      acceleration: Vec3F::zero(),
-      angular_velocity: QuatF::one(),
-      angular_acceleration: QuatF::one(),
-    }
+    Self::new(Vec3F::zero(), Vec3F::zero(), Vec3F::zero(), Vec3F::zero())
   }
 
-  pub fn new(velocity: Vec3F, acceleration: Vec3F, angular_velocity: QuatF, angular_acceleration: QuatF) -> Self {
+  pub fn new(velocity: Vec3F, acceleration: Vec3F, angular_velocity: Vec3F, angular_acceleration: Vec3F) -> Self {
     Self {
       velocity,
       acceleration,
       angular_velocity,
       angular_acceleration,
+      force: Vec3F::zero(),
+      torque: Vec3F::zero(),
     }
   }
 
+  // Force through the centre of mass.
+  pub fn apply_force(&mut self, force: Vec3F) {
+    self.force += force;
+  }
+
+  // Force applied at a world-space `point`, which also spins the body about its `center_of_mass`.
+  pub fn apply_force_at_point(&mut self, force: Vec3F, point: &Vec3F, center_of_mass: &Vec3F) {
+    self.force += force;
+    self.torque += (point - center_of_mass).cross(force);
+  }
+
+  pub fn apply_torque(&mut self, torque: Vec3F) {
+    self.torque += torque;
+  }
+
   pub fn reset_acceleration(&mut self) {
     self.acceleration = Vec3F::zero();
-    self.angular_acceleration = QuatF::zero();
+    self.angular_acceleration = Vec3F::zero();
+    self.force = Vec3F::zero();
+    self.torque = Vec3F::zero();
   }
 }
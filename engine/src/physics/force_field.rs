@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use cgmath::prelude::*;
+
+use crate::utils::Vec3F;
+
+pub const STANDARD_GRAVITY: Vec3F = Vec3F::new(0f32, -14f32, 0f32);
+
+// Drag coefficient given to bodies that don't set their own.
+pub const DEFAULT_DRAG_COEFFICIENT: f32 = 6f32 / 36f32 / 36f32;
+
+// Acceleration as a function of position and velocity.
+pub type FieldFn = Arc<dyn Fn(&Vec3F, &Vec3F) -> Vec3F + Send + Sync>;
+
+#[derive(Clone)]
+pub enum ForceField {
+  // The same acceleration everywhere, like gravity near a planet's surface.
+  Uniform(Vec3F),
+  // Inverse-square attraction towards `position`, with `strength` = G M. `softening` keeps the
+  // acceleration finite as a body passes through the centre.
+  PointMass {
+    position: Vec3F,
+    strength: f32,
+    softening: f32,
+  },
+  Custom(FieldFn),
+}
+
+impl ForceField {
+  pub fn point_mass(position: Vec3F, strength: f32) -> Self {
+    ForceField::PointMass {
+      position,
+      strength,
+      softening: 0f32,
+    }
+  }
+
+  pub fn custom<F>(field: F) -> Self
+  where
+    F: Fn(&Vec3F, &Vec3F) -> Vec3F + Send + Sync + 'static,
+  {
+    ForceField::Custom(Arc::new(field))
+  }
+
+  pub fn acceleration_at(&self, position: &Vec3F, velocity: &Vec3F) -> Vec3F {
+    match self {
+      ForceField::Uniform(acceleration) => *acceleration,
+      ForceField::PointMass {
+        position: center,
+        strength,
+        softening,
+      } => {
+        let offset = center - position;
+        let r2 = offset.magnitude2() + softening * softening;
+        if r2 <= 0f32 {
+          Vec3F::zero()
+        } else {
+          offset * (strength / (r2 * r2.sqrt()))
+        }
+      }
+      ForceField::Custom(field) => field(position, velocity),
+    }
+  }
+}
+
+// Every force field acting on bodies marked with `Gravity`, keyed by name so they can be replaced
+// at runtime. Defaults to a single uniform "gravity" field.
+#[derive(Clone)]
+pub struct ForceFields {
+  fields: Vec<(String, ForceField)>,
+  // Multiplies every body's `Drag` coefficient.
+  pub drag_scale: f32,
+}
+
+impl Default for ForceFields {
+  fn default() -> Self {
+    Self {
+      fields: vec![("gravity".to_string(), ForceField::Uniform(STANDARD_GRAVITY))],
+      drag_scale: 1f32,
+    }
+  }
+}
+
+impl ForceFields {
+  pub fn empty() -> Self {
+    Self {
+      fields: Vec::new(),
+      drag_scale: 1f32,
+    }
+  }
+
+  // Adds the field, replacing any existing field with the same name.
+  pub fn insert(&mut self, name: &str, field: ForceField) {
+    match self.fields.iter_mut().find(|(n, _)| n == name) {
+      Some(entry) => entry.1 = field,
+      None => self.fields.push((name.to_string(), field)),
+    }
+  }
+
+  pub fn remove(&mut self, name: &str) -> Option<ForceField> {
+    let index = self.fields.iter().position(|(n, _)| n == name)?;
+    Some(self.fields.remove(index).1)
+  }
+
+  pub fn get(&self, name: &str) -> Option<&ForceField> {
+    self.fields.iter().find(|(n, _)| n == name).map(|(_, f)| f)
+  }
+
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.fields.iter().map(|(n, _)| n.as_str())
+  }
+
+  pub fn acceleration_at(&self, position: &Vec3F, velocity: &Vec3F) -> Vec3F {
+    self
+      .fields
+      .iter()
+      .fold(Vec3F::zero(), |acc, (_, f)| acc + f.acceleration_at(position, velocity))
+  }
+
+  // Quadratic drag, a = -k |v| v.
+  pub fn drag_acceleration(&self, coefficient: f32, velocity: &Vec3F) -> Vec3F {
+    velocity * -(coefficient * self.drag_scale * velocity.magnitude())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn point_mass_follows_inverse_square() {
+    let field = ForceField::point_mass(Vec3F::zero(), 8f32);
+    let near = field.acceleration_at(&Vec3F::new(2f32, 0f32, 0f32), &Vec3F::zero());
+    let far = field.acceleration_at(&Vec3F::new(4f32, 0f32, 0f32), &Vec3F::zero());
+    assert_eq!(near, Vec3F::new(-2f32, 0f32, 0f32));
+    assert_eq!(far, Vec3F::new(-0.5f32, 0f32, 0f32));
+    let softened = ForceField::PointMass {
+      position: Vec3F::zero(),
+      strength: 8f32,
+      softening: 1f32,
+    };
+    assert_eq!(softened.acceleration_at(&Vec3F::zero(), &Vec3F::zero()), Vec3F::zero());
+  }
+
+  #[test]
+  fn fields_sum_and_replace_by_name() {
+    let mut fields = ForceFields::default();
+    fields.insert("wind", ForceField::custom(|_, v| v * -0.5f32));
+    let velocity = Vec3F::new(2f32, 0f32, 0f32);
+    assert_eq!(
+      fields.acceleration_at(&Vec3F::zero(), &velocity),
+      STANDARD_GRAVITY + Vec3F::new(-1f32, 0f32, 0f32)
+    );
+    fields.insert("gravity", ForceField::Uniform(Vec3F::zero()));
+    assert_eq!(fields.names().count(), 2);
+    assert_eq!(
+      fields.acceleration_at(&Vec3F::zero(), &velocity),
+      Vec3F::new(-1f32, 0f32, 0f32)
+    );
+    assert!(fields.remove("wind").is_some());
+    assert_eq!(fields.acceleration_at(&Vec3F::zero(), &velocity), Vec3F::zero());
+  }
+
+  #[test]
+  fn drag_opposes_motion_quadratically() {
+    let mut fields = ForceFields::empty();
+    fields.drag_scale = 2f32;
+    let drag = fields.drag_acceleration(0.5f32, &Vec3F::new(0f32, 3f32, 0f32));
+    assert_eq!(drag, Vec3F::new(0f32, -9f32, 0f32));
+  }
+}
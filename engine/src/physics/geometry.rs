@@ -0,0 +1,234 @@
+use cgmath::prelude::*;
+
+use crate::utils::Vec3F;
+
+const PARALLEL_TOLERANCE: f32 = 1e-8f32;
+
+pub fn closest_point_on_segment(pt: &Vec3F, a: &Vec3F, b: &Vec3F) -> Vec3F {
+  let ab = b - a;
+  let len2 = ab.magnitude2();
+  if len2 < PARALLEL_TOLERANCE {
+    return *a;
+  }
+  let t = ((pt - a).dot(ab) / len2).clamp(0f32, 1f32);
+  a + ab * t
+}
+
+// The part of a triangle (a, b, c) that a closest point lies on. Corners are numbered a, b, c
+// and edge `i` runs from corner `i` to the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriangleFeature {
+  Vertex(usize),
+  Edge(usize),
+  Face,
+}
+
+// Closest point to `pt` on the triangle (a, b, c), following Ericson's Real-Time Collision
+// Detection 5.1.5.
+pub fn closest_point_on_triangle(pt: &Vec3F, a: &Vec3F, b: &Vec3F, c: &Vec3F) -> Vec3F {
+  closest_feature_on_triangle(pt, a, b, c).0
+}
+
+// `closest_point_on_triangle`, along with the feature the point lies on.
+pub fn closest_feature_on_triangle(pt: &Vec3F, a: &Vec3F, b: &Vec3F, c: &Vec3F) -> (Vec3F, TriangleFeature) {
+  let ab = b - a;
+  let ac = c - a;
+  let ap = pt - a;
+  let d1 = ab.dot(ap);
+  let d2 = ac.dot(ap);
+  if d1 <= 0f32 && d2 <= 0f32 {
+    return (*a, TriangleFeature::Vertex(0));
+  }
+  let bp = pt - b;
+  let d3 = ab.dot(bp);
+  let d4 = ac.dot(bp);
+  if d3 >= 0f32 && d4 <= d3 {
+    return (*b, TriangleFeature::Vertex(1));
+  }
+  let vc = d1 * d4 - d3 * d2;
+  if vc <= 0f32 && d1 >= 0f32 && d3 <= 0f32 {
+    return (a + ab * (d1 / (d1 - d3)), TriangleFeature::Edge(0));
+  }
+  let cp = pt - c;
+  let d5 = ab.dot(cp);
+  let d6 = ac.dot(cp);
+  if d6 >= 0f32 && d5 <= d6 {
+    return (*c, TriangleFeature::Vertex(2));
+  }
+  let vb = d5 * d2 - d1 * d6;
+  if vb <= 0f32 && d2 >= 0f32 && d6 <= 0f32 {
+    return (a + ac * (d2 / (d2 - d6)), TriangleFeature::Edge(2));
+  }
+  let va = d3 * d6 - d5 * d4;
+  if va <= 0f32 && (d4 - d3) >= 0f32 && (d5 - d6) >= 0f32 {
+    return (
+      b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6))),
+      TriangleFeature::Edge(1),
+    );
+  }
+  let denom = 1f32 / (va + vb + vc);
+  (a + ab * (vb * denom) + ac * (vc * denom), TriangleFeature::Face)
+}
+
+// Earliest time at which a point moving from `origin` with `velocity` comes within `radius`
+// of `center`. Points that start inside the sphere or are moving away never hit it.
+pub fn sweep_point_sphere(origin: &Vec3F, velocity: &Vec3F, center: &Vec3F, radius: f32) -> Option<f32> {
+  let m = origin - center;
+  let a = velocity.magnitude2();
+  let b = m.dot(*velocity);
+  let c = m.magnitude2() - radius * radius;
+  if c <= 0f32 || b >= 0f32 || a < PARALLEL_TOLERANCE {
+    return None;
+  }
+  let discriminant = b * b - a * c;
+  if discriminant < 0f32 {
+    None
+  } else {
+    Some((-b - discriminant.sqrt()) / a)
+  }
+}
+
+// Earliest time at which a point moving from `origin` with `velocity` comes within `radius` of
+// the segment (a, b), along with the outward surface normal at that moment.
+pub fn sweep_point_capsule(
+  origin: &Vec3F,
+  velocity: &Vec3F,
+  (a, b): (&Vec3F, &Vec3F),
+  radius: f32,
+) -> Option<(f32, Vec3F)> {
+  if (origin - closest_point_on_segment(origin, a, b)).magnitude2() <= radius * radius {
+    return None;
+  }
+  let d = b - a;
+  let m = origin - a;
+  let dd = d.dot(d);
+  let md = m.dot(d);
+  let nd = velocity.dot(d);
+  let nn = velocity.dot(*velocity);
+  let mn = m.dot(*velocity);
+  // Side of the infinite cylinder around the segment.
+  let qa = dd * nn - nd * nd;
+  let qb = dd * mn - nd * md;
+  let qc = dd * (m.magnitude2() - radius * radius) - md * md;
+  let mut best: Option<f32> = None;
+  if qa.abs() > PARALLEL_TOLERANCE {
+    let discriminant = qb * qb - qa * qc;
+    if discriminant >= 0f32 {
+      let t = (-qb - discriminant.sqrt()) / qa;
+      let s = md + t * nd;
+      if t >= 0f32 && s >= 0f32 && s <= dd {
+        best = Some(t);
+      }
+    }
+  }
+  // Hemispherical end caps.
+  for end in [a, b].iter() {
+    if let Some(t) = sweep_point_sphere(origin, velocity, end, radius) {
+      best = Some(best.map_or(t, |b| b.min(t)));
+    }
+  }
+  best.map(|t| {
+    let contact = origin + velocity * t;
+    let normal = (contact - closest_point_on_segment(&contact, a, b)).normalize();
+    (t, normal)
+  })
+}
+
+// Earliest time at which a sphere of `radius` moving from `origin` with `velocity` touches the
+// triangle (a, b, c), along with the surface normal at that moment.
+pub fn sweep_sphere_triangle(
+  origin: &Vec3F,
+  velocity: &Vec3F,
+  radius: f32,
+  (a, b, c): (&Vec3F, &Vec3F, &Vec3F),
+) -> Option<(f32, Vec3F)> {
+  let mut best: Option<(f32, Vec3F)> = None;
+  let face_normal = (b - a).cross(c - a);
+  if face_normal.magnitude2() > PARALLEL_TOLERANCE {
+    let mut n = face_normal.normalize();
+    let mut distance = (origin - a).dot(n);
+    if distance < 0f32 {
+      n = -n;
+      distance = -distance;
+    }
+    let approach = velocity.dot(n);
+    if distance > radius && approach < 0f32 {
+      let t = (distance - radius) / -approach;
+      let touch = origin + velocity * t - n * radius;
+      if (closest_point_on_triangle(&touch, a, b, c) - touch).magnitude2() < 1e-8f32 {
+        best = Some((t, n));
+      }
+    }
+  }
+  if best.is_none() {
+    // Missed the face, so the first contact (if any) is on an edge or a vertex.
+    for (p, q) in [(a, b), (b, c), (c, a)].iter() {
+      match (best, sweep_point_capsule(origin, velocity, (p, q), radius)) {
+        (Some((bt, _)), Some((t, _))) if bt <= t => {}
+        (_, Some(hit)) => best = Some(hit),
+        _ => {}
+      }
+    }
+  }
+  best
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::assert_close;
+
+  #[test]
+  fn closest_point_on_triangle_handles_each_region() {
+    let a = Vec3F::new(0f32, 0f32, 0f32);
+    let b = Vec3F::new(2f32, 0f32, 0f32);
+    let c = Vec3F::new(0f32, 2f32, 0f32);
+    let inside = closest_point_on_triangle(&Vec3F::new(0.5f32, 0.5f32, 3f32), &a, &b, &c);
+    assert_eq!(inside, Vec3F::new(0.5f32, 0.5f32, 0f32));
+    let vertex = closest_point_on_triangle(&Vec3F::new(-1f32, -1f32, 0f32), &a, &b, &c);
+    assert_eq!(vertex, a);
+    let edge = closest_point_on_triangle(&Vec3F::new(2f32, 2f32, 0f32), &a, &b, &c);
+    assert_eq!(edge, Vec3F::new(1f32, 1f32, 0f32));
+    let feature = |pt: Vec3F| closest_feature_on_triangle(&pt, &a, &b, &c).1;
+    assert_eq!(feature(Vec3F::new(0.5f32, 0.5f32, 3f32)), TriangleFeature::Face);
+    assert_eq!(feature(Vec3F::new(-1f32, -1f32, 0f32)), TriangleFeature::Vertex(0));
+    assert_eq!(feature(Vec3F::new(3f32, -1f32, 0f32)), TriangleFeature::Vertex(1));
+    assert_eq!(feature(Vec3F::new(1f32, -1f32, 0f32)), TriangleFeature::Edge(0));
+    assert_eq!(feature(Vec3F::new(2f32, 2f32, 0f32)), TriangleFeature::Edge(1));
+    assert_eq!(feature(Vec3F::new(-1f32, 1f32, 0f32)), TriangleFeature::Edge(2));
+  }
+
+  #[test]
+  fn sweep_point_sphere_hits_front_surface() {
+    let t = sweep_point_sphere(&Vec3F::zero(), &Vec3F::unit_x(), &Vec3F::new(5f32, 0f32, 0f32), 1f32);
+    assert_close(t.unwrap(), 4f32);
+    assert!(sweep_point_sphere(&Vec3F::zero(), &-Vec3F::unit_x(), &Vec3F::new(5f32, 0f32, 0f32), 1f32).is_none());
+  }
+
+  #[test]
+  fn sweep_point_capsule_hits_side_and_caps() {
+    let a = Vec3F::new(5f32, -1f32, 0f32);
+    let b = Vec3F::new(5f32, 1f32, 0f32);
+    let (t, n) = sweep_point_capsule(&Vec3F::zero(), &Vec3F::unit_x(), (&a, &b), 1f32).unwrap();
+    assert_close(t, 4f32);
+    assert_eq!(n, -Vec3F::unit_x());
+    let (t, n) = sweep_point_capsule(&Vec3F::new(5f32, 10f32, 0f32), &-Vec3F::unit_y(), (&a, &b), 1f32).unwrap();
+    assert_close(t, 8f32);
+    assert_eq!(n, Vec3F::unit_y());
+  }
+
+  #[test]
+  fn sweep_sphere_triangle_hits_face_then_edge() {
+    let a = Vec3F::new(-1f32, -1f32, 0f32);
+    let b = Vec3F::new(1f32, -1f32, 0f32);
+    let c = Vec3F::new(0f32, 1f32, 0f32);
+    let (t, n) = sweep_sphere_triangle(&Vec3F::new(0f32, 0f32, 5f32), &-Vec3F::unit_z(), 0.5f32, (&a, &b, &c)).unwrap();
+    assert_close(t, 4.5f32);
+    assert_eq!(n, Vec3F::unit_z());
+    // Passing just below the bottom edge only grazes it.
+    let origin = Vec3F::new(0f32, -1.3f32, 5f32);
+    let (t, n) = sweep_sphere_triangle(&origin, &-Vec3F::unit_z(), 0.5f32, (&a, &b, &c)).unwrap();
+    assert_close(t, 5f32 - 0.4f32);
+    assert!(n.y < 0f32 && n.z > 0f32);
+  }
+}
@@ -0,0 +1,116 @@
+use cgmath::{InnerSpace, Matrix, One, Rad, Rotation3, SquareMatrix, Zero};
+use specs::{Component, VecStorage};
+
+use crate::utils::{Mat3F, QuatF, Vec3F};
+
+const MIN_ANGULAR_SPEED: f32 = 1e-8f32;
+
+// Moment of inertia about the centre of mass, in the body's local frame. Bodies without one
+// treat torque directly as angular acceleration.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[storage(VecStorage)]
+pub struct Inertia {
+  local: Mat3F,
+  inverse_local: Mat3F,
+}
+
+impl Inertia {
+  pub fn new(local: Mat3F) -> Self {
+    Self {
+      local,
+      inverse_local: local.invert().unwrap_or_else(Mat3F::zero),
+    }
+  }
+
+  pub fn solid_sphere(mass: f32, radius: f32) -> Self {
+    let i = 0.4f32 * mass * radius * radius;
+    Self::new(Mat3F::from_diagonal(Vec3F::new(i, i, i)))
+  }
+
+  // A cuboid with side lengths `dims` along its local axes.
+  pub fn solid_box(mass: f32, dims: &Vec3F) -> Self {
+    let (x2, y2, z2) = (dims.x * dims.x, dims.y * dims.y, dims.z * dims.z);
+    let k = mass / 12f32;
+    Self::new(Mat3F::from_diagonal(Vec3F::new(
+      k * (y2 + z2),
+      k * (x2 + z2),
+      k * (x2 + y2),
+    )))
+  }
+
+  pub fn local(&self) -> &Mat3F {
+    &self.local
+  }
+
+  // The tensor for a body currently at `rotation`, R I R^T.
+  pub fn world(&self, rotation: &QuatF) -> Mat3F {
+    let r = Mat3F::from(*rotation);
+    r * self.local * r.transpose()
+  }
+
+  pub fn world_inverse(&self, rotation: &QuatF) -> Mat3F {
+    let r = Mat3F::from(*rotation);
+    r * self.inverse_local * r.transpose()
+  }
+
+  pub fn angular_momentum(&self, rotation: &QuatF, angular_velocity: &Vec3F) -> Vec3F {
+    self.world(rotation) * angular_velocity
+  }
+
+  // Euler's rotation equations in the world frame, I dw/dt = torque - w x (I w). The second term
+  // is what makes an asymmetric body tumble with no torque applied.
+  pub fn angular_acceleration(&self, rotation: &QuatF, angular_velocity: &Vec3F, torque: &Vec3F) -> Vec3F {
+    let gyroscopic = angular_velocity.cross(self.angular_momentum(rotation, angular_velocity));
+    self.world_inverse(rotation) * (torque - gyroscopic)
+  }
+}
+
+// The rotation made by spinning at `angular_velocity` for `dt` seconds, to be applied on the left
+// of the current orientation.
+pub fn angular_displacement(angular_velocity: &Vec3F, dt: f32) -> QuatF {
+  let speed = angular_velocity.magnitude();
+  if speed < MIN_ANGULAR_SPEED {
+    QuatF::one()
+  } else {
+    QuatF::from_axis_angle(angular_velocity / speed, Rad(speed * dt))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::assert_vec_close;
+  use cgmath::{Deg, Rotation, Rotation3};
+
+  #[test]
+  fn box_tensor_follows_rotation() {
+    let inertia = Inertia::solid_box(12f32, &Vec3F::new(1f32, 2f32, 3f32));
+    assert_eq!(inertia.local().x.x, 13f32);
+    assert_eq!(inertia.local().y.y, 10f32);
+    assert_eq!(inertia.local().z.z, 5f32);
+    // Turned a quarter about z, the local x and y moments swap places.
+    let rotation = QuatF::from_angle_z(Deg(90f32));
+    let world = inertia.world(&rotation);
+    assert!((world.x.x - 10f32).abs() < 1e-4f32);
+    assert!((world.y.y - 13f32).abs() < 1e-4f32);
+    let identity = world * inertia.world_inverse(&rotation);
+    assert_vec_close(identity * Vec3F::unit_x(), Vec3F::unit_x());
+  }
+
+  #[test]
+  fn torque_about_principal_axis() {
+    let inertia = Inertia::solid_sphere(5f32, 1f32);
+    let alpha = inertia.angular_acceleration(&QuatF::one(), &Vec3F::zero(), &Vec3F::new(0f32, 4f32, 0f32));
+    assert_vec_close(alpha, Vec3F::new(0f32, 2f32, 0f32));
+    // A symmetric body spinning freely stays spinning the same way.
+    let free = inertia.angular_acceleration(&QuatF::one(), &Vec3F::new(1f32, 2f32, 3f32), &Vec3F::zero());
+    assert_vec_close(free, Vec3F::zero());
+  }
+
+  #[test]
+  fn angular_displacement_rotates_about_the_axis() {
+    let half_turn = angular_displacement(&Vec3F::new(0f32, std::f32::consts::PI, 0f32), 1f32);
+    assert_vec_close(half_turn.rotate_vector(Vec3F::unit_x()), -Vec3F::unit_x());
+    assert_eq!(angular_displacement(&Vec3F::zero(), 1f32), QuatF::one());
+  }
+}
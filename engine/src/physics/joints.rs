@@ -0,0 +1,351 @@
+use cgmath::prelude::*;
+use specs::{Component, Entity, VecStorage};
+
+use crate::physics::{angular_displacement, Inertia, Mass, RigidBody, TransformComponent};
+use crate::utils::{Mat3F, QuatF, Vec3F};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JointKind {
+  // Keeps the anchors `length` apart, like a rigid rod. Both bodies turn freely.
+  Distance { length: f32 },
+  // Holds the anchors together; both bodies turn freely about them.
+  BallSocket,
+  // A ball socket that only lets the bodies turn relative to each other about `axis`, given in
+  // this body's local frame.
+  Hinge { axis: Vec3F },
+  // Holds the anchors together and the relative orientation the bodies had when the joint was
+  // first solved.
+  Fixed,
+}
+
+// Links this entity's `RigidBody` to another body. `connected` defaults to the entity's parent in
+// its `EntityTree`, and with no parent the joint pins it to the world, with `connected_anchor` in
+// world space. Bodies without a `RigidBody` take part but never move. A joint is skipped while its
+// connected entity is deleted or has no `TransformComponent`.
+#[derive(Component, Debug, Clone, PartialEq)]
+#[storage(VecStorage)]
+pub struct Joint {
+  pub kind: JointKind,
+  pub connected: Option<Entity>,
+  // Attachment points in each body's local frame.
+  pub anchor: Vec3F,
+  pub connected_anchor: Vec3F,
+  // Orientation of this body relative to the connected one, captured on the first solve.
+  reference: Option<QuatF>,
+}
+
+impl Joint {
+  pub fn new(kind: JointKind, anchor: Vec3F, connected_anchor: Vec3F) -> Self {
+    Self {
+      kind,
+      connected: None,
+      anchor,
+      connected_anchor,
+      reference: None,
+    }
+  }
+
+  pub fn distance(length: f32, anchor: Vec3F, connected_anchor: Vec3F) -> Self {
+    Self::new(JointKind::Distance { length }, anchor, connected_anchor)
+  }
+
+  pub fn ball_socket(anchor: Vec3F, connected_anchor: Vec3F) -> Self {
+    Self::new(JointKind::BallSocket, anchor, connected_anchor)
+  }
+
+  pub fn hinge(axis: Vec3F, anchor: Vec3F, connected_anchor: Vec3F) -> Self {
+    assert!(axis.magnitude2() > 0f32, "A hinge joint needs a non-zero axis");
+    Self::new(JointKind::Hinge { axis: axis.normalize() }, anchor, connected_anchor)
+  }
+
+  pub fn fixed(anchor: Vec3F, connected_anchor: Vec3F) -> Self {
+    Self::new(JointKind::Fixed, anchor, connected_anchor)
+  }
+
+  pub fn connected_to(mut self, entity: Entity) -> Self {
+    self.connected = Some(entity);
+    self
+  }
+
+  // Remembers the current relative orientation for hinge and fixed joints, if not already set.
+  pub fn capture_reference(&mut self, body: &QuatF, connected: &QuatF) {
+    if self.reference.is_none() {
+      self.reference = Some(connected.conjugate() * body);
+    }
+  }
+}
+
+pub struct JointSolver {
+  pub iterations: usize,
+  // Fraction of the positional error fed back into the velocities each tick (Baumgarte).
+  pub stiffness: f32,
+}
+
+impl Default for JointSolver {
+  fn default() -> Self {
+    Self {
+      iterations: 10,
+      stiffness: 0.2f32,
+    }
+  }
+}
+
+// A body as the solver sees it. Static bodies have zero inverse mass and inertia.
+#[derive(Debug, Clone)]
+pub struct SolverBody {
+  pub position: Vec3F,
+  pub rotation: QuatF,
+  pub velocity: Vec3F,
+  pub angular_velocity: Vec3F,
+  inverse_mass: f32,
+  inverse_inertia: Mat3F,
+}
+
+impl SolverBody {
+  pub fn fixed(position: Vec3F, rotation: QuatF) -> Self {
+    Self {
+      position,
+      rotation,
+      velocity: Vec3F::zero(),
+      angular_velocity: Vec3F::zero(),
+      inverse_mass: 0f32,
+      inverse_inertia: Mat3F::zero(),
+    }
+  }
+
+  // Without an `Inertia`, torque is treated as angular acceleration elsewhere, so the unit
+  // tensor is used to match.
+  pub fn dynamic(
+    transform: &TransformComponent,
+    rigid_body: &RigidBody,
+    mass: Option<&Mass>,
+    inertia: Option<&Inertia>,
+  ) -> Self {
+    Self {
+      position: transform.translation,
+      rotation: transform.rotation,
+      velocity: rigid_body.velocity,
+      angular_velocity: rigid_body.angular_velocity,
+      inverse_mass: mass.map_or(1f32, Mass::inverse),
+      inverse_inertia: inertia.map_or(Mat3F::identity(), |i| i.world_inverse(&transform.rotation)),
+    }
+  }
+
+  fn world_point(&self, local: &Vec3F) -> Vec3F {
+    self.position + self.rotation.rotate_vector(*local)
+  }
+}
+
+// One scalar velocity constraint, J v + bias = 0, between bodies `a` and `b`.
+struct ConstraintRow {
+  a: usize,
+  b: usize,
+  // Applied to `b`, and negated to `a`.
+  linear: Vec3F,
+  angular_a: Vec3F,
+  angular_b: Vec3F,
+  bias: f32,
+  effective_mass: f32,
+}
+
+impl ConstraintRow {
+  fn new(bodies: &[SolverBody], (a, b): (usize, usize), linear: Vec3F, angular: (Vec3F, Vec3F), bias: f32) -> Self {
+    let (body_a, body_b) = (&bodies[a], &bodies[b]);
+    let (angular_a, angular_b) = angular;
+    let k = (body_a.inverse_mass + body_b.inverse_mass) * linear.magnitude2()
+      + angular_a.dot(body_a.inverse_inertia * angular_a)
+      + angular_b.dot(body_b.inverse_inertia * angular_b);
+    Self {
+      a,
+      b,
+      linear,
+      angular_a,
+      angular_b,
+      bias,
+      effective_mass: if k > 0f32 { 1f32 / k } else { 0f32 },
+    }
+  }
+
+  fn solve(&self, bodies: &mut [SolverBody]) {
+    let jv = {
+      let (body_a, body_b) = (&bodies[self.a], &bodies[self.b]);
+      self.linear.dot(body_b.velocity - body_a.velocity)
+        + self.angular_a.dot(body_a.angular_velocity)
+        + self.angular_b.dot(body_b.angular_velocity)
+    };
+    let lambda = -(jv + self.bias) * self.effective_mass;
+    let body_a = &mut bodies[self.a];
+    body_a.velocity -= self.linear * (lambda * body_a.inverse_mass);
+    body_a.angular_velocity += body_a.inverse_inertia * self.angular_a * lambda;
+    let body_b = &mut bodies[self.b];
+    body_b.velocity += self.linear * (lambda * body_b.inverse_mass);
+    body_b.angular_velocity += body_b.inverse_inertia * self.angular_b * lambda;
+  }
+}
+
+// Two unit vectors perpendicular to `axis` and each other.
+fn tangents(axis: &Vec3F) -> (Vec3F, Vec3F) {
+  let helper = if axis.x.abs() < 0.9f32 {
+    Vec3F::unit_x()
+  } else {
+    Vec3F::unit_y()
+  };
+  let t1 = axis.cross(helper).normalize();
+  (t1, axis.cross(t1))
+}
+
+// Resolves the joints with sequential impulses: each row's impulse is computed and applied in
+// turn, `iterations` times over, so that connected joints settle together. Each entry in `joints`
+// is (body, connected body, joint) with indices into `bodies`.
+pub fn solve_joints(bodies: &mut [SolverBody], joints: &[(usize, usize, &Joint)], solver: &JointSolver, dt: f32) {
+  let feedback = if dt > 0f32 { solver.stiffness / dt } else { 0f32 };
+  let mut rows = Vec::new();
+  for (body, connected, joint) in joints {
+    // `a` is the connected body, `b` this one, so errors read as "where this body is off to".
+    let (a, b) = (*connected, *body);
+    let r_a = bodies[a].rotation.rotate_vector(joint.connected_anchor);
+    let r_b = bodies[b].rotation.rotate_vector(joint.anchor);
+    let error = bodies[b].world_point(&joint.anchor) - bodies[a].world_point(&joint.connected_anchor);
+
+    let point_row = |rows: &mut Vec<ConstraintRow>, direction: Vec3F, offset: f32| {
+      rows.push(ConstraintRow::new(
+        bodies,
+        (a, b),
+        direction,
+        (-r_a.cross(direction), r_b.cross(direction)),
+        feedback * offset,
+      ));
+    };
+    match joint.kind {
+      JointKind::Distance { length } => {
+        let distance = error.magnitude();
+        if distance > 0f32 {
+          point_row(&mut rows, error / distance, distance - length);
+        }
+      }
+      JointKind::BallSocket | JointKind::Hinge { .. } | JointKind::Fixed => {
+        for direction in [Vec3F::unit_x(), Vec3F::unit_y(), Vec3F::unit_z()] {
+          point_row(&mut rows, direction, error.dot(direction));
+        }
+      }
+    }
+
+    let locked_axes = match (joint.kind, joint.reference) {
+      (JointKind::Fixed, Some(_)) => vec![Vec3F::unit_x(), Vec3F::unit_y(), Vec3F::unit_z()],
+      (JointKind::Hinge { axis }, Some(_)) => {
+        let (t1, t2) = tangents(&bodies[b].rotation.rotate_vector(axis));
+        vec![t1, t2]
+      }
+      _ => Vec::new(),
+    };
+    if let Some(reference) = joint.reference {
+      // The rotation taking where this body should be turned to where it is.
+      let mut twist = bodies[b].rotation * (bodies[a].rotation * reference).conjugate();
+      if twist.s < 0f32 {
+        twist = -twist;
+      }
+      let angular_error = twist.v * 2f32;
+      for direction in locked_axes {
+        rows.push(ConstraintRow::new(
+          bodies,
+          (a, b),
+          Vec3F::zero(),
+          (-direction, direction),
+          feedback * angular_error.dot(direction),
+        ));
+      }
+    }
+  }
+
+  for _ in 0..solver.iterations {
+    for row in rows.iter() {
+      row.solve(bodies);
+    }
+  }
+}
+
+// Writes the solved velocities back, moving the body by the change in velocity over `dt` since
+// its position was already integrated with the unsolved one.
+pub fn apply_solved_body(body: &SolverBody, transform: &mut TransformComponent, rigid_body: &mut RigidBody, dt: f32) {
+  transform.translation += (body.velocity - rigid_body.velocity) * dt;
+  transform.push_rotation(&angular_displacement(
+    &(body.angular_velocity - rigid_body.angular_velocity),
+    dt,
+  ));
+  rigid_body.velocity = body.velocity;
+  rigid_body.angular_velocity = body.angular_velocity;
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cgmath::Rotation3;
+  use specs::prelude::*;
+
+  fn body_at(position: Vec3F, velocity: Vec3F) -> SolverBody {
+    SolverBody::dynamic(
+      &TransformComponent {
+        translation: position,
+        ..Default::default()
+      },
+      &RigidBody::new(velocity, Vec3F::zero(), Vec3F::zero(), Vec3F::zero()),
+      None,
+      None,
+    )
+  }
+
+  fn entity() -> Entity {
+    World::new().create_entity().build()
+  }
+
+  #[test]
+  fn distance_joint_removes_stretching_velocity() {
+    let mut bodies = vec![
+      SolverBody::fixed(Vec3F::zero(), QuatF::one()),
+      body_at(Vec3F::new(2f32, 0f32, 0f32), Vec3F::new(3f32, 4f32, 0f32)),
+    ];
+    let joint = Joint::distance(2f32, Vec3F::zero(), Vec3F::zero());
+    solve_joints(&mut bodies, &[(1, 0, &joint)], &JointSolver::default(), 1f32 / 60f32);
+    assert!(bodies[1].velocity.x.abs() < 1e-4f32);
+    assert!((bodies[1].velocity.y - 4f32).abs() < 1e-4f32);
+    assert_eq!(bodies[0].velocity, Vec3F::zero());
+  }
+
+  #[test]
+  fn ball_socket_shares_momentum() {
+    let mut bodies = vec![
+      body_at(Vec3F::zero(), Vec3F::zero()),
+      body_at(Vec3F::new(2f32, 0f32, 0f32), Vec3F::new(0f32, 0f32, 4f32)),
+    ];
+    let joint = Joint::ball_socket(Vec3F::new(-1f32, 0f32, 0f32), Vec3F::new(1f32, 0f32, 0f32)).connected_to(entity());
+    solve_joints(&mut bodies, &[(1, 0, &joint)], &JointSolver::default(), 1f32 / 60f32);
+    let total = bodies[0].velocity + bodies[1].velocity;
+    assert!((total - Vec3F::new(0f32, 0f32, 4f32)).magnitude() < 1e-3f32);
+    // Both anchors end up moving together.
+    let anchor_a = bodies[0].velocity + bodies[0].angular_velocity.cross(Vec3F::new(1f32, 0f32, 0f32));
+    let anchor_b = bodies[1].velocity + bodies[1].angular_velocity.cross(Vec3F::new(-1f32, 0f32, 0f32));
+    assert!((anchor_a - anchor_b).magnitude() < 1e-3f32);
+  }
+
+  #[test]
+  fn hinge_only_turns_about_its_axis() {
+    let mut bodies = vec![
+      SolverBody::fixed(Vec3F::zero(), QuatF::one()),
+      body_at(Vec3F::zero(), Vec3F::zero()),
+    ];
+    bodies[1].angular_velocity = Vec3F::new(1f32, 2f32, 3f32);
+    let mut joint = Joint::hinge(Vec3F::unit_y(), Vec3F::zero(), Vec3F::zero());
+    joint.capture_reference(&QuatF::one(), &QuatF::one());
+    solve_joints(&mut bodies, &[(1, 0, &joint)], &JointSolver::default(), 1f32 / 60f32);
+    assert!((bodies[1].angular_velocity - Vec3F::new(0f32, 2f32, 0f32)).magnitude() < 1e-4f32);
+
+    // A fixed joint pulls a body that has turned away back towards its reference.
+    let mut fixed = Joint::fixed(Vec3F::zero(), Vec3F::zero());
+    fixed.capture_reference(&QuatF::one(), &QuatF::one());
+    bodies[1].angular_velocity = Vec3F::zero();
+    bodies[1].rotation = QuatF::from_angle_z(cgmath::Deg(10f32));
+    solve_joints(&mut bodies, &[(1, 0, &fixed)], &JointSolver::default(), 1f32 / 60f32);
+    assert!(bodies[1].angular_velocity.z < 0f32);
+    assert!(bodies[1].angular_velocity.x.abs() < 1e-4f32);
+  }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use cgmath::prelude::*;
+use specs::{Component, VecStorage};
+
+use super::geometry::{closest_feature_on_triangle, sweep_sphere_triangle, TriangleFeature};
+use crate::graphics::{AssetLibrary, MeshComponent};
+use crate::physics::{Collision, CollisionSummary, TransformComponent};
+use crate::utils::{swizzle_down, swizzle_up, Vec3F};
+
+// A static triangle soup in world space. The transform is baked in when the collider is built,
+// so a mesh that moves needs its collider rebuilt. The triangles are shared between clones.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct MeshCollision {
+  triangles: Arc<Vec<[Vec3F; 3]>>,
+  // Indexed like `triangles`.
+  normals: Arc<Vec<TriangleNormals>>,
+  center: Vec3F,
+  bounding_radius: f32,
+}
+
+// Angle-weighted pseudo-normals (Baerentzen and Aanaes) of a triangle's face, corners and edges.
+// Whichever feature a closest point lies on, the offset to it is in front of that feature's
+// pseudo-normal exactly when the point is outside, so the sign holds at shared edges and corners.
+#[derive(Debug, Clone)]
+struct TriangleNormals {
+  face: Vec3F,
+  vertices: [Vec3F; 3],
+  edges: [Vec3F; 3],
+}
+
+impl TriangleNormals {
+  fn get(&self, feature: TriangleFeature) -> Vec3F {
+    match feature {
+      TriangleFeature::Vertex(i) => self.vertices[i],
+      TriangleFeature::Edge(i) => self.edges[i],
+      TriangleFeature::Face => self.face,
+    }
+  }
+}
+
+// Corners are matched by position, so that meshes with split vertices (flat shading, seams)
+// still share their edges and corners.
+fn position_key(p: &Vec3F) -> [u32; 3] {
+  // Adding zero turns -0.0 into 0.0.
+  [(p.x + 0f32).to_bits(), (p.y + 0f32).to_bits(), (p.z + 0f32).to_bits()]
+}
+
+fn pseudo_normals(triangles: &[[Vec3F; 3]]) -> Vec<TriangleNormals> {
+  let faces: Vec<Vec3F> = triangles
+    .iter()
+    .map(|[a, b, c]| {
+      let normal = (b - a).cross(c - a);
+      if normal.magnitude2() > 0f32 {
+        normal.normalize()
+      } else {
+        Vec3F::zero()
+      }
+    })
+    .collect();
+  let mut vertices: HashMap<[u32; 3], Vec3F> = HashMap::new();
+  let mut edges: HashMap<([u32; 3], [u32; 3]), Vec3F> = HashMap::new();
+  let edge_key = |p: &Vec3F, q: &Vec3F| {
+    let (p, q) = (position_key(p), position_key(q));
+    if p < q {
+      (p, q)
+    } else {
+      (q, p)
+    }
+  };
+  for (corners, face) in triangles.iter().zip(faces.iter()) {
+    for i in 0..3 {
+      let (corner, next, prev) = (corners[i], corners[(i + 1) % 3], corners[(i + 2) % 3]);
+      let angle = if *face == Vec3F::zero() {
+        0f32
+      } else {
+        (next - corner).angle(prev - corner).0
+      };
+      *vertices.entry(position_key(&corner)).or_insert_with(Vec3F::zero) += face * angle;
+      *edges.entry(edge_key(&corner, &next)).or_insert_with(Vec3F::zero) += *face;
+    }
+  }
+  triangles
+    .iter()
+    .zip(faces.iter())
+    .map(|(corners, face)| TriangleNormals {
+      face: *face,
+      vertices: [0, 1, 2].map(|i| vertices[&position_key(&corners[i])]),
+      edges: [0, 1, 2].map(|i| edges[&edge_key(&corners[i], &corners[(i + 1) % 3])]),
+    })
+    .collect()
+}
+
+impl MeshCollision {
+  pub fn new(positions: &[Vec3F], indices: &[u32], transform: &TransformComponent) -> Self {
+    let matrix = transform.matrix();
+    let world: Vec<Vec3F> = positions
+      .iter()
+      .map(|p| swizzle_down(&(matrix * swizzle_up(p))))
+      .collect();
+    let triangles: Vec<[Vec3F; 3]> = indices
+      .chunks_exact(3)
+      .map(|tri| [world[tri[0] as usize], world[tri[1] as usize], world[tri[2] as usize]])
+      .collect();
+    let (lows, highs) = world.iter().fold(
+      (
+        Vec3F::new(f32::MAX, f32::MAX, f32::MAX),
+        Vec3F::new(f32::MIN, f32::MIN, f32::MIN),
+      ),
+      |(lo, hi), p| {
+        (
+          Vec3F::new(lo.x.min(p.x), lo.y.min(p.y), lo.z.min(p.z)),
+          Vec3F::new(hi.x.max(p.x), hi.y.max(p.y), hi.z.max(p.z)),
+        )
+      },
+    );
+    let center = if world.is_empty() {
+      Vec3F::zero()
+    } else {
+      (lows + highs) / 2f32
+    };
+    let bounding_radius = world.iter().fold(0f32, |acc, p| acc.max((p - center).magnitude()));
+    Self {
+      normals: Arc::new(pseudo_normals(&triangles)),
+      triangles: Arc::new(triangles),
+      center,
+      bounding_radius,
+    }
+  }
+
+  // Reads the positions and indices back out of the mesh's vertex array.
+  pub fn from_mesh(mesh: &MeshComponent, assets: &AssetLibrary, transform: &TransformComponent) -> Option<Self> {
+    let mut key = mesh.vertex_array_id.clone();
+    let vertex_array = assets.get_mesh_mut(&mut key)?;
+    Some(Self::new(&vertex_array.positions(), vertex_array.indices(), transform))
+  }
+
+  pub fn triangle_count(&self) -> usize {
+    self.triangles.len()
+  }
+}
+
+impl Collision for MeshCollision {
+  // Distance to the nearest triangle, negative when behind the surface there. Triangles are
+  // expected to wind counter-clockwise when seen from outside.
+  fn distance_to(&self, pt: &Vec3F) -> f32 {
+    self
+      .triangles
+      .iter()
+      .zip(self.normals.iter())
+      .map(|([a, b, c], normals)| {
+        let (closest, feature) = closest_feature_on_triangle(pt, a, b, c);
+        let distance = (pt - closest).magnitude();
+        if (pt - closest).dot(normals.get(feature)) < 0f32 {
+          -distance
+        } else {
+          distance
+        }
+      })
+      .fold(f32::MAX, |best, d| if d.abs() < best.abs() { d } else { best })
+  }
+
+  fn bounding_sphere(&self) -> (Vec3F, f32) {
+    (self.center, self.bounding_radius)
+  }
+
+  fn sphere_collision(&self, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary> {
+    self
+      .triangles
+      .iter()
+      .filter_map(|[a, b, c]| sweep_sphere_triangle(sphere.0, velocity, *sphere.1, (a, b, c)))
+      .map(|(time, surface_normal)| CollisionSummary {
+        time,
+        position: sphere.0 + velocity * time,
+        surface_normal,
+      })
+      .min()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  // A unit cube centred on the origin with outward-facing, counter-clockwise triangles.
+  fn cube() -> (Vec<Vec3F>, Vec<u32>) {
+    let positions = (0..8)
+      .map(|i| {
+        Vec3F::new(
+          if i & 1 == 0 { -0.5f32 } else { 0.5f32 },
+          if i & 2 == 0 { -0.5f32 } else { 0.5f32 },
+          if i & 4 == 0 { -0.5f32 } else { 0.5f32 },
+        )
+      })
+      .collect();
+    let indices = vec![
+      0, 2, 1, 1, 2, 3, // -z
+      4, 5, 6, 5, 7, 6, // +z
+      0, 1, 4, 1, 5, 4, // -y
+      2, 6, 3, 3, 6, 7, // +y
+      0, 4, 2, 2, 4, 6, // -x
+      1, 3, 5, 3, 7, 5, // +x
+    ];
+    (positions, indices)
+  }
+
+  #[test]
+  fn signed_distance_to_transformed_cube() {
+    let (positions, indices) = cube();
+    let mut transform = TransformComponent::default();
+    transform.translation = Vec3F::new(10f32, 0f32, 0f32);
+    transform.scale = Vec3F::new(2f32, 2f32, 2f32);
+    let mesh = MeshCollision::new(&positions, &indices, &transform);
+    assert_eq!(mesh.triangle_count(), 12);
+    assert!((mesh.distance_to(&Vec3F::new(10f32, 4f32, 0f32)) - 3f32).abs() < 1e-4f32);
+    assert!((mesh.distance_to(&Vec3F::new(10f32, 0.5f32, 0f32)) + 0.5f32).abs() < 1e-4f32);
+  }
+
+  // Two faces meeting at a sharp edge along z: with `outward` the faces look into the groove
+  // between them, making the edge concave, otherwise they form a knife-edged ridge.
+  fn groove(outward: bool) -> MeshCollision {
+    let positions = vec![
+      Vec3F::new(0f32, 0f32, -1f32),
+      Vec3F::new(0f32, 0f32, 1f32),
+      Vec3F::new(-0.4f32, 4f32, -1f32),
+      Vec3F::new(-0.4f32, 4f32, 1f32),
+      Vec3F::new(0.4f32, 4f32, -1f32),
+      Vec3F::new(0.4f32, 4f32, 1f32),
+    ];
+    let mut indices = vec![0, 2, 1, 1, 2, 3, 0, 1, 4, 1, 5, 4];
+    if !outward {
+      indices.reverse();
+    }
+    MeshCollision::new(&positions, &indices, &TransformComponent::default())
+  }
+
+  #[test]
+  fn signed_distance_around_a_sharp_edge() {
+    // Closest to the edge itself, and in front of only one of the faces' planes.
+    let beside_edge = Vec3F::new(1f32, -0.2f32, 0f32).normalize() * 0.1f32;
+    let in_groove = Vec3F::new(0f32, 1f32, 0f32);
+    let groove_distance = 0.4f32 / Vec3F::new(4f32, 0.4f32, 0f32).magnitude();
+
+    let concave = groove(true);
+    assert!((concave.distance_to(&beside_edge) + 0.1f32).abs() < 1e-4f32);
+    assert!((concave.distance_to(&in_groove) - groove_distance).abs() < 1e-4f32);
+
+    let ridge = groove(false);
+    assert!((ridge.distance_to(&beside_edge) - 0.1f32).abs() < 1e-4f32);
+    assert!((ridge.distance_to(&in_groove) + groove_distance).abs() < 1e-4f32);
+  }
+
+  #[test]
+  fn sphere_stops_on_the_nearest_face() {
+    let (positions, indices) = cube();
+    let mesh = MeshCollision::new(&positions, &indices, &TransformComponent::default());
+    let summary = mesh
+      .sphere_collision((&Vec3F::new(-5f32, 0.1f32, 0f32), &0.5f32), &Vec3F::unit_x())
+      .unwrap();
+    assert!((summary.time - 4f32).abs() < 1e-4f32);
+    assert_eq!(summary.surface_normal, -Vec3F::unit_x());
+    assert!(mesh
+      .sphere_collision((&Vec3F::new(-5f32, 3f32, 0f32), &0.5f32), &Vec3F::unit_x())
+      .is_none());
+  }
+}
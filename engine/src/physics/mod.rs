@@ -1,7 +1,31 @@
+mod broadphase;
+mod collider_set;
 mod colliders;
 pub mod collision;
 pub mod components;
+mod force_field;
+mod geometry;
+mod inertia;
+mod joints;
+mod mesh_collision;
+mod nbody;
+mod queries;
+mod response;
+mod sweep;
+mod trigger;
 
+pub use self::broadphase::*;
+pub use self::collider_set::*;
 pub use self::colliders::*;
 pub use self::collision::*;
 pub use self::components::*;
+pub use self::force_field::*;
+pub use self::geometry::*;
+pub use self::inertia::*;
+pub use self::joints::*;
+pub use self::mesh_collision::*;
+pub use self::nbody::*;
+pub use self::queries::*;
+pub use self::response::*;
+pub use self::sweep::*;
+pub use self::trigger::*;
@@ -0,0 +1,238 @@
+use cgmath::prelude::*;
+use specs::{Component, NullStorage};
+
+use crate::datastructures::{BarnesHutTree, HasMass, HasPosition};
+use crate::utils::Vec3F;
+
+// Marks a body as one of the masses in the N-body simulation. These bodies are integrated by
+// `NBodySystem` rather than `MotionSystem`, and pull on each other with their `Mass`.
+#[derive(Component, Debug, Default, Clone, Copy)]
+#[storage(NullStorage)]
+pub struct NBody;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NBodyMode {
+  // Every pair, O(n^2). Best for a handful of bodies or as a reference.
+  Exact,
+  // O(n log n) octree approximation. Smaller `theta` is more accurate; 0.5 is the usual choice.
+  BarnesHut { theta: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NBodySettings {
+  pub mode: NBodyMode,
+  pub gravitational_constant: f32,
+  // Plummer softening length, keeps close encounters from blowing up the integrator.
+  pub softening: f32,
+}
+
+impl Default for NBodySettings {
+  fn default() -> Self {
+    Self {
+      mode: NBodyMode::BarnesHut { theta: 0.5f32 },
+      gravitational_constant: 1f32,
+      softening: 0.1f32,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GravitatingBody {
+  pub position: Vec3F,
+  pub mass: f32,
+}
+
+impl HasPosition for GravitatingBody {
+  fn position(&self) -> &Vec3F {
+    &self.position
+  }
+}
+
+impl HasMass for GravitatingBody {
+  fn mass(&self) -> f32 {
+    self.mass
+  }
+}
+
+impl NBodySettings {
+  // Gravitational acceleration on each body from all the others.
+  pub fn accelerations(&self, bodies: &[GravitatingBody]) -> Vec<Vec3F> {
+    let g = self.gravitational_constant;
+    match self.mode {
+      NBodyMode::Exact => {
+        let softening2 = self.softening * self.softening;
+        let mut accelerations = vec![Vec3F::zero(); bodies.len()];
+        for i in 0..bodies.len() {
+          for j in (i + 1)..bodies.len() {
+            let offset = bodies[j].position - bodies[i].position;
+            let r2 = offset.magnitude2() + softening2;
+            if r2 <= 0f32 {
+              continue;
+            }
+            let pull = offset * (g / (r2 * r2.sqrt()));
+            accelerations[i] += pull * bodies[j].mass;
+            accelerations[j] -= pull * bodies[i].mass;
+          }
+        }
+        accelerations
+      }
+      NBodyMode::BarnesHut { theta } => {
+        let tree = BarnesHutTree::new(bodies.to_vec(), 1);
+        bodies
+          .iter()
+          .map(|body| tree.field_at(&body.position, theta, self.softening) * g)
+          .collect()
+      }
+    }
+  }
+
+  // Kinetic plus gravitational potential energy. Accumulated in f64 so the drift it's used to
+  // measure isn't swamped by rounding.
+  pub fn total_energy(&self, bodies: &[GravitatingBody], velocities: &[Vec3F]) -> f64 {
+    let kinetic: f64 = bodies
+      .iter()
+      .zip(velocities.iter())
+      .map(|(body, v)| 0.5f64 * body.mass as f64 * v.magnitude2() as f64)
+      .sum();
+    kinetic + self.potential_energy(bodies)
+  }
+
+  pub fn potential_energy(&self, bodies: &[GravitatingBody]) -> f64 {
+    let g = self.gravitational_constant as f64;
+    match self.mode {
+      NBodyMode::Exact => {
+        let softening2 = (self.softening * self.softening) as f64;
+        let mut potential = 0f64;
+        for i in 0..bodies.len() {
+          for j in (i + 1)..bodies.len() {
+            let r2 = (bodies[j].position - bodies[i].position).magnitude2() as f64 + softening2;
+            if r2 > 0f64 {
+              potential -= g * bodies[i].mass as f64 * bodies[j].mass as f64 / r2.sqrt();
+            }
+          }
+        }
+        potential
+      }
+      NBodyMode::BarnesHut { theta } => {
+        let tree = BarnesHutTree::new(bodies.to_vec(), 1);
+        // Every pair is seen from both ends, hence the half.
+        0.5f64
+          * g
+          * bodies
+            .iter()
+            .map(|body| body.mass as f64 * tree.potential_at(&body.position, theta, self.softening) as f64)
+            .sum::<f64>()
+      }
+    }
+  }
+
+  // One drift-kick-drift leapfrog step. Being symplectic, the energy error stays bounded instead
+  // of creeping up the way it does with plain Euler. `external` adds any non-gravitational
+  // acceleration per body.
+  pub fn leapfrog_step(&self, bodies: &mut [GravitatingBody], velocities: &mut [Vec3F], external: &[Vec3F], dt: f32) {
+    let half_dt = dt / 2f32;
+    for (body, velocity) in bodies.iter_mut().zip(velocities.iter()) {
+      body.position += velocity * half_dt;
+    }
+    let accelerations = self.accelerations(bodies);
+    for ((body, velocity), (acceleration, external)) in bodies
+      .iter_mut()
+      .zip(velocities.iter_mut())
+      .zip(accelerations.iter().zip(external.iter()))
+    {
+      *velocity += (acceleration + external) * dt;
+      body.position += *velocity * half_dt;
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  // Two masses of 4 circling the origin at radius 1. With G = 1 each feels a pull of
+  // G m / (2r)^2 = 1, so the circular speed is 1.
+  fn binary() -> (Vec<GravitatingBody>, Vec<Vec3F>) {
+    let bodies = vec![
+      GravitatingBody {
+        position: Vec3F::new(1f32, 0f32, 0f32),
+        mass: 4f32,
+      },
+      GravitatingBody {
+        position: Vec3F::new(-1f32, 0f32, 0f32),
+        mass: 4f32,
+      },
+    ];
+    let velocities = vec![Vec3F::unit_z(), -Vec3F::unit_z()];
+    (bodies, velocities)
+  }
+
+  #[test]
+  fn exact_pairs_pull_equally() {
+    let settings = NBodySettings {
+      mode: NBodyMode::Exact,
+      gravitational_constant: 2f32,
+      softening: 0f32,
+    };
+    let bodies = vec![
+      GravitatingBody {
+        position: Vec3F::zero(),
+        mass: 1f32,
+      },
+      GravitatingBody {
+        position: Vec3F::new(2f32, 0f32, 0f32),
+        mass: 3f32,
+      },
+    ];
+    let accelerations = settings.accelerations(&bodies);
+    assert_eq!(accelerations[0], Vec3F::new(1.5f32, 0f32, 0f32));
+    assert_eq!(accelerations[1], Vec3F::new(-0.5f32, 0f32, 0f32));
+    assert_eq!(settings.potential_energy(&bodies), -3f64);
+  }
+
+  #[test]
+  fn barnes_hut_matches_exact_for_a_cloud() {
+    let bodies: Vec<GravitatingBody> = (0..100)
+      .map(|i| GravitatingBody {
+        position: Vec3F::new(((i * 7) % 19) as f32, ((i * 11) % 13) as f32, ((i * 3) % 7) as f32),
+        mass: 1f32,
+      })
+      .collect();
+    let exact = NBodySettings {
+      mode: NBodyMode::Exact,
+      ..NBodySettings::default()
+    };
+    let approximate = NBodySettings::default();
+    let expected = exact.accelerations(&bodies);
+    let found = approximate.accelerations(&bodies);
+    let error: f32 = expected
+      .iter()
+      .zip(found.iter())
+      .map(|(e, f)| (e - f).magnitude())
+      .sum();
+    let scale: f32 = expected.iter().map(|e| e.magnitude()).sum();
+    assert!(error / scale < 0.02f32);
+    let (e, f) = (exact.potential_energy(&bodies), approximate.potential_energy(&bodies));
+    assert!(((e - f) / e).abs() < 0.01f64);
+  }
+
+  #[test]
+  fn leapfrog_keeps_a_binary_bound() {
+    let settings = NBodySettings {
+      mode: NBodyMode::Exact,
+      gravitational_constant: 1f32,
+      softening: 0f32,
+    };
+    let (mut bodies, mut velocities) = binary();
+    let external = vec![Vec3F::zero(); 2];
+    let initial = settings.total_energy(&bodies, &velocities);
+    // About five orbits at 60Hz.
+    for _ in 0..2000 {
+      settings.leapfrog_step(&mut bodies, &mut velocities, &external, 1f32 / 60f32);
+    }
+    let drift = (settings.total_energy(&bodies, &velocities) - initial) / initial.abs();
+    assert!(drift.abs() < 1e-3f64);
+    assert!((bodies[0].position.magnitude() - 1f32).abs() < 0.01f32);
+    assert!((bodies[0].position + bodies[1].position).magnitude() < 1e-3f32);
+  }
+}
@@ -0,0 +1,337 @@
+use cgmath::prelude::*;
+use specs::prelude::*;
+
+use super::geometry::closest_point_on_segment;
+use super::{
+  AxisAlignedCubeCollision, Broadphase, CapsuleCollision, ColliderEntry, ColliderStorages, Collision, CollisionSummary,
+  MeshCollision, OrientedBoxCollision, SphereCollision, Trigger,
+};
+use crate::utils::Vec3F;
+
+// Step used to estimate surface normals from the signed distance field.
+const NORMAL_EPSILON: f32 = 1e-3f32;
+
+// A copy of one collider, taken so that queries don't need the collider storages.
+#[derive(Debug, Clone)]
+pub enum QueryShape {
+  AxisAlignedCube(AxisAlignedCubeCollision),
+  OrientedBox(OrientedBoxCollision),
+  Sphere(SphereCollision),
+  Capsule(CapsuleCollision),
+  Mesh(MeshCollision),
+}
+
+impl QueryShape {
+  pub fn sphere_collision(&self, sphere: (&Vec3F, &f32), velocity: &Vec3F) -> Option<CollisionSummary> {
+    match self {
+      QueryShape::AxisAlignedCube(c) => c.sphere_collision(sphere, velocity),
+      QueryShape::OrientedBox(c) => c.sphere_collision(sphere, velocity),
+      QueryShape::Sphere(c) => c.sphere_collision(sphere, velocity),
+      QueryShape::Capsule(c) => c.sphere_collision(sphere, velocity),
+      QueryShape::Mesh(c) => c.sphere_collision(sphere, velocity),
+    }
+  }
+
+  pub fn distance_to(&self, pt: &Vec3F) -> f32 {
+    match self {
+      QueryShape::AxisAlignedCube(c) => c.distance_to(pt),
+      QueryShape::OrientedBox(c) => c.distance_to(pt),
+      QueryShape::Sphere(c) => c.distance_to(pt),
+      QueryShape::Capsule(c) => c.distance_to(pt),
+      QueryShape::Mesh(c) => c.distance_to(pt),
+    }
+  }
+
+  pub fn bounding_sphere(&self) -> (Vec3F, f32) {
+    match self {
+      QueryShape::AxisAlignedCube(c) => c.bounding_sphere(),
+      QueryShape::OrientedBox(c) => c.bounding_sphere(),
+      QueryShape::Sphere(c) => c.bounding_sphere(),
+      QueryShape::Capsule(c) => c.bounding_sphere(),
+      QueryShape::Mesh(c) => c.bounding_sphere(),
+    }
+  }
+
+  // Outward normal of the surface nearest `pt`, from central differences of the distance field.
+  pub fn normal_at(&self, pt: &Vec3F) -> Vec3F {
+    let axis =
+      |unit: Vec3F| self.distance_to(&(pt + unit * NORMAL_EPSILON)) - self.distance_to(&(pt - unit * NORMAL_EPSILON));
+    let gradient = Vec3F::new(axis(Vec3F::unit_x()), axis(Vec3F::unit_y()), axis(Vec3F::unit_z()));
+    if gradient.magnitude2() > 0f32 {
+      gradient.normalize()
+    } else {
+      Vec3F::zero()
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneHit {
+  pub entity: Entity,
+  // How far the cast travelled, or for `overlap_sphere` and `nearest_k` the signed distance to the
+  // surface.
+  pub distance: f32,
+  pub point: Vec3F,
+  pub normal: Vec3F,
+}
+
+// Scene queries against every collider, for gameplay code asking "what is under the crosshair"
+// or "what is within 5 units". The physics loop refreshes it once per tick with
+// `PhysicsQuerySystem`; reach it from a system through `SystemUtilities::physics`.
+#[derive(Default)]
+pub struct PhysicsQueries {
+  broadphase: Broadphase,
+  // Indexed the same as `broadphase.entries()`.
+  shapes: Vec<QueryShape>,
+}
+
+impl PhysicsQueries {
+  pub fn rebuild(&mut self, colliders: Vec<(Entity, QueryShape)>) {
+    let entries = colliders
+      .iter()
+      .map(|(entity, shape)| {
+        let (center, bounding_radius) = shape.bounding_sphere();
+        ColliderEntry {
+          entity: *entity,
+          center,
+          bounding_radius,
+        }
+      })
+      .collect();
+    self.shapes = colliders.into_iter().map(|(_, shape)| shape).collect();
+    self.broadphase.rebuild(entries);
+  }
+
+  pub fn count(&self) -> usize {
+    self.shapes.len()
+  }
+
+  // First collider hit by the ray within `max_distance`.
+  pub fn raycast(&self, origin: &Vec3F, direction: &Vec3F, max_distance: f32) -> Option<SceneHit> {
+    self.sphere_cast(origin, 0f32, direction, max_distance)
+  }
+
+  // First collider touched by a sphere moved from `origin` along `direction`. The hit point is
+  // where the sphere touches the surface, not where its centre ends up.
+  pub fn sphere_cast(&self, origin: &Vec3F, radius: f32, direction: &Vec3F, max_distance: f32) -> Option<SceneHit> {
+    if direction.magnitude2() == 0f32 {
+      return None;
+    }
+    let direction = direction.normalize();
+    let end = origin + direction * max_distance;
+    let entries = self.broadphase.entries();
+    self
+      .broadphase
+      .candidate_indices(&((origin + end) / 2f32), max_distance / 2f32 + radius)
+      .into_iter()
+      .filter(|&i| {
+        let entry = &entries[i];
+        let closest = closest_point_on_segment(&entry.center, origin, &end);
+        (entry.center - closest).magnitude() <= entry.bounding_radius + radius
+      })
+      .filter_map(|i| {
+        let summary = self.shapes[i].sphere_collision((origin, &radius), &direction)?;
+        // Also turns away a NaN time.
+        if !(0f32..=max_distance).contains(&summary.time) {
+          return None;
+        }
+        Some(SceneHit {
+          entity: entries[i].entity,
+          distance: summary.time,
+          point: summary.position - summary.surface_normal * radius,
+          normal: summary.surface_normal,
+        })
+      })
+      .min_by(|a, b| a.distance.total_cmp(&b.distance))
+  }
+
+  // Every collider reaching into the sphere, nearest first.
+  pub fn overlap_sphere(&self, center: &Vec3F, radius: f32) -> Vec<SceneHit> {
+    let mut hits: Vec<SceneHit> = self
+      .broadphase
+      .candidate_indices(center, radius)
+      .into_iter()
+      .map(|i| self.surface_hit(i, center))
+      .filter(|hit| hit.distance <= radius)
+      .collect();
+    hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    hits
+  }
+
+  // The `k` colliders with surfaces closest to `position`, nearest first. The search radius
+  // doubles until it holds `k` surfaces; anything outside it is further than everything inside.
+  pub fn nearest_k(&self, position: &Vec3F, k: usize) -> Vec<SceneHit> {
+    if k == 0 || self.shapes.is_empty() || !position.is_finite() {
+      return Vec::new();
+    }
+    let mut radius = 1f32;
+    loop {
+      let candidates = self.broadphase.candidate_indices(position, radius);
+      // A collider with non-finite bounds never turns up, so stop once the radius can't grow.
+      let everything = candidates.len() == self.shapes.len() || radius.is_infinite();
+      let mut hits: Vec<SceneHit> = candidates
+        .into_iter()
+        .map(|i| self.surface_hit(i, position))
+        .filter(|hit| hit.distance.is_finite() && (everything || hit.distance <= radius))
+        .collect();
+      if hits.len() >= k || everything {
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits.truncate(k);
+        return hits;
+      }
+      radius *= 2f32;
+    }
+  }
+
+  fn surface_hit(&self, index: usize, pt: &Vec3F) -> SceneHit {
+    let shape = &self.shapes[index];
+    let distance = shape.distance_to(pt);
+    let normal = shape.normal_at(pt);
+    SceneHit {
+      entity: self.broadphase.entries()[index].entity,
+      distance,
+      point: pt - normal * distance,
+      normal,
+    }
+  }
+}
+
+// Every solid collider in the world as query shapes, ready for `PhysicsQueries::rebuild`. `Trigger`
+// volumes are left out, so a raycast passes through them as a body would.
+pub fn query_shapes(
+  entities: &Entities,
+  colliders: &ColliderStorages,
+  triggers: &ReadStorage<Trigger>,
+) -> Vec<(Entity, QueryShape)> {
+  let (aabbs, oriented, spheres, capsules, meshes) = colliders;
+  let mut shapes: Vec<(Entity, QueryShape)> = Vec::new();
+  shapes.extend(
+    (entities, aabbs)
+      .join()
+      .map(|(e, c)| (e, QueryShape::AxisAlignedCube(c.clone()))),
+  );
+  shapes.extend(
+    (entities, oriented)
+      .join()
+      .map(|(e, c)| (e, QueryShape::OrientedBox(c.clone()))),
+  );
+  shapes.extend(
+    (entities, spheres)
+      .join()
+      .map(|(e, c)| (e, QueryShape::Sphere(c.clone()))),
+  );
+  shapes.extend(
+    (entities, capsules)
+      .join()
+      .map(|(e, c)| (e, QueryShape::Capsule(c.clone()))),
+  );
+  shapes.extend((entities, meshes).join().map(|(e, c)| (e, QueryShape::Mesh(c.clone()))));
+  shapes.retain(|(entity, _)| !triggers.contains(*entity));
+  shapes
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::physics::TransformComponent;
+  use crate::utils::assert_vec_close;
+
+  fn scene() -> (Vec<Entity>, PhysicsQueries) {
+    let mut world = World::new();
+    let entities: Vec<Entity> = (0..3).map(|_| world.create_entity().build()).collect();
+    let mut queries = PhysicsQueries::default();
+    queries.rebuild(vec![
+      (
+        entities[0],
+        QueryShape::AxisAlignedCube(AxisAlignedCubeCollision::from_transform(&TransformComponent {
+          translation: Vec3F::new(10f32, 0f32, 0f32),
+          scale: Vec3F::new(2f32, 2f32, 2f32),
+          ..Default::default()
+        })),
+      ),
+      (
+        entities[1],
+        QueryShape::Sphere(SphereCollision::new(Vec3F::new(20f32, 0f32, 0f32), 1f32)),
+      ),
+      (
+        entities[2],
+        QueryShape::Capsule(CapsuleCollision::new(
+          Vec3F::new(0f32, -5f32, 5f32),
+          Vec3F::new(0f32, 5f32, 5f32),
+          1f32,
+        )),
+      ),
+    ]);
+    (entities, queries)
+  }
+
+  #[test]
+  fn raycast_returns_the_first_surface() {
+    let (entities, queries) = scene();
+    let hit = queries.raycast(&Vec3F::zero(), &Vec3F::unit_x(), 100f32).unwrap();
+    assert_eq!(hit.entity, entities[0]);
+    assert!((hit.distance - 9f32).abs() < 1e-4f32);
+    assert_vec_close(hit.point, Vec3F::new(9f32, 0f32, 0f32));
+    assert_vec_close(hit.normal, -Vec3F::unit_x());
+    assert!(queries.raycast(&Vec3F::zero(), &Vec3F::unit_x(), 8f32).is_none());
+    assert!(queries.raycast(&Vec3F::zero(), &-Vec3F::unit_x(), 100f32).is_none());
+  }
+
+  #[test]
+  fn sphere_cast_touches_before_a_ray_would() {
+    let (entities, queries) = scene();
+    // A ray along z = 3.5 passes the capsule at z = 5, a sphere of radius 1 doesn't.
+    let origin = Vec3F::new(-10f32, 0f32, 3.5f32);
+    assert!(queries.raycast(&origin, &Vec3F::unit_x(), 30f32).is_none());
+    let hit = queries.sphere_cast(&origin, 1f32, &Vec3F::unit_x(), 30f32).unwrap();
+    assert_eq!(hit.entity, entities[2]);
+    assert!(hit.distance < 10f32);
+    assert!((queries.shapes[2].distance_to(&hit.point)).abs() < 1e-3f32);
+  }
+
+  #[test]
+  fn overlap_and_nearest_sort_by_surface_distance() {
+    let (entities, queries) = scene();
+    let probe = Vec3F::new(15f32, 0f32, 0f32);
+    let found: Vec<Entity> = queries
+      .overlap_sphere(&probe, 4.5f32)
+      .iter()
+      .map(|h| h.entity)
+      .collect();
+    // The cube's face and the sphere's surface are both 4 away; the capsule is much further.
+    assert_eq!(found.len(), 2);
+    assert!(found.contains(&entities[0]) && found.contains(&entities[1]));
+    assert!(queries.overlap_sphere(&probe, 3f32).is_empty());
+
+    let nearest = queries.nearest_k(&Vec3F::new(19f32, 0f32, 0f32), 2);
+    assert_eq!(
+      nearest.iter().map(|h| h.entity).collect::<Vec<_>>(),
+      vec![entities[1], entities[0]]
+    );
+    assert!(nearest[0].distance.abs() < 1e-4f32);
+    assert_vec_close(nearest[1].point, Vec3F::new(11f32, 0f32, 0f32));
+    assert_vec_close(nearest[1].normal, Vec3F::unit_x());
+    assert_eq!(queries.nearest_k(&Vec3F::zero(), 10).len(), 3);
+  }
+
+  #[test]
+  fn non_finite_input_finds_nothing() {
+    let (entities, mut queries) = scene();
+    let nan = Vec3F::new(f32::NAN, 0f32, 0f32);
+    assert!(queries.nearest_k(&nan, 1).is_empty());
+    assert!(queries.overlap_sphere(&nan, 100f32).is_empty());
+    assert!(queries.raycast(&nan, &Vec3F::unit_x(), 100f32).is_none());
+
+    queries.rebuild(vec![
+      (entities[0], QueryShape::Sphere(SphereCollision::new(nan, 1f32))),
+      (
+        entities[1],
+        QueryShape::Sphere(SphereCollision::new(Vec3F::new(10f32, 0f32, 0f32), 1f32)),
+      ),
+    ]);
+    let hit = queries.raycast(&Vec3F::zero(), &Vec3F::unit_x(), 100f32).unwrap();
+    assert_eq!(hit.entity, entities[1]);
+    let nearest = queries.nearest_k(&Vec3F::zero(), 2);
+    assert_eq!(nearest.iter().map(|h| h.entity).collect::<Vec<_>>(), vec![entities[1]]);
+  }
+}
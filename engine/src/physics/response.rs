@@ -0,0 +1,126 @@
+use cgmath::prelude::*;
+use specs::prelude::*;
+use specs::{Component, VecStorage};
+
+use crate::relativity::{elastic_collision, inelastic_collision};
+use crate::utils::Vec3F;
+
+// Rest mass of a body taking part in collision response.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[storage(VecStorage)]
+pub struct Mass(pub f32);
+
+impl Default for Mass {
+  fn default() -> Self {
+    Self(1f32)
+  }
+}
+
+impl Mass {
+  // A mass of zero (or less) is treated as infinite, so forces don't move the body.
+  pub fn inverse(&self) -> f32 {
+    if self.0 > 0f32 {
+      1f32 / self.0
+    } else {
+      0f32
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionMode {
+  #[default]
+  Elastic,
+  PerfectlyInelastic,
+}
+
+// Resolves a collision between two bodies, where `normal` points from the first body towards
+// the second. Returns the new rest mass and velocity of each body.
+//
+// With a speed of light the four-momentum of the pair is conserved. A perfectly inelastic
+// collision turns the kinetic energy lost into rest mass, which is shared between the bodies in
+// proportion to their old rest masses. Without one the Newtonian limit is used, where rest mass
+// is conserved and a perfectly inelastic collision loses kinetic energy instead.
+pub fn resolve_collision(
+  mode: CollisionMode,
+  (m1, v1): (f32, &Vec3F),
+  (m2, v2): (f32, &Vec3F),
+  normal: &Vec3F,
+  speed_of_light: Option<f32>,
+) -> ((f32, Vec3F), (f32, Vec3F)) {
+  match (mode, speed_of_light) {
+    (CollisionMode::Elastic, Some(c)) => {
+      let (u1, u2) = elastic_collision((m1, v1), (m2, v2), normal, c);
+      ((m1, u1), (m2, u2))
+    }
+    (CollisionMode::PerfectlyInelastic, Some(c)) => {
+      let (velocity, rest_mass) = inelastic_collision((m1, v1), (m2, v2), c);
+      let share = m1 / (m1 + m2);
+      ((rest_mass * share, velocity), (rest_mass * (1f32 - share), velocity))
+    }
+    (CollisionMode::Elastic, None) => {
+      let n = normal.normalize();
+      let closing_speed = (v1 - v2).dot(n);
+      let impulse = 2f32 * m1 * m2 / (m1 + m2) * closing_speed;
+      ((m1, v1 - n * (impulse / m1)), (m2, v2 + n * (impulse / m2)))
+    }
+    (CollisionMode::PerfectlyInelastic, None) => {
+      let velocity = (v1 * m1 + v2 * m2) / (m1 + m2);
+      ((m1, velocity), (m2, velocity))
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::relativity::FourMomentum;
+  use crate::utils::{assert_close, assert_close_within, assert_vec_close};
+
+  #[test]
+  fn newtonian_elastic_conserves_momentum_and_energy() {
+    let v1 = Vec3F::new(3f32, 1f32, 0f32);
+    let v2 = Vec3F::new(-1f32, 0f32, 0f32);
+    let ((_, u1), (_, u2)) =
+      resolve_collision(CollisionMode::Elastic, (2f32, &v1), (1f32, &v2), &Vec3F::unit_x(), None);
+    assert_vec_close(u1 * 2f32 + u2, v1 * 2f32 + v2);
+    let energy = |a: Vec3F, b: Vec3F| a.magnitude2() * 2f32 + b.magnitude2();
+    assert!((energy(u1, u2) - energy(v1, v2)).abs() < 1e-4f32);
+    assert_vec_close(u1, Vec3F::new(1f32 / 3f32, 1f32, 0f32));
+  }
+
+  #[test]
+  fn newtonian_inelastic_moves_together() {
+    let ((m1, u1), (m2, u2)) = resolve_collision(
+      CollisionMode::PerfectlyInelastic,
+      (1f32, &Vec3F::new(4f32, 0f32, 0f32)),
+      (3f32, &Vec3F::zero()),
+      &Vec3F::unit_x(),
+      None,
+    );
+    assert_vec_close(u1, Vec3F::new(1f32, 0f32, 0f32));
+    assert_eq!(u1, u2);
+    assert_eq!((m1, m2), (1f32, 3f32));
+  }
+
+  #[test]
+  fn relativistic_inelastic_conserves_four_momentum() {
+    let c = 10f32;
+    let (v1, v2) = (Vec3F::new(8f32, 1f32, 0f32), Vec3F::new(-6f32, 0f32, 2f32));
+    let ((m1, u1), (m2, u2)) = resolve_collision(
+      CollisionMode::PerfectlyInelastic,
+      (2f32, &v1),
+      (1f32, &v2),
+      &Vec3F::unit_x(),
+      Some(c),
+    );
+    let before = FourMomentum::from_velocity(2f32, &v1, c) + FourMomentum::from_velocity(1f32, &v2, c);
+    let after = FourMomentum::from_velocity(m1, &u1, c) + FourMomentum::from_velocity(m2, &u2, c);
+    // Energies are of order m c², so compare them to f32 precision rather than absolutely.
+    assert_close_within(after.energy, before.energy, 1e-6f32 * before.energy);
+    assert_vec_close(after.momentum, before.momentum);
+    // The kinetic energy lost went into rest mass, shared 2:1 like the old masses.
+    assert!(m1 + m2 > 3f32);
+    assert_close(m1, 2f32 * m2);
+  }
+}
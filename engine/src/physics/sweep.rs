@@ -0,0 +1,223 @@
+use cgmath::prelude::*;
+use specs::prelude::*;
+use specs::{Component, VecStorage};
+
+use super::{Broadphase, CollisionSummary};
+use crate::utils::Vec3F;
+
+// A moving sphere can hit at most this many surfaces in a single step before the rest of the
+// step is dropped.
+pub const MAX_SWEEP_ITERATIONS: usize = 4;
+
+// Distance kept between a sphere and the surface it stopped against, so the next sweep does
+// not start inside the collider.
+const CONTACT_SKIN: f32 = 1e-3f32;
+
+// How a body responds when it hits a surface. Restitution is the fraction of the normal
+// velocity kept (bounced back), friction the fraction of the tangential velocity lost.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[storage(VecStorage)]
+pub struct PhysicsMaterial {
+  pub restitution: f32,
+  pub friction: f32,
+}
+
+impl Default for PhysicsMaterial {
+  fn default() -> Self {
+    Self {
+      restitution: 0f32,
+      friction: 0f32,
+    }
+  }
+}
+
+impl PhysicsMaterial {
+  pub fn new(restitution: f32, friction: f32) -> Self {
+    Self { restitution, friction }
+  }
+
+  // Velocity after hitting a surface with `normal`. A restitution of zero slides along the
+  // surface, one reflects the velocity.
+  pub fn bounce(&self, velocity: &Vec3F, normal: &Vec3F) -> Vec3F {
+    let normal_velocity = normal * velocity.dot(*normal);
+    let tangent_velocity = velocity - normal_velocity;
+    tangent_velocity * (1f32 - self.friction) - normal_velocity * self.restitution
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct SweepResult {
+  pub position: Vec3F,
+  pub velocity: Vec3F,
+  pub contacts: Vec<(Entity, CollisionSummary)>,
+}
+
+// Moves a sphere through the colliders in `broadphase` for `dt` seconds. Motion is clamped to
+// the earliest time of impact, the velocity bounced off the surface, and the remainder of the
+// step swept again, up to `MAX_SWEEP_ITERATIONS` times. `narrowphase` collides the sphere against
+// one candidate entity, and `ignore` skips the sphere's own collider.
+pub fn sweep_sphere<F>(
+  broadphase: &Broadphase,
+  narrowphase: F,
+  ignore: Option<Entity>,
+  (center, radius): (&Vec3F, f32),
+  velocity: &Vec3F,
+  dt: f32,
+  material: &PhysicsMaterial,
+) -> SweepResult
+where
+  F: Fn(Entity, (&Vec3F, &f32), &Vec3F) -> Option<CollisionSummary>,
+{
+  let mut result = SweepResult {
+    position: *center,
+    velocity: *velocity,
+    contacts: Vec::new(),
+  };
+  let mut remaining = dt;
+  for _ in 0..MAX_SWEEP_ITERATIONS {
+    let reach = radius + result.velocity.magnitude() * remaining;
+    let earliest = broadphase
+      .candidates(&result.position, reach)
+      .into_iter()
+      .filter(|candidate| Some(candidate.entity) != ignore)
+      .filter_map(|candidate| {
+        narrowphase(candidate.entity, (&result.position, &radius), &result.velocity)
+          .map(|summary| (candidate.entity, summary))
+      })
+      // Only surfaces being moved into count, otherwise a sphere resting on a surface would
+      // keep colliding with it.
+      .filter(|(_, summary)| summary.time <= remaining && result.velocity.dot(summary.surface_normal) < 0f32)
+      .min_by(|a, b| a.1.cmp(&b.1));
+
+    match earliest {
+      Some((entity, summary)) => {
+        result.position = summary.position + summary.surface_normal * CONTACT_SKIN;
+        result.velocity = material.bounce(&result.velocity, &summary.surface_normal);
+        result.contacts.push((entity, summary));
+        remaining -= summary.time;
+      }
+      None => {
+        result.position += result.velocity * remaining;
+        return result;
+      }
+    }
+  }
+  result
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::physics::{AxisAlignedCubeCollision, ColliderEntry, Collision, TransformComponent};
+  use crate::utils::assert_close;
+
+  // One axis-aligned box per (center, scale) pair.
+  fn scene(world: &mut World, boxes: &[(Vec3F, Vec3F)]) -> (Broadphase, Vec<(Entity, AxisAlignedCubeCollision)>) {
+    let colliders: Vec<(Entity, AxisAlignedCubeCollision)> = boxes
+      .iter()
+      .map(|(center, scale)| {
+        let mut transform = TransformComponent::default();
+        transform.translation = *center;
+        transform.scale = *scale;
+        (
+          world.create_entity().build(),
+          AxisAlignedCubeCollision::from_transform(&transform),
+        )
+      })
+      .collect();
+    let mut broadphase = Broadphase::default();
+    broadphase.rebuild(
+      colliders
+        .iter()
+        .map(|(entity, aabb)| ColliderEntry {
+          entity: *entity,
+          center: *aabb.center(),
+          bounding_radius: aabb.bounding_radius(),
+        })
+        .collect(),
+    );
+    (broadphase, colliders)
+  }
+
+  fn sweep(
+    broadphase: &Broadphase,
+    colliders: &Vec<(Entity, AxisAlignedCubeCollision)>,
+    velocity: Vec3F,
+    material: PhysicsMaterial,
+  ) -> SweepResult {
+    let narrowphase = |e: Entity, sphere: (&Vec3F, &f32), v: &Vec3F| {
+      colliders
+        .iter()
+        .find(|(c, _)| *c == e)
+        .and_then(|(_, aabb)| aabb.sphere_collision(sphere, v))
+    };
+    sweep_sphere(
+      broadphase,
+      narrowphase,
+      None,
+      (&Vec3F::zero(), 0.5f32),
+      &velocity,
+      1f32,
+      &material,
+    )
+  }
+
+  #[test]
+  fn stops_at_contact_instead_of_tunnelling() {
+    let mut world = World::new();
+    let (broadphase, colliders) = scene(
+      &mut world,
+      &[(Vec3F::new(5f32, 0f32, 0f32), Vec3F::new(1f32, 1f32, 1f32))],
+    );
+    let result = sweep(
+      &broadphase,
+      &colliders,
+      Vec3F::new(20f32, 0f32, 0f32),
+      PhysicsMaterial::default(),
+    );
+    // Contacts leave the sphere CONTACT_SKIN off the surface.
+    assert_close(result.position.x, 4f32 - CONTACT_SKIN);
+    assert_close(result.velocity.magnitude(), 0f32);
+    assert_eq!(result.contacts.len(), 1);
+  }
+
+  #[test]
+  fn slides_along_the_surface_with_friction() {
+    let mut world = World::new();
+    let (broadphase, colliders) = scene(
+      &mut world,
+      &[(Vec3F::new(5f32, 0f32, 0f32), Vec3F::new(1f32, 40f32, 1f32))],
+    );
+    let material = PhysicsMaterial::new(0f32, 0.5f32);
+    let result = sweep(&broadphase, &colliders, Vec3F::new(8f32, 4f32, 0f32), material);
+    // Contact after 0.5s at (4, 2), then half the tangential speed for the remaining 0.5s.
+    assert_close(result.position.x, 4f32 - CONTACT_SKIN);
+    assert_close(result.position.y, 3f32);
+    assert_close(result.velocity.y, 2f32);
+  }
+
+  #[test]
+  fn bounces_off_several_surfaces_in_one_step() {
+    let mut world = World::new();
+    let tall = Vec3F::new(1f32, 40f32, 1f32);
+    let (broadphase, colliders) = scene(
+      &mut world,
+      &[
+        (Vec3F::new(2f32, 0f32, 0f32), tall),
+        (Vec3F::new(-2f32, 0f32, 0f32), tall),
+      ],
+    );
+    let result = sweep(
+      &broadphase,
+      &colliders,
+      Vec3F::new(4f32, 0f32, 0f32),
+      PhysicsMaterial::new(1f32, 0f32),
+    );
+    // 1 unit to the right wall, 2 back to the left wall, 1 more to the right.
+    // Each contact's skin is made up on the way back, so the sphere ends up one skin further
+    // along per contact.
+    assert_eq!(result.contacts.len(), 2);
+    assert_close(result.position.x, 2f32 * CONTACT_SKIN);
+    assert_close(result.velocity.x, 4f32);
+  }
+}
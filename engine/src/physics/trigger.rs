@@ -0,0 +1,36 @@
+use specs::{Component, NullStorage};
+
+use crate::ecs::Guid;
+use crate::events::StatefulEventChannel;
+
+// Turns a collider into a trigger volume: `CanCollide` bodies pass straight through it, and
+// `TriggerSystem` reports when they enter, stay in and leave it instead.
+#[derive(Component, Debug, Default, Clone, Copy)]
+#[storage(NullStorage)]
+pub struct Trigger;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum TriggerPhase {
+  // First frame the body overlaps the trigger.
+  Enter,
+  // Every following frame it still does.
+  Stay,
+  // First frame it no longer does, or either entity was deleted.
+  Exit,
+}
+
+// Subscribe to every trigger event or to those where either participant has the given `Guid`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum TriggerTopic {
+  All,
+  Involving(Guid),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriggerEvent {
+  pub phase: TriggerPhase,
+  pub trigger: Guid,
+  pub other: Guid,
+}
+
+pub type TriggerEventChannel = StatefulEventChannel<TriggerTopic, TriggerEvent>;
@@ -52,7 +52,7 @@ impl Screen {
       .with_source_file("shaders/screen_shader.glsl")
       .build();
     Self {
-      framebuffer: Framebuffer::from_dims(x_dim, y_dim),
+      framebuffer: Framebuffer::hdr(x_dim, y_dim),
       shader,
       screen_quad,
     }
@@ -64,13 +64,18 @@ impl Screen {
     self.framebuffer.bind_texture_slot(texture_slot);
     let buffer_texture = Uniform::Int(texture_slot as i32);
     self.shader.set_uniform("tex", &buffer_texture);
-    self.screen_quad.bind();
-    self.screen_quad.draw(self.shader.element_type());
-    self.screen_quad.unbind();
+    self.draw_quad(&self.shader);
     self.framebuffer.unbind_texture_slot(texture_slot);
     self.shader.unbind();
   }
 
+  // Covers the bound framebuffer using the bound shader.
+  pub fn draw_quad(&self, shader: &Shader) {
+    self.screen_quad.bind();
+    self.screen_quad.draw(shader.element_type());
+    self.screen_quad.unbind();
+  }
+
   pub fn bind_framebuffer(&self) {
     self.framebuffer.bind();
   }
@@ -84,6 +89,10 @@ impl Screen {
     (dims.x as f32) / (dims.y as f32)
   }
 
+  pub fn framebuffer(&self) -> &Framebuffer {
+    &self.framebuffer
+  }
+
   pub fn set_framebuffer(&mut self, fb: Framebuffer) {
     self.framebuffer = fb;
   }
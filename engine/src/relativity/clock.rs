@@ -0,0 +1,85 @@
+use specs::prelude::*;
+use specs::{Component, VecStorage};
+
+use super::velocity_lorentz_factor;
+use crate::utils::Vec3F;
+
+// Accumulates the proper time experienced by an entity alongside the coordinate (world) time
+// that elapsed while it was alive.
+#[derive(Component, Debug, Clone, Default)]
+#[storage(VecStorage)]
+pub struct ProperClock {
+  proper_time: f64,
+  coordinate_time: f64,
+}
+
+impl ProperClock {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // Advances the clock by one world timestep, dtau = dt / gamma.
+  pub fn tick(&mut self, dt: f32, velocity: &Vec3F, speed_of_light: f32) -> f32 {
+    let d_tau = dt / velocity_lorentz_factor(velocity, speed_of_light);
+    self.proper_time += d_tau as f64;
+    self.coordinate_time += dt as f64;
+    d_tau
+  }
+
+  pub fn proper_time(&self) -> f64 {
+    self.proper_time
+  }
+
+  pub fn coordinate_time(&self) -> f64 {
+    self.coordinate_time
+  }
+
+  // How far this clock has fallen behind a stationary clock started at the same moment.
+  pub fn dilation(&self) -> f64 {
+    self.coordinate_time - self.proper_time
+  }
+}
+
+// World-frame and player-frame time for the current frame. Gameplay code that should run on
+// the player's clock can read `player_dt` instead of the global `Timestep`.
+#[derive(Debug, Clone, Default)]
+pub struct RelativisticTime {
+  pub world_time: f64,
+  pub world_dt: f32,
+  pub player_time: f64,
+  pub player_dt: f32,
+}
+
+impl RelativisticTime {
+  // The twin-paradox gap between a stationary reference clock and the player's clock.
+  pub fn twin_gap(&self) -> f64 {
+    self.world_time - self.player_time
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn stationary_clock_keeps_coordinate_time() {
+    let mut clock = ProperClock::new();
+    for _ in 0..10 {
+      clock.tick(0.1f32, &Vec3F::new(0f32, 0f32, 0f32), 1f32);
+    }
+    assert_eq!((clock.proper_time() - 1f64).abs() < 1e-5, true);
+    assert_eq!(clock.dilation().abs() < 1e-5, true);
+  }
+
+  #[test]
+  fn moving_clock_runs_slow() {
+    let mut clock = ProperClock::new();
+    let velocity = Vec3F::new(0.6f32, 0f32, 0f32);
+    for _ in 0..10 {
+      clock.tick(0.125f32, &velocity, 1f32);
+    }
+    assert_eq!((clock.coordinate_time() - 1.25f64).abs() < 1e-5, true);
+    assert_eq!((clock.proper_time() - 1f64).abs() < 1e-5, true);
+    assert_eq!((clock.dilation() - 0.25f64).abs() < 1e-5, true);
+  }
+}
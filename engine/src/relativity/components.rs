@@ -0,0 +1,8 @@
+use specs::prelude::*;
+use specs::{Component, NullStorage};
+
+// Opts a `RigidBody` into relativistic integration. Its acceleration is treated as a
+// proper acceleration and its speed can never reach the `SpeedOfLight`.
+#[derive(Component, Debug, Clone, Default)]
+#[storage(NullStorage)]
+pub struct Relativistic;
@@ -0,0 +1,125 @@
+use cgmath::prelude::*;
+
+use super::{lorentz_factor, MAX_BETA};
+use crate::utils::{Color, Vec3F};
+
+// Each color channel is treated as a narrow spectral line at one of these wavelengths (nm).
+// They are chosen so that `wavelength_to_rgb` maps them onto pure primaries, which keeps an
+// unshifted color unchanged. Mirrors the constants in `shaders/lorentz_helper.glsl`.
+pub const RED_WAVELENGTH: f32 = 650f32;
+pub const GREEN_WAVELENGTH: f32 = 510f32;
+pub const BLUE_WAVELENGTH: f32 = 440f32;
+
+const MIN_SPEED: f32 = 1e-6f32;
+
+// Ratio of observed to emitted frequency for light reaching an observer moving with
+// `observer_velocity` from a stationary source in the direction `to_source`.
+// Values above 1 are blueshifted, values below 1 are redshifted.
+pub fn doppler_factor(observer_velocity: &Vec3F, to_source: &Vec3F, speed_of_light: f32) -> f32 {
+  let speed = observer_velocity.magnitude();
+  if speed < MIN_SPEED || to_source.magnitude2() < MIN_SPEED {
+    return 1f32;
+  }
+  let beta = (speed / speed_of_light).min(MAX_BETA);
+  let cos_theta = observer_velocity.normalize().dot(to_source.normalize());
+  lorentz_factor(beta) * (1f32 + beta * cos_theta)
+}
+
+// Approximate visible-spectrum color of a single wavelength in nanometers (Dan Bruton's
+// piecewise fit). Wavelengths outside 380-780nm are invisible and map to black.
+pub fn wavelength_to_rgb(wavelength: f32) -> Color {
+  let w = wavelength;
+  let rgb = if w < 380f32 {
+    Color::new(0f32, 0f32, 0f32)
+  } else if w < 440f32 {
+    Color::new(-(w - 440f32) / 60f32, 0f32, 1f32)
+  } else if w < 490f32 {
+    Color::new(0f32, (w - 440f32) / 50f32, 1f32)
+  } else if w < 510f32 {
+    Color::new(0f32, 1f32, -(w - 510f32) / 20f32)
+  } else if w < 580f32 {
+    Color::new((w - 510f32) / 70f32, 1f32, 0f32)
+  } else if w < 645f32 {
+    Color::new(1f32, -(w - 645f32) / 65f32, 0f32)
+  } else if w <= 780f32 {
+    Color::new(1f32, 0f32, 0f32)
+  } else {
+    Color::new(0f32, 0f32, 0f32)
+  };
+  // Fall off in intensity towards the edges of human vision.
+  let falloff = if !(380f32..=780f32).contains(&w) {
+    0f32
+  } else if w < 420f32 {
+    0.3f32 + 0.7f32 * (w - 380f32) / 40f32
+  } else if w > 700f32 {
+    0.3f32 + 0.7f32 * (780f32 - w) / 80f32
+  } else {
+    1f32
+  };
+  rgb * falloff
+}
+
+// Shifts every channel's wavelength by the doppler factor and recombines them.
+pub fn doppler_shift_color(color: &Color, doppler: f32) -> Color {
+  wavelength_to_rgb(RED_WAVELENGTH / doppler) * color.x
+    + wavelength_to_rgb(GREEN_WAVELENGTH / doppler) * color.y
+    + wavelength_to_rgb(BLUE_WAVELENGTH / doppler) * color.z
+}
+
+// Relativistic beaming: the brightness of a source scales with the fourth power of the
+// doppler factor, so objects ahead of a fast observer brighten and objects behind dim.
+pub fn searchlight_intensity(doppler: f32) -> f32 {
+  doppler.powi(4)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::{assert_close, assert_vec_close};
+
+  #[test]
+  fn doppler_factor_matches_known_values() {
+    let velocity = Vec3F::new(0.6f32, 0f32, 0f32);
+    // Approaching: sqrt((1 + b) / (1 - b))
+    assert_close(doppler_factor(&velocity, &Vec3F::unit_x(), 1f32), 2f32);
+    // Receding: sqrt((1 - b) / (1 + b))
+    assert_close(doppler_factor(&velocity, &-Vec3F::unit_x(), 1f32), 0.5f32);
+    // Transverse: pure time dilation
+    assert_close(doppler_factor(&velocity, &Vec3F::unit_y(), 1f32), 1.25f32);
+    assert_close(doppler_factor(&Vec3F::zero(), &Vec3F::unit_y(), 1f32), 1f32);
+  }
+
+  #[test]
+  fn wavelength_to_rgb_matches_known_values() {
+    assert_vec_close(wavelength_to_rgb(BLUE_WAVELENGTH), Color::new(0f32, 0f32, 1f32));
+    assert_vec_close(wavelength_to_rgb(475f32), Color::new(0f32, 0.7f32, 1f32));
+    assert_vec_close(wavelength_to_rgb(GREEN_WAVELENGTH), Color::new(0f32, 1f32, 0f32));
+    assert_vec_close(wavelength_to_rgb(580f32), Color::new(1f32, 1f32, 0f32));
+    assert_vec_close(wavelength_to_rgb(RED_WAVELENGTH), Color::new(1f32, 0f32, 0f32));
+    assert_vec_close(wavelength_to_rgb(740f32), Color::new(0.65f32, 0f32, 0f32));
+    assert_vec_close(wavelength_to_rgb(300f32), Color::new(0f32, 0f32, 0f32));
+    assert_vec_close(wavelength_to_rgb(900f32), Color::new(0f32, 0f32, 0f32));
+  }
+
+  #[test]
+  fn unshifted_color_is_unchanged() {
+    let color = Color::new(0.2f32, 0.5f32, 0.9f32);
+    assert_vec_close(doppler_shift_color(&color, 1f32), color);
+    assert_close(searchlight_intensity(1f32), 1f32);
+  }
+
+  #[test]
+  fn blueshift_moves_red_towards_green() {
+    let red = Color::new(1f32, 0f32, 0f32);
+    let shifted = doppler_shift_color(&red, RED_WAVELENGTH / GREEN_WAVELENGTH);
+    assert_vec_close(shifted, Color::new(0f32, 1f32, 0f32));
+  }
+
+  #[test]
+  fn strong_redshift_pushes_blue_out_of_view() {
+    let blue = Color::new(0f32, 0f32, 1f32);
+    let shifted = doppler_shift_color(&blue, 0.5f32);
+    assert_vec_close(shifted, Color::new(0f32, 0f32, 0f32));
+    assert_close(searchlight_intensity(0.5f32), 0.0625f32);
+  }
+}
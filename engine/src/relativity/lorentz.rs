@@ -0,0 +1,225 @@
+use cgmath::prelude::*;
+
+use crate::utils::{Mat3F, Vec3F};
+
+// Speed of light in world units per second.
+pub const DEFAULT_SPEED_OF_LIGHT: f32 = 20f32;
+
+// Beta is clamped just below 1 so that gamma stays finite.
+pub const MAX_BETA: f32 = 0.999f32;
+
+const MIN_SPEED: f32 = 1e-6f32;
+
+pub fn lorentz_factor(beta: f32) -> f32 {
+  1f32 / (1f32 - beta * beta).sqrt()
+}
+
+pub fn velocity_lorentz_factor(velocity: &Vec3F, speed_of_light: f32) -> f32 {
+  let beta = (velocity.magnitude() / speed_of_light).min(MAX_BETA);
+  lorentz_factor(beta)
+}
+
+// Proper velocity u = gamma * v. Unlike coordinate velocity it is unbounded, which makes it
+// the natural quantity to integrate when applying a proper acceleration. Only velocities at or
+// past the speed of light are clamped, anything slower maps exactly.
+pub fn proper_velocity(velocity: &Vec3F, speed_of_light: f32) -> Vec3F {
+  let beta2 = (velocity.magnitude2() / (speed_of_light * speed_of_light)).min(1f32 - f32::EPSILON);
+  velocity / (1f32 - beta2).sqrt()
+}
+
+pub fn velocity_from_proper(proper: &Vec3F, speed_of_light: f32) -> Vec3F {
+  proper / (1f32 + proper.magnitude2() / (speed_of_light * speed_of_light)).sqrt()
+}
+
+pub fn rapidity(velocity: &Vec3F, speed_of_light: f32) -> f32 {
+  let beta = (velocity.magnitude() / speed_of_light).min(MAX_BETA);
+  beta.atanh()
+}
+
+// Limits the speed of `velocity` to MAX_BETA.
+pub fn clamp_velocity(velocity: &Vec3F, speed_of_light: f32) -> Vec3F {
+  let max_speed = MAX_BETA * speed_of_light;
+  if velocity.magnitude2() > max_speed * max_speed {
+    velocity.normalize_to(max_speed)
+  } else {
+    *velocity
+  }
+}
+
+// Applies `acceleration` as a proper acceleration for `dt` seconds. The resulting speed
+// approaches the speed of light asymptotically no matter how long the thrust is held. The
+// proper velocity is integrated as is, only the coordinate velocity it gives back is clamped.
+pub fn integrate_proper_velocity(velocity: &Vec3F, acceleration: &Vec3F, dt: f32, speed_of_light: f32) -> Vec3F {
+  let proper = proper_velocity(velocity, speed_of_light) + acceleration * dt;
+  clamp_velocity(&velocity_from_proper(&proper, speed_of_light), speed_of_light)
+}
+
+// Builds an orthonormal basis whose first column points along `direction`.
+// The returned matrix maps velocity-aligned coordinates back into world space.
+pub fn velocity_basis(direction: &Vec3F) -> Mat3F {
+  let x_axis = direction.normalize();
+  let helper = if x_axis.y.abs() < 0.9f32 {
+    Vec3F::unit_y()
+  } else {
+    Vec3F::unit_x()
+  };
+  let z_axis = x_axis.cross(helper).normalize();
+  let y_axis = z_axis.cross(x_axis).normalize();
+  Mat3F::from_cols(x_axis, y_axis, z_axis)
+}
+
+// Everything the `lorentz_helper.glsl` shaders need to know about the observer's motion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LorentzFrame {
+  pub beta: f32,
+  pub gamma: f32,
+  pub change_of_basis: Mat3F,
+  pub change_of_basis_inverse: Mat3F,
+}
+
+impl Default for LorentzFrame {
+  fn default() -> Self {
+    Self::at_rest()
+  }
+}
+
+impl LorentzFrame {
+  pub fn at_rest() -> Self {
+    Self {
+      beta: 0f32,
+      gamma: 1f32,
+      change_of_basis: Mat3F::identity(),
+      change_of_basis_inverse: Mat3F::identity(),
+    }
+  }
+
+  pub fn from_velocity(velocity: &Vec3F, speed_of_light: f32) -> Self {
+    let speed = velocity.magnitude();
+    if speed < MIN_SPEED {
+      return Self::at_rest();
+    }
+    let beta = (speed / speed_of_light).min(MAX_BETA);
+    let basis = velocity_basis(velocity);
+    Self {
+      beta,
+      gamma: lorentz_factor(beta),
+      change_of_basis: basis.transpose(),
+      change_of_basis_inverse: basis,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::{assert_close, assert_close_within, assert_vec_close};
+
+  #[test]
+  fn frame_at_rest_is_identity() {
+    let frame = LorentzFrame::from_velocity(&Vec3F::zero(), DEFAULT_SPEED_OF_LIGHT);
+    assert_eq!(frame, LorentzFrame::at_rest());
+  }
+
+  #[test]
+  fn gamma_matches_known_values() {
+    let frame = LorentzFrame::from_velocity(&Vec3F::new(0f32, 0f32, 0.6f32), 1f32);
+    assert_close(frame.beta, 0.6f32);
+    assert_close(frame.gamma, 1.25f32);
+  }
+
+  #[test]
+  fn beta_is_clamped_below_one() {
+    let frame = LorentzFrame::from_velocity(&Vec3F::new(5f32, 0f32, 0f32), 1f32);
+    assert_close(frame.beta, MAX_BETA);
+    assert_eq!(frame.gamma.is_finite(), true);
+  }
+
+  #[test]
+  fn proper_velocity_round_trips() {
+    let velocity = Vec3F::new(3f32, -4f32, 12f32);
+    let proper = proper_velocity(&velocity, 20f32);
+    assert_close(proper.magnitude(), 13f32 * lorentz_factor(13f32 / 20f32));
+    let round_trip = velocity_from_proper(&proper, 20f32);
+    assert_close(round_trip.x, velocity.x);
+    assert_close(round_trip.y, velocity.y);
+    assert_close(round_trip.z, velocity.z);
+  }
+
+  #[test]
+  fn proper_velocity_round_trips_near_the_limit() {
+    let c = 20f32;
+    let velocity = Vec3F::new(0f32, 0.9995f32 * c, 0f32);
+    let proper = proper_velocity(&velocity, c);
+    // 1 - beta^2 keeps only a few significant digits this close to c.
+    assert_close_within(proper.y, velocity.y * lorentz_factor(0.9995f32), 1e-3f32 * proper.y);
+    assert_vec_close(velocity_from_proper(&proper, c), velocity);
+  }
+
+  #[test]
+  fn braking_from_past_max_beta_keeps_its_momentum() {
+    let c = 10f32;
+    let velocity = Vec3F::new(0.9995f32 * c, 0f32, 0f32);
+    let thrust = Vec3F::new(-2000f32, 0f32, 0f32);
+    // Integrated in one go, the proper velocity changes by exactly thrust * dt.
+    let expected = velocity * lorentz_factor(0.9995f32) + thrust * 0.1f32;
+    let result = integrate_proper_velocity(&velocity, &thrust, 0.1f32, c);
+    assert!(result.magnitude() < MAX_BETA * c);
+    // Same precision loss near c as above.
+    assert_close_within(proper_velocity(&result, c).x, expected.x, 1e-3f32 * expected.x);
+  }
+
+  #[test]
+  fn integrated_speed_is_clamped_to_max_beta() {
+    let c = 10f32;
+    let thrust = Vec3F::new(0f32, 0f32, 1e6f32);
+    let result = integrate_proper_velocity(&Vec3F::zero(), &thrust, 1f32, c);
+    assert_close(result.magnitude(), MAX_BETA * c);
+  }
+
+  #[test]
+  fn rapidity_adds_for_collinear_boosts() {
+    let c = 1f32;
+    let v1 = 0.5f32;
+    let v2 = 0.3f32;
+    let combined = (v1 + v2) / (1f32 + v1 * v2);
+    let sum = rapidity(&Vec3F::new(v1, 0f32, 0f32), c) + rapidity(&Vec3F::new(v2, 0f32, 0f32), c);
+    assert_close(rapidity(&Vec3F::new(combined, 0f32, 0f32), c), sum);
+  }
+
+  #[test]
+  fn sustained_thrust_approaches_light_speed() {
+    let c = 10f32;
+    let thrust = Vec3F::new(0f32, 0f32, 50f32);
+    let mut velocity = Vec3F::zero();
+    let mut last_speed = 0f32;
+    for _ in 0..1000 {
+      velocity = integrate_proper_velocity(&velocity, &thrust, 0.016f32, c);
+      let speed = velocity.magnitude();
+      assert_eq!(speed < c, true);
+      assert_eq!(speed >= last_speed, true);
+      last_speed = speed;
+    }
+    assert_eq!(last_speed > 0.99f32 * c, true);
+  }
+
+  #[test]
+  fn change_of_basis_aligns_velocity_with_x() {
+    for velocity in [
+      Vec3F::new(1f32, 2f32, 3f32),
+      Vec3F::new(0f32, 4f32, 0f32),
+      Vec3F::new(0f32, -1f32, 0.01f32),
+    ]
+    .iter()
+    {
+      let frame = LorentzFrame::from_velocity(velocity, 10f32);
+      let aligned = frame.change_of_basis * velocity;
+      assert_close(aligned.x, velocity.magnitude());
+      assert_close(aligned.y, 0f32);
+      assert_close(aligned.z, 0f32);
+      let round_trip = frame.change_of_basis_inverse * aligned;
+      assert_close(round_trip.x, velocity.x);
+      assert_close(round_trip.y, velocity.y);
+      assert_close(round_trip.z, velocity.z);
+    }
+  }
+}
@@ -0,0 +1,19 @@
+mod clock;
+mod components;
+mod doppler;
+mod lorentz;
+mod momentum;
+mod observer;
+mod retarded;
+mod speed_of_light;
+mod worldline;
+
+pub use self::clock::*;
+pub use self::components::*;
+pub use self::doppler::*;
+pub use self::lorentz::*;
+pub use self::momentum::*;
+pub use self::observer::*;
+pub use self::retarded::*;
+pub use self::speed_of_light::*;
+pub use self::worldline::*;
@@ -0,0 +1,197 @@
+use std::ops::{Add, Sub};
+
+use cgmath::prelude::*;
+
+use super::proper_velocity;
+use crate::utils::Vec3F;
+
+const MIN_BOOST_SPEED: f32 = 1e-6f32;
+
+// Energy-momentum four-vector (E, p), with E = gamma m c^2 and p = gamma m v.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FourMomentum {
+  pub energy: f32,
+  pub momentum: Vec3F,
+}
+
+impl FourMomentum {
+  pub fn new(energy: f32, momentum: Vec3F) -> Self {
+    Self { energy, momentum }
+  }
+
+  pub fn from_velocity(rest_mass: f32, velocity: &Vec3F, speed_of_light: f32) -> Self {
+    let momentum = proper_velocity(velocity, speed_of_light) * rest_mass;
+    Self::from_momentum(rest_mass, momentum, speed_of_light)
+  }
+
+  // The on-shell four-momentum of a particle of `rest_mass` carrying `momentum`.
+  pub fn from_momentum(rest_mass: f32, momentum: Vec3F, speed_of_light: f32) -> Self {
+    let c2 = speed_of_light * speed_of_light;
+    let energy = (rest_mass * rest_mass * c2 * c2 + momentum.magnitude2() * c2).sqrt();
+    Self { energy, momentum }
+  }
+
+  pub fn velocity(&self, speed_of_light: f32) -> Vec3F {
+    self.momentum * (speed_of_light * speed_of_light / self.energy)
+  }
+
+  // Rest mass of a particle (or system of particles) with this four-momentum,
+  // m^2 c^4 = E^2 - p^2 c^2.
+  pub fn invariant_mass(&self, speed_of_light: f32) -> f32 {
+    let c2 = speed_of_light * speed_of_light;
+    (self.energy * self.energy - self.momentum.magnitude2() * c2)
+      .max(0f32)
+      .sqrt()
+      / c2
+  }
+
+  // Expresses this four-momentum in a frame moving with `velocity` relative to the current one.
+  pub fn boost(&self, velocity: &Vec3F, speed_of_light: f32) -> Self {
+    let speed = velocity.magnitude();
+    if speed < MIN_BOOST_SPEED {
+      return *self;
+    }
+    let direction = velocity / speed;
+    let beta = speed / speed_of_light;
+    let gamma = 1f32 / (1f32 - beta * beta).sqrt();
+    let p_parallel = self.momentum.dot(direction);
+    let energy = gamma * (self.energy - speed * p_parallel);
+    let new_parallel = gamma * (p_parallel - speed * self.energy / (speed_of_light * speed_of_light));
+    Self {
+      energy,
+      momentum: self.momentum + direction * (new_parallel - p_parallel),
+    }
+  }
+}
+
+impl Add for FourMomentum {
+  type Output = Self;
+
+  fn add(self, rhs: Self) -> Self {
+    Self::new(self.energy + rhs.energy, self.momentum + rhs.momentum)
+  }
+}
+
+impl Sub for FourMomentum {
+  type Output = Self;
+
+  fn sub(self, rhs: Self) -> Self {
+    Self::new(self.energy - rhs.energy, self.momentum - rhs.momentum)
+  }
+}
+
+// Elastic collision between two bodies touching along `normal`. The momenta are reflected
+// about the contact plane in the centre-of-momentum frame, where an elastic collision leaves
+// each energy unchanged, and then boosted back. Returns the new velocities.
+pub fn elastic_collision(
+  (m1, v1): (f32, &Vec3F),
+  (m2, v2): (f32, &Vec3F),
+  normal: &Vec3F,
+  speed_of_light: f32,
+) -> (Vec3F, Vec3F) {
+  let c = speed_of_light;
+  let p1 = FourMomentum::from_velocity(m1, v1, c);
+  let p2 = FourMomentum::from_velocity(m2, v2, c);
+  let cm_velocity = (p1 + p2).velocity(c);
+  let p1_cm = p1.boost(&cm_velocity, c);
+  let p2_cm = p2.boost(&cm_velocity, c);
+  let n = normal.normalize();
+  let p1_after = FourMomentum::new(p1_cm.energy, p1_cm.momentum - n * (2f32 * p1_cm.momentum.dot(n)));
+  let p2_after = FourMomentum::new(p2_cm.energy, p2_cm.momentum - n * (2f32 * p2_cm.momentum.dot(n)));
+  (
+    p1_after.boost(&-cm_velocity, c).velocity(c),
+    p2_after.boost(&-cm_velocity, c).velocity(c),
+  )
+}
+
+// Perfectly inelastic collision: both bodies leave with the velocity of the combined system.
+// Returns that velocity and the rest mass of the composite, which exceeds m1 + m2 by the
+// kinetic energy converted to heat.
+pub fn inelastic_collision((m1, v1): (f32, &Vec3F), (m2, v2): (f32, &Vec3F), speed_of_light: f32) -> (Vec3F, f32) {
+  let total = FourMomentum::from_velocity(m1, v1, speed_of_light) + FourMomentum::from_velocity(m2, v2, speed_of_light);
+  (total.velocity(speed_of_light), total.invariant_mass(speed_of_light))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::relativity::lorentz_factor;
+  use crate::utils::{assert_close, assert_close_within, assert_vec_close};
+
+  const C: f32 = 10f32;
+
+  fn total(m1: f32, v1: &Vec3F, m2: f32, v2: &Vec3F) -> FourMomentum {
+    FourMomentum::from_velocity(m1, v1, C) + FourMomentum::from_velocity(m2, v2, C)
+  }
+
+  #[test]
+  fn invariant_mass_is_frame_independent() {
+    let p = FourMomentum::from_velocity(2f32, &Vec3F::new(6f32, 0f32, 3f32), C);
+    assert_close(p.invariant_mass(C), 2f32);
+    let boosted = p.boost(&Vec3F::new(-2f32, 5f32, 0f32), C);
+    assert_close(boosted.invariant_mass(C), 2f32);
+    assert_vec_close(p.boost(&p.velocity(C), C).momentum, Vec3F::zero());
+  }
+
+  #[test]
+  fn equal_mass_elastic_head_on_swaps_velocities() {
+    let v1 = Vec3F::new(8f32, 0f32, 0f32);
+    let v2 = Vec3F::new(-3f32, 0f32, 0f32);
+    let (u1, u2) = elastic_collision((1f32, &v1), (1f32, &v2), &Vec3F::unit_x(), C);
+    assert_vec_close(u1, v2);
+    assert_vec_close(u2, v1);
+  }
+
+  #[test]
+  fn elastic_collision_conserves_four_momentum() {
+    let v1 = Vec3F::new(7f32, 1f32, 0f32);
+    let v2 = Vec3F::new(-2f32, 0f32, 4f32);
+    let normal = Vec3F::new(1f32, 0.5f32, -0.2f32);
+    let (u1, u2) = elastic_collision((3f32, &v1), (1f32, &v2), &normal, C);
+    let before = total(3f32, &v1, 1f32, &v2);
+    let after = total(3f32, &u1, 1f32, &u2);
+    // Energies are of order m c², hundreds of units, so compare them to f32 precision rather
+    // than absolutely.
+    assert_close_within(after.energy, before.energy, 1e-6f32 * before.energy);
+    assert_vec_close(after.momentum, before.momentum);
+    // Tangential velocity is unaffected in the centre-of-momentum frame, so nothing moves
+    // faster than light afterwards.
+    assert!(u1.magnitude() < C && u2.magnitude() < C);
+  }
+
+  #[test]
+  fn elastic_collision_matches_newtonian_limit() {
+    let c = 1e4f32;
+    let v1 = Vec3F::new(2f32, 0f32, 0f32);
+    let v2 = Vec3F::zero();
+    let (u1, u2) = elastic_collision((1f32, &v1), (3f32, &v2), &Vec3F::unit_x(), c);
+    // u1 = (m1 - m2) / (m1 + m2) v1, u2 = 2 m1 / (m1 + m2) v1
+    assert_vec_close(u1, Vec3F::new(-1f32, 0f32, 0f32));
+    assert_vec_close(u2, Vec3F::new(1f32, 0f32, 0f32));
+  }
+
+  #[test]
+  fn inelastic_head_on_converts_kinetic_energy_to_mass() {
+    let v = 6f32;
+    let (velocity, mass) = inelastic_collision(
+      (1f32, &Vec3F::new(v, 0f32, 0f32)),
+      (1f32, &Vec3F::new(-v, 0f32, 0f32)),
+      C,
+    );
+    assert_vec_close(velocity, Vec3F::zero());
+    assert_close(mass, 2f32 * lorentz_factor(v / C));
+  }
+
+  #[test]
+  fn inelastic_collision_conserves_four_momentum() {
+    let v1 = Vec3F::new(9f32, 0f32, 0f32);
+    let v2 = Vec3F::new(0f32, 2f32, 0f32);
+    let (velocity, mass) = inelastic_collision((2f32, &v1), (1f32, &v2), C);
+    let before = total(2f32, &v1, 1f32, &v2);
+    let after = FourMomentum::from_velocity(mass, &velocity, C);
+    // Energies are of order m c², hundreds of units, so compare them to f32 precision rather
+    // than absolutely.
+    assert_close_within(after.energy, before.energy, 1e-6f32 * before.energy);
+    assert_vec_close(after.momentum, before.momentum);
+  }
+}
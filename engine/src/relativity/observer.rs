@@ -0,0 +1,180 @@
+use cgmath::prelude::*;
+
+use super::LorentzFrame;
+use crate::utils::Vec3F;
+
+const MIN_BETA: f32 = 0.01f32;
+
+// CPU mirror of `shaders/lorentz_helper.glsl`. Maps between where things are in the world
+// frame and where the observer sees them, so gameplay code agrees with what is rendered.
+// `lorentz_flag` follows the same convention as the shader uniform.
+#[derive(Debug, Clone)]
+pub struct RelativisticObserver {
+  pub frame: LorentzFrame,
+  pub position: Vec3F,
+  pub lorentz_flag: i32,
+}
+
+impl Default for RelativisticObserver {
+  fn default() -> Self {
+    Self {
+      frame: LorentzFrame::at_rest(),
+      position: Vec3F::zero(),
+      lorentz_flag: 0,
+    }
+  }
+}
+
+impl RelativisticObserver {
+  pub fn new(frame: LorentzFrame, position: Vec3F, lorentz_flag: i32) -> Self {
+    Self {
+      frame,
+      position,
+      lorentz_flag,
+    }
+  }
+
+  // GLSL `lorentzTransform`
+  pub fn lorentz_transform(&self, pos: &Vec3F) -> Vec3F {
+    let ref_frame_pos = self.frame.change_of_basis * (pos - self.position);
+    let transformed = Vec3F::new(ref_frame_pos.x / self.frame.gamma, ref_frame_pos.y, ref_frame_pos.z);
+    self.frame.change_of_basis_inverse * transformed + self.position
+  }
+
+  // GLSL `timeTransform`
+  pub fn time_transform(&self, pos: &Vec3F) -> Vec3F {
+    let ref_frame_pos = self.frame.change_of_basis * (pos - self.position);
+    let x1 = ref_frame_pos.x;
+    let y1 = ref_frame_pos.y;
+    let z1 = ref_frame_pos.z;
+    let r = (y1 * y1 + z1 * z1).sqrt();
+    let mut h2 = 1f32 / self.frame.beta;
+    h2 = h2 * h2;
+    let x2 = (2f32 * x1 * h2
+      + ((2f32 * x1 * h2) * (2f32 * x1 * h2) - 4f32 * (h2 - 1f32) * (x1 * x1 * h2 - r * r)).sqrt())
+      / (2f32 * (h2 - 1f32));
+    let transformed = Vec3F::new(x2, y1, z1);
+    self.frame.change_of_basis_inverse * transformed + self.position
+  }
+
+  // GLSL `transformRelativistic`: world position -> apparent position.
+  pub fn apparent_position(&self, pos: &Vec3F) -> Vec3F {
+    let mut ret = *pos;
+    if self.lorentz_flag != 0 {
+      ret = self.lorentz_transform(&ret);
+      if self.lorentz_flag >= 2 && self.frame.beta > MIN_BETA {
+        ret = self.time_transform(&ret);
+      }
+    }
+    ret
+  }
+
+  // Inverse of `apparent_position`: apparent position -> world position.
+  pub fn true_position(&self, apparent: &Vec3F) -> Vec3F {
+    if self.lorentz_flag == 0 {
+      return *apparent;
+    }
+    let mut ref_frame_pos = self.frame.change_of_basis * (apparent - self.position);
+    if self.lorentz_flag >= 2 && self.frame.beta > MIN_BETA {
+      // The light seen now left the point when it was one light-travel-time further back.
+      ref_frame_pos.x -= self.frame.beta * ref_frame_pos.magnitude();
+    }
+    ref_frame_pos.x *= self.frame.gamma;
+    self.frame.change_of_basis_inverse * ref_frame_pos + self.position
+  }
+
+  // World-time offset, relative to now, of the event at `pos` that the observer considers
+  // simultaneous with the present (t = v.x / c^2). Positive ahead of the observer, negative
+  // behind. Mirrors `simultaneityOffset` in `shaders/simultaneity_helper.glsl`.
+  pub fn simultaneity_offset(&self, pos: &Vec3F, speed_of_light: f32) -> f32 {
+    let along_velocity = (self.frame.change_of_basis * (pos - self.position)).x;
+    self.frame.beta * along_velocity / speed_of_light
+  }
+
+  // Time taken for light to travel from `pos` to the observer.
+  pub fn light_delay(&self, pos: &Vec3F, speed_of_light: f32) -> f32 {
+    (pos - self.position).magnitude() / speed_of_light
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::utils::{assert_close, assert_vec_close};
+
+  fn observer(velocity: Vec3F, flag: i32) -> RelativisticObserver {
+    RelativisticObserver::new(
+      LorentzFrame::from_velocity(&velocity, 1f32),
+      Vec3F::new(1f32, 2f32, 3f32),
+      flag,
+    )
+  }
+
+  #[test]
+  fn classical_mode_is_identity() {
+    let obs = observer(Vec3F::new(0.6f32, 0f32, 0f32), 0);
+    let pos = Vec3F::new(5f32, -2f32, 7f32);
+    assert_vec_close(obs.apparent_position(&pos), pos);
+    assert_vec_close(obs.true_position(&pos), pos);
+  }
+
+  #[test]
+  fn lorentz_transform_contracts_along_velocity() {
+    let obs = observer(Vec3F::new(0.6f32, 0f32, 0f32), 1);
+    let pos = obs.position + Vec3F::new(10f32, 4f32, -3f32);
+    assert_vec_close(
+      obs.apparent_position(&pos),
+      obs.position + Vec3F::new(8f32, 4f32, -3f32),
+    );
+  }
+
+  #[test]
+  fn time_transform_matches_on_axis_solution() {
+    // Directly ahead, h2 * (x2 - x1)^2 = x2^2 reduces to x2 = x1 / (1 - beta).
+    let obs = observer(Vec3F::new(0f32, 0f32, 0.6f32), 2);
+    let offset = obs.time_transform(&(obs.position + Vec3F::new(0f32, 0f32, 4f32))) - obs.position;
+    assert_vec_close(offset, Vec3F::new(0f32, 0f32, 10f32));
+  }
+
+  #[test]
+  fn time_transform_lies_on_light_cone() {
+    let obs = observer(Vec3F::new(0.3f32, 0.4f32, 0f32), 2);
+    let pos = obs.position + Vec3F::new(-3f32, 6f32, 2f32);
+    let apparent = obs.time_transform(&pos);
+    // The point moved (in the observer's frame) for exactly as long as its light took to arrive.
+    let along_velocity = |p: &Vec3F| (obs.frame.change_of_basis * (p - obs.position)).x;
+    let light_distance = (apparent - obs.position).magnitude();
+    assert_close(
+      along_velocity(&apparent) - along_velocity(&pos),
+      obs.frame.beta * light_distance,
+    );
+  }
+
+  #[test]
+  fn simultaneity_offset_grows_along_velocity() {
+    let obs = observer(Vec3F::new(0f32, 0.5f32, 0f32), 1);
+    let ahead = obs.position + Vec3F::new(0f32, 4f32, 0f32);
+    let behind = obs.position - Vec3F::new(0f32, 4f32, 0f32);
+    let beside = obs.position + Vec3F::new(4f32, 0f32, 0f32);
+    assert_close(obs.simultaneity_offset(&ahead, 1f32), 2f32);
+    assert_close(obs.simultaneity_offset(&behind, 1f32), -2f32);
+    assert_close(obs.simultaneity_offset(&beside, 1f32), 0f32);
+    assert_close(obs.light_delay(&beside, 2f32), 2f32);
+  }
+
+  #[test]
+  fn true_position_inverts_apparent_position() {
+    for flag in 0..5 {
+      let obs = observer(Vec3F::new(0.2f32, -0.5f32, 0.6f32), flag);
+      for pos in [
+        Vec3F::new(4f32, 4f32, 4f32),
+        Vec3F::new(-10f32, 0f32, 3f32),
+        Vec3F::new(1f32, 2f32, 30f32),
+      ]
+      .iter()
+      {
+        assert_vec_close(obs.true_position(&obs.apparent_position(pos)), *pos);
+      }
+    }
+  }
+}
@@ -0,0 +1,173 @@
+use std::collections::VecDeque;
+
+use cgmath::prelude::*;
+use specs::prelude::*;
+use specs::{Component, VecStorage};
+
+use crate::physics::TransformComponent;
+use crate::utils::Vec3F;
+
+const DEFAULT_HISTORY_CAPACITY: usize = 256;
+const BISECTION_STEPS: usize = 24;
+
+// Ring buffer of (world time, position) samples. Lets an observer see a moving entity where
+// it was when the light now arriving left it, rather than where it is right now.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct PositionHistory {
+  samples: VecDeque<(f64, Vec3F)>,
+  capacity: usize,
+}
+
+impl Default for PositionHistory {
+  fn default() -> Self {
+    Self::new(DEFAULT_HISTORY_CAPACITY)
+  }
+}
+
+impl PositionHistory {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      samples: VecDeque::with_capacity(capacity),
+      capacity: capacity.max(1),
+    }
+  }
+
+  pub fn record(&mut self, time: f64, position: Vec3F) {
+    if self.samples.len() == self.capacity {
+      self.samples.pop_front();
+    }
+    self.samples.push_back((time, position));
+  }
+
+  pub fn len(&self) -> usize {
+    self.samples.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.samples.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.samples.clear();
+  }
+
+  // Linearly interpolates the recorded worldline, clamping to the oldest/newest samples.
+  pub fn position_at(&self, time: f64) -> Option<Vec3F> {
+    let (first_t, first_p) = self.samples.front()?;
+    if time <= *first_t {
+      return Some(*first_p);
+    }
+    for i in 1..self.samples.len() {
+      let (t0, p0) = self.samples[i - 1];
+      let (t1, p1) = self.samples[i];
+      if time <= t1 {
+        let alpha = if t1 > t0 {
+          ((time - t0) / (t1 - t0)) as f32
+        } else {
+          1f32
+        };
+        return Some(p0.lerp(p1, alpha));
+      }
+    }
+    self.samples.back().map(|(_t, p)| *p)
+  }
+
+  // Solves |observer - x(t)| = c * (now - t) for the time t at which light now reaching the
+  // observer left this entity. Falls back to the oldest sample if the history is too short.
+  pub fn retarded_time(&self, observer: &Vec3F, now: f64, speed_of_light: f32) -> Option<f64> {
+    let c = speed_of_light as f64;
+    let residual = |t: f64, p: &Vec3F| c * (now - t) - (observer - p).magnitude() as f64;
+    let (mut late_t, late_p) = *self.samples.back()?;
+    if residual(late_t, &late_p) >= 0f64 {
+      return Some(late_t);
+    }
+    for i in (0..self.samples.len() - 1).rev() {
+      let (early_t, early_p) = self.samples[i];
+      if residual(early_t, &early_p) >= 0f64 {
+        // The root is bracketed by [early_t, late_t]
+        let mut low = early_t;
+        let mut high = late_t;
+        for _ in 0..BISECTION_STEPS {
+          let mid = (low + high) / 2f64;
+          let mid_p = self.position_at(mid).unwrap();
+          if residual(mid, &mid_p) >= 0f64 {
+            low = mid;
+          } else {
+            high = mid;
+          }
+        }
+        return Some((low + high) / 2f64);
+      }
+      late_t = early_t;
+    }
+    self.samples.front().map(|(t, _p)| *t)
+  }
+
+  pub fn retarded_position(&self, observer: &Vec3F, now: f64, speed_of_light: f32) -> Option<Vec3F> {
+    self
+      .retarded_time(observer, now, speed_of_light)
+      .and_then(|t| self.position_at(t))
+  }
+}
+
+// Where the observer currently sees an entity with a `PositionHistory`. Used by the renderer in
+// place of the entity's `TransformComponent` when light travel time is enabled.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct RetardedTransform(pub TransformComponent);
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn position_history_is_bounded() {
+    let mut history = PositionHistory::new(4);
+    for i in 0..10 {
+      history.record(i as f64, Vec3F::new(i as f32, 0f32, 0f32));
+    }
+    assert_eq!(history.len(), 4);
+    assert_eq!(history.position_at(0f64), Some(Vec3F::new(6f32, 0f32, 0f32)));
+    assert_eq!(history.position_at(7.5f64), Some(Vec3F::new(7.5f32, 0f32, 0f32)));
+    assert_eq!(history.position_at(20f64), Some(Vec3F::new(9f32, 0f32, 0f32)));
+  }
+
+  #[test]
+  fn stationary_entity_is_seen_one_light_travel_time_ago() {
+    let mut history = PositionHistory::new(200);
+    for i in 0..=100 {
+      history.record(i as f64 * 0.1f64, Vec3F::new(0f32, 0f32, 4f32));
+    }
+    let t = history.retarded_time(&Vec3F::zero(), 10f64, 2f32).unwrap();
+    assert_eq!((t - 8f64).abs() < 1e-4, true);
+  }
+
+  #[test]
+  fn moving_entity_retarded_position_lies_on_past_light_cone() {
+    let mut history = PositionHistory::new(200);
+    let worldline = |t: f64| Vec3F::new(0.5f32 * t as f32, 10f32, 0f32);
+    for i in 0..=150 {
+      let t = i as f64 * 0.1f64;
+      history.record(t, worldline(t));
+    }
+    let observer = Vec3F::zero();
+    let now = 15f64;
+    let t = history.retarded_time(&observer, now, 1f32).unwrap();
+    let p = history.retarded_position(&observer, now, 1f32).unwrap();
+    assert_eq!(((now - t) - (observer - p).magnitude() as f64).abs() < 1e-3, true);
+    assert_eq!((p - worldline(t)).magnitude() < 1e-3, true);
+  }
+
+  #[test]
+  fn short_history_clamps_to_oldest_sample() {
+    let mut history = PositionHistory::new(10);
+    history.record(9f64, Vec3F::new(100f32, 0f32, 0f32));
+    history.record(10f64, Vec3F::new(100f32, 0f32, 0f32));
+    assert_eq!(history.retarded_time(&Vec3F::zero(), 10f64, 1f32), Some(9f64));
+    assert_eq!(
+      PositionHistory::default().retarded_time(&Vec3F::zero(), 10f64, 1f32),
+      None
+    );
+  }
+}
@@ -0,0 +1,21 @@
+use super::DEFAULT_SPEED_OF_LIGHT;
+
+// World-wide speed of light, in world units per second.
+#[derive(Debug, Clone, Copy)]
+pub struct SpeedOfLight(pub f32);
+
+impl Default for SpeedOfLight {
+  fn default() -> Self {
+    Self(DEFAULT_SPEED_OF_LIGHT)
+  }
+}
+
+impl SpeedOfLight {
+  pub fn new(c: f32) -> Self {
+    Self(c)
+  }
+
+  pub fn get(&self) -> f32 {
+    self.0
+  }
+}
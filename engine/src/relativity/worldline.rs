@@ -0,0 +1,205 @@
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use specs::{Component, VecStorage};
+
+use crate::utils::{Vec2F, Vec3F};
+
+pub const PLAYER_WORLDLINE: &str = "player";
+
+// Marks an entity whose worldline should be recorded under `label`. The player is always
+// recorded under `PLAYER_WORLDLINE` and does not need a tag.
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct WorldlineTag {
+  pub label: String,
+}
+
+impl WorldlineTag {
+  pub fn new(label: &str) -> Self {
+    Self {
+      label: label.to_string(),
+    }
+  }
+}
+
+// A single event (t, x, y, z) in world coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WorldlineEvent {
+  pub t: f64,
+  pub x: f32,
+  pub y: f32,
+  pub z: f32,
+}
+
+impl WorldlineEvent {
+  pub fn new(t: f64, position: &Vec3F) -> Self {
+    Self {
+      t,
+      x: position.x,
+      y: position.y,
+      z: position.z,
+    }
+  }
+
+  pub fn position(&self) -> Vec3F {
+    Vec3F::new(self.x, self.y, self.z)
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Worldline {
+  pub label: String,
+  pub events: Vec<WorldlineEvent>,
+}
+
+impl Worldline {
+  pub fn new(label: &str) -> Self {
+    Self {
+      label: label.to_string(),
+      events: Vec::new(),
+    }
+  }
+
+  pub fn last(&self) -> Option<&WorldlineEvent> {
+    self.events.last()
+  }
+
+  // Projects the worldline onto the (x, ct) plane of a 1+1D Minkowski diagram along `axis`,
+  // relative to the event `origin`. Only events in the last `window` seconds are kept.
+  pub fn minkowski_projection(
+    &self,
+    axis: usize,
+    origin: &WorldlineEvent,
+    window: f64,
+    speed_of_light: f32,
+  ) -> Vec<Vec2F> {
+    let origin_position = origin.position();
+    self
+      .events
+      .iter()
+      .filter(|event| origin.t - event.t <= window)
+      .map(|event| {
+        let x = event.position()[axis] - origin_position[axis];
+        let ct = (event.t - origin.t) as f32 * speed_of_light;
+        Vec2F::new(x, ct)
+      })
+      .collect()
+  }
+}
+
+// Every worldline recorded during a session, exportable as CSV or JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldlineRecording {
+  pub worldlines: Vec<Worldline>,
+}
+
+impl WorldlineRecording {
+  pub fn record(&mut self, label: &str, t: f64, position: &Vec3F) {
+    let event = WorldlineEvent::new(t, position);
+    match self.worldlines.iter_mut().find(|w| w.label == label) {
+      Some(worldline) => worldline.events.push(event),
+      None => {
+        let mut worldline = Worldline::new(label);
+        worldline.events.push(event);
+        self.worldlines.push(worldline);
+      }
+    }
+  }
+
+  pub fn get(&self, label: &str) -> Option<&Worldline> {
+    self.worldlines.iter().find(|w| w.label == label)
+  }
+
+  pub fn player(&self) -> Option<&Worldline> {
+    self.get(PLAYER_WORLDLINE)
+  }
+
+  pub fn event_count(&self) -> usize {
+    self.worldlines.iter().map(|w| w.events.len()).sum()
+  }
+
+  pub fn clear(&mut self) {
+    self.worldlines.clear();
+  }
+
+  // One row per event: `label,t,x,y,z`.
+  pub fn to_csv(&self) -> String {
+    let mut csv = String::from("label,t,x,y,z\n");
+    for worldline in self.worldlines.iter() {
+      for e in worldline.events.iter() {
+        writeln!(csv, "{},{},{},{},{}", worldline.label, e.t, e.x, e.y, e.z).unwrap();
+      }
+    }
+    csv
+  }
+
+  pub fn to_json(&self) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(self)
+  }
+
+  pub fn from_json(json: &str) -> serde_json::Result<Self> {
+    serde_json::from_str(json)
+  }
+
+  pub fn export_csv<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    fs::write(path, self.to_csv())
+  }
+
+  pub fn export_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+    let json = self.to_json().map_err(io::Error::other)?;
+    fs::write(path, json)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn recording() -> WorldlineRecording {
+    let mut recording = WorldlineRecording::default();
+    recording.record(PLAYER_WORLDLINE, 0.0, &Vec3F::new(0f32, 0f32, 0f32));
+    recording.record("probe", 0.0, &Vec3F::new(1f32, 2f32, 3f32));
+    recording.record(PLAYER_WORLDLINE, 0.5, &Vec3F::new(4f32, 0f32, 0f32));
+    recording
+  }
+
+  #[test]
+  fn record_groups_events_by_label() {
+    let recording = recording();
+    assert_eq!(recording.worldlines.len(), 2);
+    assert_eq!(recording.player().unwrap().events.len(), 2);
+    assert_eq!(recording.get("probe").unwrap().events.len(), 1);
+    assert_eq!(recording.event_count(), 3);
+  }
+
+  #[test]
+  fn csv_has_one_row_per_event() {
+    let csv = recording().to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "label,t,x,y,z");
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[3], "probe,0,1,2,3");
+  }
+
+  #[test]
+  fn json_round_trips() {
+    let recording = recording();
+    let json = recording.to_json().unwrap();
+    assert_eq!(WorldlineRecording::from_json(&json).unwrap(), recording);
+  }
+
+  #[test]
+  fn minkowski_projection_is_relative_to_origin() {
+    let recording = recording();
+    let player = recording.player().unwrap();
+    let origin = *player.last().unwrap();
+    let points = player.minkowski_projection(0, &origin, 10.0, 2f32);
+    assert_eq!(points, vec![Vec2F::new(-4f32, -1f32), Vec2F::new(0f32, 0f32)]);
+    assert_eq!(player.minkowski_projection(0, &origin, 0.25, 2f32).len(), 1);
+  }
+}
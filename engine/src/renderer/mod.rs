@@ -1,4 +1,5 @@
 pub mod platform;
+pub mod post_process;
 pub mod render_command;
 pub mod render_pipeline;
 pub mod render_queue;
@@ -6,6 +7,7 @@ pub mod renderer;
 pub mod renderer_config;
 
 pub use self::platform::*;
+pub use self::post_process::*;
 pub use self::render_command::*;
 pub use self::render_pipeline::*;
 pub use self::render_queue::*;
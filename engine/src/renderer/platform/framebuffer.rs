@@ -6,16 +6,34 @@ use std::ptr;
 use gl;
 
 static MAX_FRAMEBUFFER_SIZE: i32 = 8192;
+// Position, normal, albedo and specular.
+pub const G_BUFFER_TARGETS: usize = 4;
+
+// What a framebuffer renders into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attachments {
+  // One RGBA8 color texture and a depth/stencil texture.
+  Color,
+  // One RGBA16F color texture and a depth/stencil texture, so that light can add up past 1.0
+  // until it is tone mapped.
+  Hdr,
+  // Only a sampleable depth texture, for shadow maps.
+  DepthOnly,
+  // G_BUFFER_TARGETS floating point color textures for deferred shading, and a depth/stencil
+  // texture.
+  GBuffer,
+}
 
 pub struct FramebufferSpec {
   pub dims: Vec2I,
   pub samples: u32,
   pub swapchain_target: bool,
+  pub attachments: Attachments,
 }
 
 pub struct Framebuffer {
   pub spec: FramebufferSpec,
-  color_attachment: u32,
+  color_attachments: Vec<u32>,
   depth_attachment: u32,
   id: RwAssetRef<u32>,
 }
@@ -24,7 +42,7 @@ impl Framebuffer {
   pub fn new(spec: FramebufferSpec) -> Framebuffer {
     let mut ret = Framebuffer {
       spec,
-      color_attachment: 0,
+      color_attachments: Vec::new(),
       depth_attachment: 0,
       id: RwAssetRef::new(0),
     };
@@ -37,6 +55,37 @@ impl Framebuffer {
       dims: Vec2I::new(w, h),
       samples: 1,
       swapchain_target: false,
+      attachments: Attachments::Color,
+    };
+    Framebuffer::new(spec)
+  }
+
+  pub fn hdr(w: i32, h: i32) -> Framebuffer {
+    let spec = FramebufferSpec {
+      dims: Vec2I::new(w, h),
+      samples: 1,
+      swapchain_target: false,
+      attachments: Attachments::Hdr,
+    };
+    Framebuffer::new(spec)
+  }
+
+  pub fn depth_only(w: i32, h: i32) -> Framebuffer {
+    let spec = FramebufferSpec {
+      dims: Vec2I::new(w, h),
+      samples: 1,
+      swapchain_target: false,
+      attachments: Attachments::DepthOnly,
+    };
+    Framebuffer::new(spec)
+  }
+
+  pub fn g_buffer(w: i32, h: i32) -> Framebuffer {
+    let spec = FramebufferSpec {
+      dims: Vec2I::new(w, h),
+      samples: 1,
+      swapchain_target: false,
+      attachments: Attachments::GBuffer,
     };
     Framebuffer::new(spec)
   }
@@ -49,9 +98,20 @@ impl Framebuffer {
   }
 
   pub fn bind_texture_slot(&self, slot: u32) {
+    self.bind_color_texture_slot(0, slot);
+  }
+
+  pub fn bind_color_texture_slot(&self, index: usize, slot: u32) {
+    unsafe {
+      gl::ActiveTexture(gl::TEXTURE0 + slot);
+      gl::BindTexture(gl::TEXTURE_2D, self.color_attachments[index]);
+    }
+  }
+
+  pub fn bind_depth_texture_slot(&self, slot: u32) {
     unsafe {
       gl::ActiveTexture(gl::TEXTURE0 + slot);
-      gl::BindTexture(gl::TEXTURE_2D, self.color_attachment);
+      gl::BindTexture(gl::TEXTURE_2D, self.depth_attachment);
     }
   }
 
@@ -68,66 +128,161 @@ impl Framebuffer {
     }
   }
 
+  // Zeroes every color attachment and resets depth, leaving the framebuffer bound. Unlike
+  // Window::clear_framebuffer, this ignores the clear color.
+  pub fn clear(&self) {
+    self.bind();
+    let zero = [0f32; 4];
+    unsafe {
+      for i in 0..self.color_attachments.len() {
+        gl::ClearBufferfv(gl::COLOR, i as i32, zero.as_ptr());
+      }
+      gl::Clear(gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+    }
+  }
+
+  // Copies this framebuffer's depth into `target` and leaves `target` bound. Both need the same
+  // depth format.
+  pub fn blit_depth_to(&self, target: &Framebuffer) {
+    let (from, to) = (&self.spec.dims, &target.spec.dims);
+    unsafe {
+      gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.id());
+      gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target.id());
+      gl::BlitFramebuffer(
+        0,
+        0,
+        from.x,
+        from.y,
+        0,
+        0,
+        to.x,
+        to.y,
+        gl::DEPTH_BUFFER_BIT,
+        gl::NEAREST,
+      );
+    }
+    target.bind();
+  }
+
   pub fn id(&self) -> u32 {
     *self.id.get()
   }
 
   fn initialize(&mut self) {
+    // Internal format, pixel type and filtering of each color attachment.
+    let color_formats = match self.spec.attachments {
+      Attachments::DepthOnly => {
+        self.initialize_depth_only();
+        return;
+      }
+      Attachments::Color => vec![(gl::RGBA8, gl::UNSIGNED_BYTE, gl::LINEAR)],
+      Attachments::Hdr => vec![(gl::RGBA16F, gl::FLOAT, gl::LINEAR)],
+      Attachments::GBuffer => vec![(gl::RGBA16F, gl::FLOAT, gl::NEAREST); G_BUFFER_TARGETS],
+    };
     let mut id = self.id();
     unsafe {
       // Create Framebuffer
       gl::CreateFramebuffers(1, &mut id);
       gl::BindFramebuffer(gl::FRAMEBUFFER, id);
 
-      // Color texture buffer
-      gl::CreateTextures(gl::TEXTURE_2D, 1, &mut self.color_attachment);
-      gl::BindTexture(gl::TEXTURE_2D, self.color_attachment);
-      gl::TexImage2D(
+      // Color texture buffers
+      let mut draw_buffers = Vec::new();
+      for (i, (internal_format, pixel_type, filter)) in color_formats.into_iter().enumerate() {
+        let mut color_attachment = 0;
+        gl::CreateTextures(gl::TEXTURE_2D, 1, &mut color_attachment);
+        gl::BindTexture(gl::TEXTURE_2D, color_attachment);
+        gl::TexImage2D(
+          gl::TEXTURE_2D,
+          0,
+          internal_format as i32,
+          self.spec.dims.x,
+          self.spec.dims.y,
+          0,
+          gl::RGBA,
+          pixel_type,
+          ptr::null(),
+        );
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter as i32);
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        let target = gl::COLOR_ATTACHMENT0 + i as u32;
+        gl::FramebufferTexture2D(gl::FRAMEBUFFER, target, gl::TEXTURE_2D, color_attachment, 0);
+        self.color_attachments.push(color_attachment);
+        draw_buffers.push(target);
+      }
+      gl::DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr());
+
+      // Time to do a depth buffer
+      gl::CreateTextures(gl::TEXTURE_2D, 1, &mut self.depth_attachment);
+      gl::BindTexture(gl::TEXTURE_2D, self.depth_attachment);
+      gl::TexStorage2D(
         gl::TEXTURE_2D,
-        0,
-        gl::RGBA8 as i32,
+        1,
+        gl::DEPTH24_STENCIL8,
         self.spec.dims.x,
         self.spec.dims.y,
-        0,
-        gl::RGBA,
-        gl::UNSIGNED_BYTE,
-        ptr::null(),
       );
-      gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-      gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-      gl::BindTexture(gl::TEXTURE_2D, 0);
       gl::FramebufferTexture2D(
         gl::FRAMEBUFFER,
-        gl::COLOR_ATTACHMENT0,
+        gl::DEPTH_STENCIL_ATTACHMENT,
         gl::TEXTURE_2D,
-        self.color_attachment,
+        self.depth_attachment,
         0,
       );
 
-      // Time to do a depth buffer
+      // TOOD: Assert complete
+      gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+      let framebuffer_status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+      if framebuffer_status != gl::FRAMEBUFFER_COMPLETE {
+        println!("Frame buffer is not ready! {}", framebuffer_status);
+      }
+    }
+
+    self.id.set(id);
+  }
+
+  fn initialize_depth_only(&mut self) {
+    let mut id = self.id();
+    unsafe {
+      gl::CreateFramebuffers(1, &mut id);
+      gl::BindFramebuffer(gl::FRAMEBUFFER, id);
+
       gl::CreateTextures(gl::TEXTURE_2D, 1, &mut self.depth_attachment);
       gl::BindTexture(gl::TEXTURE_2D, self.depth_attachment);
-      gl::TexStorage2D(
+      gl::TexImage2D(
         gl::TEXTURE_2D,
-        1,
-        gl::DEPTH24_STENCIL8,
+        0,
+        gl::DEPTH_COMPONENT24 as i32,
         self.spec.dims.x,
         self.spec.dims.y,
+        0,
+        gl::DEPTH_COMPONENT,
+        gl::FLOAT,
+        ptr::null(),
       );
+      gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+      gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+      // Everything outside the map reads as farthest away, so it is never shadowed.
+      let border = [1f32, 1f32, 1f32, 1f32];
+      gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as i32);
+      gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as i32);
+      gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+      gl::BindTexture(gl::TEXTURE_2D, 0);
       gl::FramebufferTexture2D(
         gl::FRAMEBUFFER,
-        gl::DEPTH_STENCIL_ATTACHMENT,
+        gl::DEPTH_ATTACHMENT,
         gl::TEXTURE_2D,
         self.depth_attachment,
         0,
       );
+      gl::DrawBuffer(gl::NONE);
+      gl::ReadBuffer(gl::NONE);
 
-      // TOOD: Assert complete
-      gl::BindFramebuffer(gl::FRAMEBUFFER, id);
       let framebuffer_status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
       if framebuffer_status != gl::FRAMEBUFFER_COMPLETE {
-        println!("Frame buffer is not ready! {}", framebuffer_status);
+        println!("Depth frame buffer is not ready! {}", framebuffer_status);
       }
+      gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
     }
 
     self.id.set(id);
@@ -141,12 +296,11 @@ impl Drop for Framebuffer {
       unsafe {
         gl::BindFramebuffer(gl::FRAMEBUFFER, id);
         gl::DeleteFramebuffers(1, &mut id);
-        gl::BindTexture(gl::TEXTURE_2D, self.color_attachment);
-        gl::DeleteTextures(1, &mut self.color_attachment);
+        gl::DeleteTextures(self.color_attachments.len() as i32, self.color_attachments.as_ptr());
         gl::BindTexture(gl::TEXTURE_2D, self.depth_attachment);
         gl::DeleteTextures(1, &mut self.depth_attachment);
       }
-      self.color_attachment = 0;
+      self.color_attachments.clear();
       self.depth_attachment = 0;
     }
   }
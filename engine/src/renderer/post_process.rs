@@ -0,0 +1,145 @@
+use crate::datastructures::RegistryItem;
+use crate::graphics::{Shader, ShaderBuilder, Uniform};
+use crate::platform::Screen;
+use crate::renderer::{BloomConfig, Framebuffer, PostEffect, PostProcessConfig};
+use crate::utils::*;
+
+// The screen's slot, free again once the scene is drawn.
+const INPUT_SLOT: u32 = 32;
+// The first shadow map's slot, unbound at the end of the scene.
+const SECOND_INPUT_SLOT: u32 = 33;
+
+fn post_shader(filename: &str) -> Shader {
+  ShaderBuilder::default().with_source_file(filename).build()
+}
+
+// Runs the enabled effects of a PostProcessConfig over the screen's HDR framebuffer, each one
+// reading the previous one's output. The last effect draws straight to the window.
+pub struct PostProcessor {
+  bright_pass: Shader,
+  blur: Shader,
+  bloom_composite: Shader,
+  tone_map: Shader,
+  gamma: Shader,
+  // Ping-pong targets between effects, at screen size. Created on first use.
+  targets: Vec<Framebuffer>,
+  // Ping-pong targets for blurring, at half the screen size.
+  bloom_targets: Vec<Framebuffer>,
+}
+
+impl Default for PostProcessor {
+  fn default() -> Self {
+    PostProcessor {
+      bright_pass: post_shader("shaders/post/bright_pass.glsl"),
+      blur: post_shader("shaders/post/blur.glsl"),
+      bloom_composite: post_shader("shaders/post/bloom_composite.glsl"),
+      tone_map: post_shader("shaders/post/tone_map.glsl"),
+      gamma: post_shader("shaders/post/gamma.glsl"),
+      targets: Vec::new(),
+      bloom_targets: Vec::new(),
+    }
+  }
+}
+
+impl PostProcessor {
+  pub fn apply(&mut self, config: &PostProcessConfig, screen: &Screen) {
+    let chain = config.chain();
+    if chain.is_empty() {
+      screen.draw_framebuffer_contents();
+      return;
+    }
+    let dims = screen.framebuffer().spec.dims;
+    self.prepare_targets(dims);
+    unsafe {
+      gl::Disable(gl::DEPTH_TEST);
+    }
+    let mut input = screen.framebuffer();
+    for (i, effect) in chain.iter().enumerate() {
+      let output = if i + 1 == chain.len() {
+        None
+      } else {
+        Some(&self.targets[i % 2])
+      };
+      match effect {
+        PostEffect::BLOOM => self.bloom(&config.bloom, input, output, screen),
+        PostEffect::TONEMAP => {
+          let tone_map = &config.tone_map;
+          let uniforms = [
+            ("exposure", Uniform::Float(tone_map.exposure)),
+            ("tone_mapper", Uniform::Int(tone_map.mapper.id())),
+          ];
+          self.pass(&self.tone_map, input, output, &uniforms, screen);
+        }
+        PostEffect::GAMMA => {
+          let uniforms = [("gamma", Uniform::Float(config.gamma.gamma))];
+          self.pass(&self.gamma, input, output, &uniforms, screen);
+        }
+      }
+      if let Some(output) = output {
+        input = output;
+      }
+    }
+    unsafe {
+      gl::Enable(gl::DEPTH_TEST);
+    }
+  }
+
+  // Blurs what is past the threshold at half resolution, then adds it back over the input.
+  fn bloom(&self, config: &BloomConfig, input: &Framebuffer, output: Option<&Framebuffer>, screen: &Screen) {
+    let (blurred, scratch) = (&self.bloom_targets[0], &self.bloom_targets[1]);
+    let threshold = [("threshold", Uniform::Float(config.threshold))];
+    self.pass(&self.bright_pass, input, Some(blurred), &threshold, screen);
+    for _ in 0..config.blur_passes {
+      let horizontal = [("horizontal", Uniform::Bool(true))];
+      self.pass(&self.blur, blurred, Some(scratch), &horizontal, screen);
+      let vertical = [("horizontal", Uniform::Bool(false))];
+      self.pass(&self.blur, scratch, Some(blurred), &vertical, screen);
+    }
+    blurred.bind_texture_slot(SECOND_INPUT_SLOT);
+    let uniforms = [
+      ("bloom", Uniform::Int(SECOND_INPUT_SLOT as i32)),
+      ("intensity", Uniform::Float(config.intensity)),
+    ];
+    self.pass(&self.bloom_composite, input, output, &uniforms, screen);
+    blurred.unbind_texture_slot(SECOND_INPUT_SLOT);
+  }
+
+  // Draws `input` through `shader` into `output`, or into the window if there is none.
+  fn pass(
+    &self,
+    shader: &Shader,
+    input: &Framebuffer,
+    output: Option<&Framebuffer>,
+    uniforms: &[(&str, Uniform)],
+    screen: &Screen,
+  ) {
+    match output {
+      Some(output) => output.bind(),
+      None => {
+        let dims = &screen.framebuffer().spec.dims;
+        unsafe {
+          gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+          gl::Viewport(0, 0, dims.x, dims.y);
+        }
+      }
+    }
+    shader.bind();
+    input.bind_texture_slot(INPUT_SLOT);
+    shader.set_uniform("tex", &Uniform::Int(INPUT_SLOT as i32));
+    for (name, uniform) in uniforms {
+      shader.set_uniform(name, uniform);
+    }
+    screen.draw_quad(shader);
+    input.unbind_texture_slot(INPUT_SLOT);
+    shader.unbind();
+  }
+
+  // Recreates the targets when the screen is resized.
+  fn prepare_targets(&mut self, dims: Vec2I) {
+    if self.targets.first().is_none_or(|target| target.spec.dims != dims) {
+      self.targets = (0..2).map(|_| Framebuffer::hdr(dims.x, dims.y)).collect();
+      let half = Vec2I::new((dims.x / 2).max(1), (dims.y / 2).max(1));
+      self.bloom_targets = (0..2).map(|_| Framebuffer::hdr(half.x, half.y)).collect();
+    }
+  }
+}
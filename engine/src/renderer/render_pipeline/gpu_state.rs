@@ -3,8 +3,8 @@ use std::borrow::{Borrow, BorrowMut};
 use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use crate::graphics::{
-  AssetLibrary, Assets, MaterialComponent, MeshComponent, Shader, ShaderBuilder, ShaderId, TextureBinder, VertexArray,
-  VertexArrayBuilder, VertexArrayId,
+  AssetLibrary, Assets, AttributeType, MaterialComponent, MeshComponent, Shader, ShaderBuilder, ShaderId,
+  TextureBinder, VertexArray, VertexArrayBuilder, VertexArrayId,
 };
 use crate::renderer::RenderQueueConsumer;
 use crate::utils::Mat4F;
@@ -15,6 +15,8 @@ pub struct GPUState<'a> {
   pub active_mesh: VertexArrayId,
   pub active_shader: ShaderId,
   pub poly_count: usize,
+  // Set when the next draw is an instanced one.
+  pub instances: Option<usize>,
 }
 
 impl<'a> GPUState<'a> {
@@ -25,6 +27,7 @@ impl<'a> GPUState<'a> {
       active_mesh,
       active_shader,
       poly_count: 0usize,
+      instances: None,
     };
     ret.shader_immut().bind();
     ret.active_mesh.bind();
@@ -50,15 +53,23 @@ impl<'a> GPUState<'a> {
     self.active_mesh.bind();
   }
 
+  pub fn mesh_mut(&self) -> Option<RwLockWriteGuard<'_, VertexArray>> {
+    let mut active_mesh = self.active_mesh.clone();
+    <AssetLibrary as Assets<VertexArrayBuilder>>::get_asset_mut(self.assets, &mut active_mesh)
+  }
+
   pub fn draw(&self) {
     let element_type = self.shader_element_type();
     let vao_opt = <AssetLibrary as Assets<VertexArrayBuilder>>::get_asset(&self.assets, &self.active_mesh);
-    vao_opt.map(|vao| vao.draw(&element_type));
+    vao_opt.map(|vao| match self.instances {
+      Some(count) => vao.draw_instanced(&element_type, count),
+      None => vao.draw(&element_type),
+    });
   }
 
   pub fn increment_poly_counter(&mut self) {
     <AssetLibrary as Assets<VertexArrayBuilder>>::get_asset(&self.assets, &self.active_mesh).map(|vao| {
-      self.poly_count += vao.poly_count();
+      self.poly_count += vao.poly_count() * self.instances.unwrap_or(1);
     });
   }
 
@@ -85,6 +96,10 @@ impl<'a> GPUState<'a> {
     }
   }
 
+  pub fn instance_layout(&self) -> Option<Vec<(String, AttributeType)>> {
+    self.shader_immut().instance_layout().cloned()
+  }
+
   fn shader_element_type(&self) -> gl::types::GLenum {
     self.shader_immut().element_type().clone()
   }
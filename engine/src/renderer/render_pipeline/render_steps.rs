@@ -1,20 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::ffi::{CStr, CString};
 
 use either::Either;
 use specs::prelude::*;
 
 use crate::datastructures::{AVLTree, AVLTreeIterator};
-use crate::graphics::{AssetLibrary, MaterialComponent, MeshComponent, Shader, ShaderId, TextureId, Uniform};
-use crate::renderer::{DrawCall, GPUState, RenderCommand};
+use crate::graphics::{
+  AssetLibrary, AttributeType, InstancingTable, MaterialComponent, MeshComponent, Shader, ShaderId, ShadowCaster,
+  ShadowReceiver, TextureId, Uniform, VertexArrayId, MAX_SHADOW_MAPS, SHADOW_MAP_SLOT,
+};
+use crate::renderer::{DrawCall, Framebuffer, GPUState, RenderCommand, G_BUFFER_TARGETS};
 
-use crate::physics::TransformComponent;
+use crate::physics::{InterpolatedTransform, TransformComponent};
+use crate::relativity::RetardedTransform;
 use crate::utils::Mat4F;
 
 use crate::debug::*;
 
+// The G-buffer's targets are bound from here upwards, after the shadow maps.
+const G_BUFFER_SLOT: u32 = SHADOW_MAP_SLOT + MAX_SHADOW_MAPS as u32;
+// Sampler names of the G-buffer's targets in the lighting shader, in attachment order.
+const G_BUFFER_SAMPLERS: [&str; G_BUFFER_TARGETS] = ["g_position", "g_normal", "g_albedo", "g_specular"];
+
 pub trait RenderStep {}
 
+// Before anything is drawn to the screen, the depth of every shadow caster is drawn from each
+// shadowed light into its shadow map, with a single depth-only shader.
+pub struct ShadowPassStep;
+impl RenderStep for ShadowPassStep {}
+
 // The pipeline is eager to start accepting uniforms.
 // The shader/mesh are bound, but no uniforms are bound.
 // Thus the pipeline is not ready for draw calls yet.
@@ -40,7 +54,8 @@ impl RenderStep for SaturatedDrawCallStep {}
 pub struct FlushedDrawCallStep;
 impl RenderStep for FlushedDrawCallStep {}
 
-// All renderables have been drawn. Now it's time for second/third pass
+// All renderables have been drawn. Now it's time for second/third pass, like lighting a G-buffer,
+// or for drawing another queue on top.
 pub struct MeshesDrawnStep;
 impl RenderStep for MeshesDrawnStep {}
 
@@ -58,6 +73,47 @@ impl<'a, S: RenderStep> RenderPipeline<'a, S> {
   }
 }
 
+impl<'a> RenderPipeline<'a, ShadowPassStep> {
+  // Each target is a shadow map with the light-space matrix of the light it belongs to.
+  pub fn cast_shadows(
+    mut self,
+    render_queue: &AVLTree<DrawCall>,
+    casters: &ReadStorage<'a, ShadowCaster>,
+    models: (
+      &ReadStorage<'a, TransformComponent>,
+      &ReadStorage<'a, InterpolatedTransform>,
+    ),
+    retarded_models: Option<&ReadStorage<'a, RetardedTransform>>,
+    targets: &[(&Framebuffer, Mat4F)],
+  ) -> Self {
+    self.state.instances = None;
+    for (shadow_map, light_space) in targets.iter() {
+      shadow_map.clear();
+      self
+        .state
+        .shader()
+        .set_uniform("light_space", &Uniform::Mat4(*light_space));
+      for dc in render_queue.iter().filter(|dc| casters.contains(dc.entity)) {
+        if self.state.active_mesh != dc.mesh_component.vertex_array_id {
+          self.state.bind_mesh(dc.mesh_component.vertex_array_id.clone());
+        }
+        let model = model_matrix(dc.entity, models, retarded_models);
+        self.state.shader().set_uniform("model", &Uniform::Mat4(model));
+        self.state.draw();
+        self.state.increment_poly_counter();
+      }
+    }
+    self
+  }
+
+  // Hands the GPU back to the first draw call of the scene. The caller binds its render target.
+  pub fn finish(mut self, first: &DrawCall) -> RenderPipeline<'a, ReadyToDrawStep> {
+    self.state.bind_shader(first.mesh_component.shader_id.clone());
+    self.state.bind_mesh(first.mesh_component.vertex_array_id.clone());
+    self.consume()
+  }
+}
+
 impl<'a> RenderPipeline<'a, ReadyToDrawStep> {
   pub fn new<'b>(queue: &mut AVLTreeIterator<'b, DrawCall>, assets: &'a mut AssetLibrary) -> Option<Self> {
     if let Some(draw_call) = queue.peek() {
@@ -75,6 +131,12 @@ impl<'a> RenderPipeline<'a, ReadyToDrawStep> {
     }
   }
 
+  // Detours through the shadow pass before any uniforms are bound.
+  pub fn shadow_pass(mut self, shadow_shader: ShaderId) -> RenderPipeline<'a, ShadowPassStep> {
+    self.state.bind_shader(shadow_shader);
+    self.consume()
+  }
+
   pub fn bind_global_uniforms<'b>(
     mut self,
     uniforms: &[&HashMap<String, Uniform>],
@@ -96,9 +158,14 @@ impl<'a> RenderPipeline<'a, ActivatedShaderStep> {
     self,
     queue: &mut AVLTreeIterator<'b, DrawCall>,
     materials: &ReadStorage<'a, MaterialComponent>,
-    models: &ReadStorage<'a, TransformComponent>,
+    receivers: &ReadStorage<'a, ShadowReceiver>,
+    models: (
+      &ReadStorage<'a, TransformComponent>,
+      &ReadStorage<'a, InterpolatedTransform>,
+    ),
+    retarded_models: Option<&ReadStorage<'a, RetardedTransform>>,
   ) -> RenderPipeline<'a, SaturatedDrawCallStep> {
-    let ret = self.ingress_drawable(queue, materials, models);
+    let ret = self.ingress_drawable(queue, materials, receivers, models, retarded_models);
     ret
   }
 
@@ -106,16 +173,115 @@ impl<'a> RenderPipeline<'a, ActivatedShaderStep> {
     mut self,
     queue: &mut AVLTreeIterator<'b, DrawCall>,
     materials: &ReadStorage<'a, MaterialComponent>,
-    models: &ReadStorage<'a, TransformComponent>,
+    receivers: &ReadStorage<'a, ShadowReceiver>,
+    models: (
+      &ReadStorage<'a, TransformComponent>,
+      &ReadStorage<'a, InterpolatedTransform>,
+    ),
+    retarded_models: Option<&ReadStorage<'a, RetardedTransform>>,
   ) -> RenderPipeline<'a, SaturatedDrawCallStep> {
+    if let Some(layout) = self.state.instance_layout() {
+      return self.ingress_instances(layout, queue, materials, receivers, models, retarded_models);
+    }
+    self.state.instances = None;
     if let Some(dc) = queue.next() {
-      let model = models.get(dc.entity).unwrap().matrix();
+      let model = model_matrix(dc.entity, models, retarded_models);
       let mtl = materials.get(dc.entity).unwrap();
       self.state.shader().set_uniform("model", &Uniform::Mat4(model));
+      let receives_shadows = Uniform::Bool(receivers.contains(dc.entity));
+      self.state.shader().set_uniform("shadow_receiver", &receives_shadows);
+      // The retarded transform already accounts for light travel time, so the shader must not.
+      let retarded = Uniform::Bool(retarded_models.is_some_and(|r| r.contains(dc.entity)));
+      self.state.shader().set_uniform("retardedModel", &retarded);
       self.state.bind_material(&mtl);
     }
     self.consume()
   }
+
+  // Consumes every queued draw call on the active mesh and shader, and packs them into the mesh's
+  // instance buffer so that they go out as one draw. Non-instanced uniforms, like textures and
+  // whether shadows are received, are bound once from the first entity.
+  fn ingress_instances<'b>(
+    mut self,
+    layout: Vec<(String, AttributeType)>,
+    queue: &mut AVLTreeIterator<'b, DrawCall>,
+    materials: &ReadStorage<'a, MaterialComponent>,
+    receivers: &ReadStorage<'a, ShadowReceiver>,
+    models: (
+      &ReadStorage<'a, TransformComponent>,
+      &ReadStorage<'a, InterpolatedTransform>,
+    ),
+    retarded_models: Option<&ReadStorage<'a, RetardedTransform>>,
+  ) -> RenderPipeline<'a, SaturatedDrawCallStep> {
+    let mut batch = Vec::new();
+    while let Some(dc) = queue.peek().filter(|dc| self.activated_on(&dc.mesh_component)) {
+      batch.push(dc.entity);
+      queue.next();
+    }
+    if let Some(mtl) = batch.first().and_then(|entity| materials.get(*entity)) {
+      self.state.bind_material(mtl);
+    }
+    let receives_shadows = Uniform::Bool(batch.first().is_some_and(|entity| receivers.contains(*entity)));
+    self.state.shader().set_uniform("shadow_receiver", &receives_shadows);
+
+    let instances = if let Some(mut vao) = self.state.mesh_mut() {
+      // A mesh drawn with a differently laid out instanced shader before starts over.
+      if vao.instancing_table().map(|table| &table.attribute_offsets) != Some(&layout) {
+        vao.enable_instancing(InstancingTable::new(layout));
+      }
+      let table = vao.instancing_table().unwrap();
+      let drawn: BTreeSet<Entity> = batch.iter().copied().collect();
+      let stale: Vec<Entity> = table.entities().filter(|e| !drawn.contains(e)).copied().collect();
+      let packed: Vec<Vec<f32>> = batch
+        .iter()
+        .map(|entity| {
+          let model = model_matrix(*entity, models, retarded_models);
+          let mtl = materials.get(*entity);
+          table.pack_instance(|name| match name {
+            "model" => Some(Uniform::Mat4(model)),
+            _ => mtl.and_then(|mtl| {
+              mtl
+                .uniforms()
+                .iter()
+                .find(|(unif, _)| unif == name)
+                .map(|(_, v)| v.clone())
+            }),
+          })
+        })
+        .collect();
+      for entity in stale.iter() {
+        vao.remove_instance(entity);
+      }
+      for (entity, values) in batch.iter().zip(packed.iter()) {
+        vao.upsert_instance(entity, values);
+      }
+      vao.sync_instances();
+      vao.instance_count()
+    } else {
+      0
+    };
+    self.state.instances = Some(instances);
+    self.consume()
+  }
+}
+
+// The matrix an entity is drawn with: where it appears to the observer when light travel time is
+// modelled, otherwise its interpolated or raw transform.
+fn model_matrix<'a>(
+  entity: Entity,
+  (models, interpolated_models): (
+    &ReadStorage<'a, TransformComponent>,
+    &ReadStorage<'a, InterpolatedTransform>,
+  ),
+  retarded_models: Option<&ReadStorage<'a, RetardedTransform>>,
+) -> Mat4F {
+  match retarded_models.and_then(|r| r.get(entity)) {
+    Some(retarded) => retarded.0.matrix(),
+    None => match interpolated_models.get(entity) {
+      Some(interpolated) => interpolated.0.matrix(),
+      None => models.get(entity).unwrap().matrix(),
+    },
+  }
 }
 
 impl<'a> RenderPipeline<'a, SaturatedDrawCallStep> {
@@ -128,6 +294,11 @@ impl<'a> RenderPipeline<'a, SaturatedDrawCallStep> {
 }
 
 impl<'a> RenderPipeline<'a, FlushedDrawCallStep> {
+  // Once the queue is exhausted.
+  pub fn finish(self) -> RenderPipeline<'a, MeshesDrawnStep> {
+    self.consume()
+  }
+
   // TODO: Handle moving to the next ReadyStep or the next ActivatedStep
   pub fn proceed<'b>(
     mut self,
@@ -158,3 +329,43 @@ impl<'a> RenderPipeline<'a, FlushedDrawCallStep> {
     }
   }
 }
+
+impl<'a> RenderPipeline<'a, MeshesDrawnStep> {
+  // Deferred shading's second pass: lights every pixel of the G-buffer into the bound framebuffer
+  // by drawing `quad`, a mesh covering the screen, with `lighting_shader`.
+  pub fn light_g_buffer(
+    mut self,
+    g_buffer: &Framebuffer,
+    lighting_shader: ShaderId,
+    quad: VertexArrayId,
+    uniforms: &[&HashMap<String, Uniform>],
+  ) -> Self {
+    self.state.clear_textures();
+    self.state.bind_shader(lighting_shader);
+    self.state.bind_mesh(quad);
+    for mgr in uniforms.iter() {
+      for (unif_name, unif_value) in mgr.iter() {
+        self.state.shader().set_uniform(unif_name, unif_value);
+      }
+    }
+    for (i, sampler) in G_BUFFER_SAMPLERS.iter().enumerate() {
+      let slot = G_BUFFER_SLOT + i as u32;
+      g_buffer.bind_color_texture_slot(i, slot);
+      self.state.shader().set_uniform(sampler, &Uniform::Int(slot as i32));
+    }
+    self.state.instances = None;
+    self.state.draw();
+    for i in 0..G_BUFFER_TARGETS {
+      g_buffer.unbind_texture_slot(G_BUFFER_SLOT + i as u32);
+    }
+    self
+  }
+
+  // Starts over on another queue, beginning with its first draw call.
+  pub fn next_queue(mut self, first: &DrawCall) -> RenderPipeline<'a, ReadyToDrawStep> {
+    self.state.clear_textures();
+    self.state.bind_shader(first.mesh_component.shader_id.clone());
+    self.state.bind_mesh(first.mesh_component.vertex_array_id.clone());
+    self.consume()
+  }
+}
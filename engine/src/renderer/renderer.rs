@@ -10,21 +10,43 @@ use specs::prelude::*;
 use crate::datastructures::{AVLTree, AVLTreeIterator, RegistryItem};
 use crate::debug::*;
 use crate::graphics::{
-  AssetLibrary, AttributeType, BufferConfig, BufferLayout, DataBuffer, DataBufferBuilder, IndexBuffer,
-  IndexBufferBuilder, MaterialComponent, MeshComponent, Shader, ShaderBuilder, Uniform, UniformLifecycle, VertexArray,
-  VertexArrayBuilder,
+  AssetLibrary, Assets, AttributeType, BufferConfig, BufferLayout, DataBuffer, DataBufferBuilder, IndexBuffer,
+  IndexBufferBuilder, LightBlock, MaterialComponent, MeshComponent, Shader, ShaderBuilder, ShadowCaster,
+  ShadowReceiver, ShaderId, Uniform, UniformBuffer, UniformLifecycle, VertexArray, VertexArrayBuilder, VertexArrayId,
+  LIGHTS_BINDING, SHADOW_MAP_SLOT,
 };
 use crate::platform::{Screen, Window};
 use crate::renderer::render_pipeline::*;
-use crate::renderer::{DrawCall, Framebuffer, PolygonMode, RenderQueueConsumer, RendererConfig};
+use crate::renderer::{
+  DrawCall, Framebuffer, PolygonMode, PostProcessConfig, PostProcessor, RenderQueueConsumer, RendererConfig,
+  ShadingMode,
+};
 
 use crate::ecs::Camera;
 
 use crate::events::{Event, EventChannel, EventPayload, KeyCode, ReceiverId, StatelessEventChannel, WindowEvent};
-use crate::physics::TransformComponent;
+use crate::physics::{InterpolatedTransform, TransformComponent};
+use crate::relativity::RetardedTransform;
 
 type TransformStack = Vec<Mat4F>;
 
+// A frame's draw calls split for deferred shading.
+struct DeferredPasses {
+  // Drawn into the G-buffer with the "g_buffer" shaders in place of the default lit ones.
+  g_buffer_queue: AVLTree<DrawCall>,
+  // Drawn forward after the G-buffer is lit.
+  forward_queue: AVLTree<DrawCall>,
+  lighting_shader: ShaderId,
+  quad: VertexArrayId,
+}
+
+fn light_buffer() -> DataBuffer {
+  DataBufferBuilder::default()
+    .with_config(BufferConfig::ubo())
+    .with_layout(BufferLayout::new(vec![]))
+    .build()
+}
+
 pub struct Renderer {
   // Screen
   screen: Screen,
@@ -35,6 +57,15 @@ pub struct Renderer {
   // Config
   config: RendererConfig,
   receiver_id: ReceiverId,
+  // Lights
+  light_buffer: DataBuffer,
+  // Light-space matrices of this frame's shadowed lights, and a shadow map for each.
+  shadow_views: Vec<Mat4F>,
+  shadow_maps: Vec<Framebuffer>,
+  // Deferred shading's targets, created on first use.
+  g_buffer: Option<Framebuffer>,
+  // Turns the screen's HDR contents into the final image.
+  post_processor: PostProcessor,
   // Transform Stack
 }
 
@@ -46,6 +77,11 @@ impl Default for Renderer {
       common_uniforms: HashMap::new(),
       config: RendererConfig::default(),
       receiver_id: 0,
+      light_buffer: light_buffer(),
+      shadow_views: Vec::new(),
+      shadow_maps: Vec::new(),
+      g_buffer: None,
+      post_processor: PostProcessor::default(),
     }
   }
 }
@@ -59,6 +95,11 @@ impl Renderer {
       common_uniforms: HashMap::new(),
       config: RendererConfig::default(),
       receiver_id,
+      light_buffer: light_buffer(),
+      shadow_views: Vec::new(),
+      shadow_maps: Vec::new(),
+      g_buffer: None,
+      post_processor: PostProcessor::default(),
     }
   }
 
@@ -77,10 +118,35 @@ impl Renderer {
     self.common_uniforms.insert(name.to_string(), uniform);
   }
 
+  // Uploads this frame's lights and exposes them to every shader as the `Lights` uniform block.
+  pub fn submit_lights(&mut self, lights: &LightBlock) {
+    self.light_buffer.write(0, &lights.data());
+    if self.light_buffer.is_initialized() {
+      self.light_buffer.sync_gpu();
+    } else {
+      self.light_buffer.init();
+      self.light_buffer.refresh(0);
+    }
+    self.light_buffer.bind_base(LIGHTS_BINDING);
+    self.common_uniforms.insert(
+      "Lights".to_string(),
+      Uniform::UniformBuffer(UniformBuffer::new(LIGHTS_BINDING)),
+    );
+    self.shadow_views = lights.shadow_views().to_vec();
+  }
+
   pub fn submit_config(&mut self, config: RendererConfig) {
     self.config = config;
   }
 
+  pub fn submit_post_process(&mut self, post_process: PostProcessConfig) {
+    self.config.post_process = post_process;
+  }
+
+  pub fn config(&self) -> &RendererConfig {
+    &self.config
+  }
+
   // Methods that do something instead of just get/set things
 
   pub fn start_scene<'a>(&mut self, camera: &Camera) {
@@ -106,7 +172,7 @@ impl Renderer {
     }
     self.screen.unbind_framebuffer();
     window.clear_intrinsic_canvas();
-    self.screen.draw_framebuffer_contents();
+    self.post_processor.apply(&self.config.post_process, &self.screen);
     window.swap_buffers();
   }
 
@@ -114,33 +180,80 @@ impl Renderer {
     &mut self,
     render_queue: RwLockReadGuard<'b, AVLTree<DrawCall>>,
     materials: &ReadStorage<'a, MaterialComponent>,
-    transforms: &ReadStorage<'a, TransformComponent>,
+    (casters, receivers): (&ReadStorage<'a, ShadowCaster>, &ReadStorage<'a, ShadowReceiver>),
+    (transforms, interpolated_transforms, retarded_transforms): (
+      &ReadStorage<'a, TransformComponent>,
+      &ReadStorage<'a, InterpolatedTransform>,
+      &ReadStorage<'a, RetardedTransform>,
+    ),
     assets: &mut Write<'a, AssetLibrary>,
     debug_metrics: &DebugMetrics,
   ) {
     debug_metrics.draw_calls.reset();
     debug_metrics.poly_count.reset();
+    // Light travel time is only modelled from RELATIVISTIC mode onwards.
+    let retarded = if self.config.relativity_mode() >= 2 {
+      Some(retarded_transforms)
+    } else {
+      None
+    };
+
+    let models = (transforms, interpolated_transforms);
 
-    let mut queue = render_queue.iter();
-    let pipeline_opt = RenderPipeline::<'_, ReadyToDrawStep>::new(&mut queue, assets);
-    if let Some(pipeline) = pipeline_opt {
-      let mut active_pipeline = pipeline.bind_global_uniforms(&[&self.config_uniforms, &self.common_uniforms]);
-      let poly_count = loop {
-        let saturated = active_pipeline.intake_queue(&mut queue, materials, transforms);
-        let flushed = saturated.flush();
+    // In deferred mode, whatever uses a default lit shader goes through the G-buffer and the rest
+    // is drawn forward on top.
+    let deferred = self.deferred_passes(&render_queue, assets);
+    let forward_queue = deferred.as_ref().map_or(&*render_queue, |passes| &passes.forward_queue);
+    let shadow_shader = assets.get_shader("shadow_depth");
+    let first_queue = match &deferred {
+      Some(passes) if !passes.g_buffer_queue.empty() => &passes.g_buffer_queue,
+      _ => forward_queue,
+    };
+    let mut pipeline = match RenderPipeline::<'_, ReadyToDrawStep>::new(&mut first_queue.iter(), assets) {
+      Some(pipeline) => pipeline,
+      None => return,
+    };
+    let first = first_queue.iter().peek().unwrap();
+
+    if let Some(shadow_shader) = shadow_shader.filter(|_| !self.shadow_views.is_empty()) {
+      self.prepare_shadow_maps();
+      let targets: Vec<(&Framebuffer, Mat4F)> =
+        self.shadow_maps.iter().zip(self.shadow_views.iter().copied()).collect();
+      pipeline = pipeline
+        .shadow_pass(shadow_shader)
+        .cast_shadows(&render_queue, casters, models, retarded, &targets)
+        .finish(first);
+      self.screen.bind_framebuffer();
+      self.bind_shadow_maps();
+    }
+
+    let drawn = match &deferred {
+      Some(passes) if !passes.g_buffer_queue.empty() => {
+        self.prepare_g_buffer();
+        let g_buffer = self.g_buffer.as_ref().unwrap();
+        g_buffer.clear();
+        let g_buffer_queue = &passes.g_buffer_queue;
+        let drawn = self.draw_queue(pipeline, g_buffer_queue, (materials, receivers), models, retarded, debug_metrics);
+        self.screen.bind_framebuffer();
+        let uniforms = [&self.config_uniforms, &self.common_uniforms];
+        let lit = drawn.light_g_buffer(g_buffer, passes.lighting_shader.clone(), passes.quad.clone(), &uniforms);
         debug_metrics.draw_calls.increment();
-        if queue.empty() {
-          self.common_uniforms.clear();
-          break flushed.state.poly_count as u32;
-        } else {
-          let proceeded = flushed.proceed(&mut queue);
-          active_pipeline = match proceeded {
-            Either::Left(ready_q) => ready_q.bind_global_uniforms(&[&self.config_uniforms, &self.common_uniforms]),
-            Either::Right(next_q) => next_q,
-          };
+        // Forward drawn meshes are depth tested against the G-buffer's.
+        g_buffer.blit_depth_to(self.screen.framebuffer());
+        match forward_queue.iter().peek() {
+          Some(first) => {
+            let pipeline = lit.next_queue(first);
+            self.draw_queue(pipeline, forward_queue, (materials, receivers), models, retarded, debug_metrics)
+          }
+          None => lit,
         }
-      };
-      debug_metrics.poly_count.increment_by(poly_count);
+      }
+      _ => self.draw_queue(pipeline, forward_queue, (materials, receivers), models, retarded, debug_metrics),
+    };
+    debug_metrics.poly_count.increment_by(drawn.state.poly_count as u32);
+    self.common_uniforms.clear();
+    for (i, shadow_map) in self.shadow_maps.iter().enumerate() {
+      shadow_map.unbind_texture_slot(SHADOW_MAP_SLOT + i as u32);
     }
     // unsafe {
     //     gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
@@ -149,6 +262,113 @@ impl Renderer {
 
   // Private helper functions
 
+  // Draws every call in `queue`, starting from a pipeline bound to its first one.
+  fn draw_queue<'p>(
+    &self,
+    pipeline: RenderPipeline<'p, ReadyToDrawStep>,
+    queue: &AVLTree<DrawCall>,
+    (materials, receivers): (&ReadStorage<'p, MaterialComponent>, &ReadStorage<'p, ShadowReceiver>),
+    models: (
+      &ReadStorage<'p, TransformComponent>,
+      &ReadStorage<'p, InterpolatedTransform>,
+    ),
+    retarded: Option<&ReadStorage<'p, RetardedTransform>>,
+    debug_metrics: &DebugMetrics,
+  ) -> RenderPipeline<'p, MeshesDrawnStep> {
+    let mut queue = queue.iter();
+    let mut active_pipeline = pipeline.bind_global_uniforms(&[&self.config_uniforms, &self.common_uniforms]);
+    loop {
+      let saturated = active_pipeline.intake_queue(&mut queue, materials, receivers, models, retarded);
+      let flushed = saturated.flush();
+      debug_metrics.draw_calls.increment();
+      if queue.empty() {
+        break flushed.finish();
+      } else {
+        let proceeded = flushed.proceed(&mut queue);
+        active_pipeline = match proceeded {
+          Either::Left(ready_q) => ready_q.bind_global_uniforms(&[&self.config_uniforms, &self.common_uniforms]),
+          Either::Right(next_q) => next_q,
+        };
+      }
+    }
+  }
+
+  // Splits the queue for deferred shading, if it is on and its shaders and screen quad are loaded.
+  fn deferred_passes(&self, queue: &AVLTree<DrawCall>, assets: &AssetLibrary) -> Option<DeferredPasses> {
+    if self.config.shading != ShadingMode::DEFERRED {
+      return None;
+    }
+    // Each lit shader with the shader that writes the same surface into the G-buffer.
+    let g_buffer_shaders: Vec<(ShaderId, ShaderId)> = [
+      ("default_texture", "g_buffer"),
+      ("instanced", "g_buffer_instanced"),
+    ]
+    .iter()
+    .filter_map(|(lit, g_buffer)| Some((assets.get_shader(lit)?, assets.get_shader(g_buffer)?)))
+    .collect();
+    let mut passes = DeferredPasses {
+      g_buffer_queue: AVLTree::default(),
+      forward_queue: AVLTree::default(),
+      lighting_shader: assets.get_shader("deferred_lighting")?,
+      quad: <AssetLibrary as Assets<VertexArrayBuilder>>::get_asset_id(assets, "screen_quad")?,
+    };
+    for draw_call in queue.iter() {
+      let mut draw_call = draw_call.clone();
+      let g_buffer_shader = g_buffer_shaders
+        .iter()
+        .find(|(lit, _)| *lit == draw_call.mesh_component.shader_id)
+        .map(|(_, g_buffer)| g_buffer.clone());
+      match g_buffer_shader {
+        Some(g_buffer_shader) => {
+          draw_call.mesh_component.shader_id = g_buffer_shader;
+          passes.g_buffer_queue.push(draw_call);
+        }
+        None => passes.forward_queue.push(draw_call),
+      }
+    }
+    Some(passes)
+  }
+
+  // The G-buffer matches the screen, so that its depth can be copied over.
+  fn prepare_g_buffer(&mut self) {
+    let dims = self.screen.framebuffer().spec.dims;
+    if self.g_buffer.as_ref().is_none_or(|g_buffer| g_buffer.spec.dims != dims) {
+      self.g_buffer = Some(Framebuffer::g_buffer(dims.x, dims.y));
+    }
+  }
+
+  // One shadow map per shadowed light, at the configured resolution.
+  fn prepare_shadow_maps(&mut self) {
+    let resolution = self.config.shadows.resolution;
+    if self.shadow_maps.first().is_some_and(|map| map.spec.dims.x != resolution) {
+      self.shadow_maps.clear();
+    }
+    while self.shadow_maps.len() < self.shadow_views.len() {
+      self.shadow_maps.push(Framebuffer::depth_only(resolution, resolution));
+    }
+  }
+
+  // Exposes the shadow maps rendered this frame to the scene's shaders.
+  fn bind_shadow_maps(&mut self) {
+    for (i, (shadow_map, light_space)) in self.shadow_maps.iter().zip(self.shadow_views.iter()).enumerate() {
+      let slot = SHADOW_MAP_SLOT + i as u32;
+      shadow_map.bind_depth_texture_slot(slot);
+      self
+        .common_uniforms
+        .insert(format!("shadow_maps[{}]", i), Uniform::Int(slot as i32));
+      self
+        .common_uniforms
+        .insert(format!("shadow_matrices[{}]", i), Uniform::Mat4(*light_space));
+    }
+    let shadows = &self.config.shadows;
+    self
+      .common_uniforms
+      .insert("shadow_bias".to_string(), Uniform::Float(shadows.bias));
+    self
+      .common_uniforms
+      .insert("shadow_pcf_radius".to_string(), Uniform::Int(shadows.pcf_radius));
+  }
+
   fn extract_camera_uniforms(&mut self, camera: &Camera) {
     self
       .common_uniforms
@@ -160,22 +380,12 @@ impl Renderer {
     self
       .common_uniforms
       .insert("light_ambient".to_string(), Uniform::Vec3(Vec3F::new(1.0, 1.0, 1.0)));
-    self
-      .common_uniforms
-      .insert("light_diffuse".to_string(), Uniform::Vec3(Vec3F::new(1.0, 1.0, 1.0)));
-    self
-      .common_uniforms
-      .insert("light_specular".to_string(), Uniform::Vec3(Vec3F::new(1.0, 1.0, 1.0)));
     self
       .common_uniforms
       .insert("camera_position".to_string(), Uniform::Vec3(camera.position()));
     self
       .common_uniforms
       .insert("debug_line_length".to_string(), Uniform::Float(0.1));
-    self.common_uniforms.insert(
-      "light_position".to_string(),
-      Uniform::Vec3(Vec3F::new(200.0, 200.0, -200.0)),
-    );
   }
 
   pub fn process_events(&mut self, chanel: &mut StatelessEventChannel<WindowEvent>) {
@@ -186,7 +396,7 @@ impl Renderer {
           match payload {
             EventPayload::WindowSize(new_sz) => {
               let vec_sz = Vec2I::new(new_sz.x as i32, new_sz.y as i32);
-              self.screen.set_framebuffer(Framebuffer::from_dims(vec_sz.x, vec_sz.y));
+              self.screen.set_framebuffer(Framebuffer::hdr(vec_sz.x, vec_sz.y));
             }
             _ => {}
           }
@@ -202,12 +412,23 @@ impl Renderer {
         new_config.debug = !new_config.debug;
         self.submit_config(new_config);
       }
+      Event::KeyPressed(KeyCode::Two) => {
+        let mut new_config = self.config.clone();
+        new_config.simultaneity_overlay = !new_config.simultaneity_overlay;
+        self.submit_config(new_config);
+      }
       Event::KeyPressed(KeyCode::One) => {
         let mut config = self.config.clone();
         config.polygon_mode = config.polygon_mode.rotate();
         println!("Setting polygon mode {:?}", config.polygon_mode);
         self.submit_config(config);
       }
+      Event::KeyPressed(KeyCode::Three) => {
+        let mut config = self.config.clone();
+        config.shading = config.shading.rotate();
+        println!("Setting shading mode {:?}", config.shading);
+        self.submit_config(config);
+      }
       _ => {}
     });
   }
@@ -3,6 +3,8 @@ pub enum RelativityMode {
   CLASSICAL,
   LORENTZ,
   RELATIVISTIC,
+  DOPPLER,
+  SEARCHLIGHT,
 }
 
 impl RelativityMode {
@@ -11,6 +13,8 @@ impl RelativityMode {
       RelativityMode::CLASSICAL => 0,
       RelativityMode::LORENTZ => 1,
       RelativityMode::RELATIVISTIC => 2,
+      RelativityMode::DOPPLER => 3,
+      RelativityMode::SEARCHLIGHT => 4,
     }
   }
 
@@ -18,7 +22,9 @@ impl RelativityMode {
     match self {
       RelativityMode::CLASSICAL => RelativityMode::LORENTZ,
       RelativityMode::LORENTZ => RelativityMode::RELATIVISTIC,
-      RelativityMode::RELATIVISTIC => RelativityMode::CLASSICAL,
+      RelativityMode::RELATIVISTIC => RelativityMode::DOPPLER,
+      RelativityMode::DOPPLER => RelativityMode::SEARCHLIGHT,
+      RelativityMode::SEARCHLIGHT => RelativityMode::CLASSICAL,
     }
   }
 }
@@ -38,11 +44,168 @@ impl PolygonMode {
   }
 }
 
+// Forward shading lights every fragment as it is drawn. Deferred shading first draws what the
+// default lit shader would into a G-buffer, then lights each pixel on screen once.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ShadingMode {
+  FORWARD,
+  DEFERRED,
+}
+
+impl ShadingMode {
+  pub fn rotate(self) -> Self {
+    match self {
+      ShadingMode::FORWARD => ShadingMode::DEFERRED,
+      ShadingMode::DEFERRED => ShadingMode::FORWARD,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShadowConfig {
+  // Width and height of every shadow map, in texels.
+  pub resolution: i32,
+  // Depth offset against shadow acne. Grows on surfaces that face away from the light.
+  pub bias: f32,
+  // PCF averages the depth test over (2 * pcf_radius + 1)^2 texels.
+  pub pcf_radius: i32,
+}
+
+impl Default for ShadowConfig {
+  fn default() -> Self {
+    ShadowConfig {
+      resolution: 2048,
+      bias: 0.005,
+      pcf_radius: 1,
+    }
+  }
+}
+
+// Effects applied to the finished frame, see PostProcessor.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PostEffect {
+  BLOOM,
+  TONEMAP,
+  GAMMA,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ToneMapper {
+  REINHARD,
+  ACES,
+}
+
+impl ToneMapper {
+  pub fn id(&self) -> i32 {
+    match self {
+      ToneMapper::REINHARD => 0,
+      ToneMapper::ACES => 1,
+    }
+  }
+}
+
+// Whatever is brighter than `threshold` bleeds into its surroundings.
+#[derive(Debug, Clone)]
+pub struct BloomConfig {
+  pub enabled: bool,
+  pub threshold: f32,
+  pub intensity: f32,
+  // Each pass blurs horizontally, then vertically.
+  pub blur_passes: i32,
+}
+
+impl Default for BloomConfig {
+  fn default() -> Self {
+    BloomConfig {
+      enabled: true,
+      threshold: 1.0,
+      intensity: 0.5,
+      blur_passes: 5,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct ToneMapConfig {
+  pub enabled: bool,
+  pub mapper: ToneMapper,
+  pub exposure: f32,
+}
+
+impl Default for ToneMapConfig {
+  fn default() -> Self {
+    ToneMapConfig {
+      enabled: true,
+      mapper: ToneMapper::ACES,
+      exposure: 1.0,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct GammaConfig {
+  pub enabled: bool,
+  pub gamma: f32,
+}
+
+impl Default for GammaConfig {
+  fn default() -> Self {
+    GammaConfig {
+      enabled: true,
+      gamma: 1.8,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct PostProcessConfig {
+  // The order effects run in. Effects left out never run.
+  pub order: Vec<PostEffect>,
+  pub bloom: BloomConfig,
+  pub tone_map: ToneMapConfig,
+  pub gamma: GammaConfig,
+}
+
+impl Default for PostProcessConfig {
+  fn default() -> Self {
+    PostProcessConfig {
+      order: vec![PostEffect::BLOOM, PostEffect::TONEMAP, PostEffect::GAMMA],
+      bloom: BloomConfig::default(),
+      tone_map: ToneMapConfig::default(),
+      gamma: GammaConfig::default(),
+    }
+  }
+}
+
+impl PostProcessConfig {
+  pub fn is_enabled(&self, effect: PostEffect) -> bool {
+    match effect {
+      PostEffect::BLOOM => self.bloom.enabled && self.bloom.blur_passes > 0,
+      PostEffect::TONEMAP => self.tone_map.enabled,
+      PostEffect::GAMMA => self.gamma.enabled,
+    }
+  }
+
+  // The enabled effects, in order.
+  pub fn chain(&self) -> Vec<PostEffect> {
+    self
+      .order
+      .iter()
+      .copied()
+      .filter(|effect| self.is_enabled(*effect))
+      .collect()
+  }
+}
+
 #[derive(Debug, Clone)]
 pub struct RendererConfig {
   pub mode: RelativityMode,
   pub debug: bool,
+  pub simultaneity_overlay: bool,
   pub polygon_mode: PolygonMode,
+  pub shading: ShadingMode,
+  pub shadows: ShadowConfig,
+  pub post_process: PostProcessConfig,
 }
 
 impl Default for RendererConfig {
@@ -50,7 +213,11 @@ impl Default for RendererConfig {
     RendererConfig {
       mode: RelativityMode::CLASSICAL,
       debug: false,
+      simultaneity_overlay: false,
       polygon_mode: PolygonMode::FILL,
+      shading: ShadingMode::FORWARD,
+      shadows: ShadowConfig::default(),
+      post_process: PostProcessConfig::default(),
     }
   }
 }
@@ -60,7 +227,11 @@ impl RendererConfig {
     RendererConfig {
       mode: mode,
       debug: false,
+      simultaneity_overlay: false,
       polygon_mode: PolygonMode::FILL,
+      shading: ShadingMode::FORWARD,
+      shadows: ShadowConfig::default(),
+      post_process: PostProcessConfig::default(),
     }
   }
 
@@ -68,3 +239,26 @@ impl RendererConfig {
     self.mode.id()
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn chain_keeps_the_order_of_enabled_effects() {
+    let mut config = PostProcessConfig {
+      order: vec![PostEffect::GAMMA, PostEffect::BLOOM, PostEffect::TONEMAP],
+      ..Default::default()
+    };
+    assert_eq!(
+      config.chain(),
+      vec![PostEffect::GAMMA, PostEffect::BLOOM, PostEffect::TONEMAP]
+    );
+    config.bloom.enabled = false;
+    assert_eq!(config.chain(), vec![PostEffect::GAMMA, PostEffect::TONEMAP]);
+    config.bloom.enabled = true;
+    config.bloom.blur_passes = 0;
+    config.gamma.enabled = false;
+    assert_eq!(config.chain(), vec![PostEffect::TONEMAP]);
+  }
+}
@@ -14,6 +14,10 @@ impl<'a, 'b> TestingEcs<'a, 'b> {
   pub fn entities(&self) -> Vec<Entity> {
     self.world.entities().join().collect()
   }
+
+  pub fn world(&self) -> &World {
+    &self.world
+  }
 }
 
 pub struct TestingEcsBuilder<'a, 'b> {
@@ -0,0 +1,29 @@
+use cgmath::prelude::*;
+
+use crate::utils::Vec3F;
+
+// Default absolute tolerance for float comparisons in tests. Anything looser should say why
+// where it is used.
+pub const TOLERANCE: f32 = 1e-4f32;
+
+pub fn assert_close(a: f32, b: f32) {
+  assert_close_within(a, b, TOLERANCE);
+}
+
+pub fn assert_close_within(a: f32, b: f32, tolerance: f32) {
+  assert!((a - b).abs() < tolerance, "{} != {} (within {})", a, b, tolerance);
+}
+
+pub fn assert_vec_close(a: Vec3F, b: Vec3F) {
+  assert_vec_close_within(a, b, TOLERANCE);
+}
+
+pub fn assert_vec_close_within(a: Vec3F, b: Vec3F, tolerance: f32) {
+  assert!(
+    (a - b).magnitude() < tolerance,
+    "{:?} != {:?} (within {})",
+    a,
+    b,
+    tolerance
+  );
+}
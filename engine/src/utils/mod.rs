@@ -1,3 +1,5 @@
+#[cfg(test)]
+mod assert;
 mod asset_ref;
 mod counter;
 pub mod math;
@@ -9,6 +11,8 @@ mod timestep;
 pub mod transform_stack;
 pub mod types;
 
+#[cfg(test)]
+pub use self::assert::*;
 pub use self::asset_ref::*;
 pub use self::counter::*;
 pub use self::math::*;
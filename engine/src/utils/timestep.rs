@@ -29,3 +29,91 @@ impl Timestep {
     self.dt().as_secs_f32()
   }
 }
+
+// Accumulates frame time and hands it out in fixed-size physics ticks, so physics runs at the
+// same rate regardless of framerate. `alpha` is how far the current frame sits between the last
+// two ticks, used to interpolate what gets drawn.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+  hz: f32,
+  accumulator: f32,
+  alpha: f32,
+  // Ticks allowed per frame before the rest of the frame time is dropped, so a slow frame
+  // cannot snowball into ever more physics work.
+  pub max_steps: usize,
+}
+
+impl Default for FixedTimestep {
+  fn default() -> Self {
+    Self::new(60f32)
+  }
+}
+
+impl FixedTimestep {
+  pub fn new(hz: f32) -> Self {
+    Self {
+      hz,
+      accumulator: 0f32,
+      alpha: 0f32,
+      max_steps: 8,
+    }
+  }
+
+  pub fn hz(&self) -> f32 {
+    self.hz
+  }
+
+  pub fn set_hz(&mut self, hz: f32) {
+    self.hz = hz;
+  }
+
+  pub fn dt(&self) -> Duration {
+    Duration::from_secs_f32(self.dt_f32())
+  }
+
+  pub fn dt_f32(&self) -> f32 {
+    1f32 / self.hz
+  }
+
+  pub fn alpha(&self) -> f32 {
+    self.alpha
+  }
+
+  // Adds a frame's worth of time and returns how many physics ticks to run for it.
+  pub fn accumulate(&mut self, frame_dt: f32) -> usize {
+    let dt = self.dt_f32();
+    self.accumulator += frame_dt;
+    let mut steps = (self.accumulator / dt) as usize;
+    if steps > self.max_steps {
+      steps = self.max_steps;
+      self.accumulator = dt * steps as f32;
+    }
+    self.accumulator -= dt * steps as f32;
+    self.alpha = (self.accumulator / dt).clamp(0f32, 1f32);
+    steps
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn accumulates_partial_ticks() {
+    let mut fixed = FixedTimestep::new(100f32);
+    assert_eq!(fixed.accumulate(0.025f32), 2);
+    assert!((fixed.alpha() - 0.5f32).abs() < 1e-3f32);
+    assert_eq!(fixed.accumulate(0.006f32), 1);
+    assert!((fixed.alpha() - 0.1f32).abs() < 1e-3f32);
+    assert_eq!(fixed.accumulate(0.004f32), 0);
+    assert!((fixed.alpha() - 0.5f32).abs() < 1e-3f32);
+  }
+
+  #[test]
+  fn drops_time_beyond_max_steps() {
+    let mut fixed = FixedTimestep::new(100f32);
+    assert_eq!(fixed.accumulate(1f32), fixed.max_steps);
+    assert_eq!(fixed.alpha(), 0f32);
+    assert_eq!(fixed.accumulate(0f32), 0);
+  }
+}
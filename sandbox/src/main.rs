@@ -1,4 +1,3 @@
-#[macro_use]
 extern crate engine;
 extern crate cgmath;
 extern crate specs;
@@ -8,13 +7,15 @@ mod components;
 mod prefabs;
 mod systems;
 
-use engine::ecs::{MotionSystem, Sys};
+use engine::ecs::{CollisionResponseSystem, CollisionSystem, JointSystem, MotionSystem, NBodySystem, RelativisticController, Sys};
+use engine::graphics::DirectionalLight;
 use engine::info;
 use engine::prefab::{ModelBuilder, ModelLoader, SkyboxBuilder, SkyboxPrefab,};
 use engine::utils::{Vec3F};
+use specs::Builder;
 
 use crate::prefabs::{Cube, CubeState};
-use crate::systems::{Multiplayer, PlayerController, SinSphere};
+use crate::systems::{Multiplayer, SinSphere};
 
 fn main() {
   env_logger::init();
@@ -22,10 +23,18 @@ fn main() {
   let builder = engine::get_game_builder();
   info!("Got the builder");
   let builder = builder
-    .with_system(Sys::<PlayerController>::default(), "player_controller", &[])
-    .with_system(MotionSystem, "motion_controller", &["player_controller"])
+    .with_system(Sys::<RelativisticController>::default(), "player_controller", &[])
+    .with_physics_system(CollisionSystem::default(), "collision_detection", &[])
+    .with_physics_system(MotionSystem, "motion_controller", &["collision_detection"])
+    .with_physics_system(CollisionResponseSystem, "collision_response", &["motion_controller"])
+    .with_physics_system(JointSystem::default(), "joints", &["collision_response"])
+    .with_physics_system(NBodySystem::default(), "n_body", &[])
     .with_system(Sys::<SinSphere>::default(), "sin_sphere", &[])
-    .with_system(Sys::<Multiplayer>::default(), "multiplayer", &["motion_controller"])
+    .with_system(Sys::<Multiplayer>::default(), "multiplayer", &["player_controller"])
+    .with_entity(|e| {
+      let sun = DirectionalLight::new(Vec3F::new(-1f32, -1f32, 1f32), Vec3F::new(1f32, 1f32, 1f32), 1f32);
+      e.with(sun.with_shadows(50f32)).build()
+    })
     .with_prefab(&mut SkyboxBuilder::default(), SkyboxPrefab::new("resources/skybox"))
     .with_prefab(
       &mut Cube::default(),
@@ -34,7 +43,8 @@ fn main() {
         Vec3F::new(8f32, 9f32, 10f32),
         "resources/debug/brickwall.jpg",
         "resources/debug/bricks_tangent.png",
-      ),
+      )
+      .with_collider(),
     )
     .with_prefab(
       &mut ModelBuilder::default(),
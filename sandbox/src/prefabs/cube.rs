@@ -1,19 +1,19 @@
-use cgmath::prelude::*;
 use specs::prelude::*;
 
 use engine::ecs::{PrefabBuilder, SystemUtilities};
 use engine::graphics::{
   Assets, ColorSpace, HydratedBuilderStep, MaterialComponent, MeshBufferBuilder, MeshBuilder, MeshComponent,
-  ShadingStrategy, TextureBuilder, VertexArrayBuilder,
+  ShadingStrategy, ShadowCaster, ShadowReceiver, TextureBuilder, VertexArrayBuilder,
 };
-use engine::physics::{RigidBody, TransformComponent};
-use engine::utils::{QuatF, Vec3F};
+use engine::physics::{AxisAlignedCubeCollision, RigidBody, TransformComponent};
+use engine::utils::Vec3F;
 
 pub struct CubeState {
   scale: f32,
   origin: Vec3F,
   texture_file: String,
   normal_file: String,
+  collider: bool,
 }
 
 impl CubeState {
@@ -23,8 +23,16 @@ impl CubeState {
       origin,
       texture_file: texture_file.to_string(),
       normal_file: normal_file.to_string(),
+      collider: false,
     }
   }
+
+  // Gives the cube a static AxisAlignedCubeCollision so CanCollide bodies cannot pass through.
+  // An axis-aligned collider cannot follow a rotation, so cubes with one don't spin.
+  pub fn with_collider(mut self) -> Self {
+    self.collider = true;
+    self
+  }
 }
 
 #[derive(Default)]
@@ -37,7 +45,8 @@ impl PrefabBuilder for Cube {
     let mesh_builder = self.build_cube_mesh();
     let mesh_builder: VertexArrayBuilder = mesh_builder.into();
     let vai = api.get_else("cube", mesh_builder);
-    let mesh = MeshComponent::new(vai, api.get_shader("default_texture").unwrap());
+    // Every cube shares the mesh and textures, so they go out as one instanced draw.
+    let mesh = MeshComponent::new(vai, api.get_shader("instanced").unwrap());
     let mut material = MaterialComponent::default();
     material.diffuse_texture(
       api.get_else(
@@ -58,12 +67,30 @@ impl PrefabBuilder for Cube {
     let mut transform = TransformComponent::identity();
     transform.push_scale(Vec3F::new(state.scale, state.scale, state.scale));
     transform.push_translation(state.origin);
+    let collider = if state.collider {
+      Some(AxisAlignedCubeCollision::from_transform(&transform))
+    } else {
+      None
+    };
     let mut rigid_body = RigidBody::new_stationary();
-    let rotation = QuatF::from_angle_y(cgmath::Deg(0.5f32)) * QuatF::from_angle_x(cgmath::Deg(0.5f32));
-    rigid_body.angular_velocity = rotation;
+    if collider.is_none() {
+      rigid_body.angular_velocity = (Vec3F::unit_x() + Vec3F::unit_y()) * cgmath::Rad::from(cgmath::Deg(30f32)).0;
+    }
     api
       .entity_builder()
-      .and(|ett| ett.with(material).with(transform).with(mesh).with(rigid_body))
+      .and(|ett| {
+        let ett = ett
+          .with(material)
+          .with(transform)
+          .with(mesh)
+          .with(rigid_body)
+          .with(ShadowCaster)
+          .with(ShadowReceiver);
+        match collider {
+          Some(collider) => ett.with(collider),
+          None => ett,
+        }
+      })
       .consume()
   }
 }
@@ -1,11 +1,9 @@
 // mod chunk_manager;
-mod player_controller;
 mod sin_sphere;
 mod multiplayer;
 // mod planet;
 
 // pub use self::planet::*;
 // pub use self::chunk_manager::*;
-pub use self::player_controller::*;
 pub use self::sin_sphere::*;
 pub use self::multiplayer::*;
@@ -8,6 +8,7 @@ use engine::{
   gui::{widgets::*, ControlPanelBuilder, SystemDebugger},
   net::{ConnectionParameters, DuplexContext, HostContext, NetActor, NetActorHandle},
   physics::TransformComponent,
+  relativity::PositionHistory,
 };
 
 use crate::prefabs::{Cube, CubeState};
@@ -153,6 +154,7 @@ impl Multiplayer {
     );
     let avatar = builder.build(api, state);
     api.add_component(&avatar, message.id);
+    api.add_component(&avatar, PositionHistory::default());
     {
       let mut panel = self.get_write_panel(&api);
       panel.set_str("Connection Status", "Connected!".to_string());
@@ -0,0 +1,72 @@
+#shader vertex
+#version 330 core
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec2 aUv;
+
+out vec2 uv;
+
+void main()
+{
+    gl_Position = vec4(aPos, 0.0, 1.0);
+    uv = aUv;
+}
+
+#shader fragment
+#version 330 core
+
+// Deferred shading's second pass: lights every pixel of the G-buffer written by g_buffer.glsl,
+// the same way simple_textured.glsl lights its fragments.
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+// G-buffer
+uniform sampler2D g_position;
+uniform sampler2D g_normal;
+uniform sampler2D g_albedo;
+uniform sampler2D g_specular;
+
+// Environment Uniforms
+uniform vec3 camera_position;
+uniform vec3 light_ambient;
+uniform float ambient_strength;
+uniform float diffuse_strength;
+uniform float specular_power;
+
+#include "shaders/lorentz_helper.glsl"
+#include "shaders/simultaneity_helper.glsl"
+#include "shaders/lights.glsl"
+
+void main()
+{
+    vec4 position = texture(g_position, uv);
+    // Nothing was drawn here, leave it to the forward pass.
+    if (position.w == 0.0) {
+        discard;
+    }
+    vec3 frag_pos = position.xyz;
+    vec4 normal = texture(g_normal, uv);
+    vec3 albedo = texture(g_albedo, uv).rgb;
+    vec3 specular = texture(g_specular, uv).rgb;
+    vec3 view_direction = normalize(camera_position - frag_pos);
+
+    // ambient
+    vec3 ambient_lighting = ambient_strength * light_ambient;
+
+    // diffuse and specular, from every light
+    vec3 diffuse_lighting;
+    vec3 specular_lighting;
+    blinnPhongShadowed(frag_pos, normal.xyz, view_direction, specular_power, normal.w > 0.5,
+                       diffuse_lighting, specular_lighting);
+    diffuse_lighting *= diffuse_strength;
+
+    // The G-buffer has no room for a separate ambient color, so ambient light reflects off the albedo.
+    vec3 ambient_contrib =  albedo * ambient_lighting;
+    vec3 diffuse_contrib =  albedo * diffuse_lighting;
+    vec3 specular_contrib = specular * specular_lighting;
+
+    // Linear HDR, gamma is applied by the post-processing chain.
+    vec3 lit = relativisticColor(ambient_contrib + diffuse_contrib + specular_contrib, frag_pos);
+    FragColor = vec4(simultaneityOverlayColor(lit, frag_pos), 1.0);
+}
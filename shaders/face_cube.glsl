@@ -53,7 +53,7 @@ float tess_level(vec3 v1, vec3 v2) {
 
   // Then I compute the angle from the midpt to the two end segments to approx. The
   // curvature. The larger the angle, the larger the degree of tessellation.
-  if (lorentzFlag == 2 && beta > 0.01) {
+  if (lorentzFlag >= 2 && beta > 0.01) {
     mat4 screenspace = projection * view;
     vec3 midpt = (v1 + v2) / 2.0;
     vec2 relV1 =  (screenspace * vec4(timeTransform(lorentzTransform(v1)), 1.0)).xy;
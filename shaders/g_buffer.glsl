@@ -0,0 +1,69 @@
+#shader vertex
+#version 330 core
+
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec3 aTangent;
+layout (location = 3) in vec3 aBitangent;
+layout (location = 4) in vec2 aTexCoords;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec2 uv;
+out vec3 frag_pos;
+out mat3 tbn;
+
+
+void main()
+{
+    gl_Position = projection * view * model * vec4(aPos, 1.0);
+    uv = aTexCoords;
+    frag_pos = vec3(model * vec4(aPos, 1.0));
+
+    mat3 normalMatrix = transpose(inverse(mat3(model)));
+    vec3 T = normalize(normalMatrix * aTangent);
+    vec3 N = normalize(normalMatrix * aNormal);
+    T = normalize(T - dot(T, N) * N);
+    vec3 B = cross(N, T);
+
+    tbn = mat3(T, B, N);
+}
+
+#shader fragment
+#version 330 core
+
+// Deferred shading's first pass: the surface that simple_textured.glsl would light, stored for
+// deferred_lighting.glsl to light once per pixel. Everything is in world space.
+
+in vec2 uv;
+in vec3 frag_pos;
+in mat3 tbn;
+
+layout (location = 0) out vec4 g_position; // w = 1 wherever something was drawn
+layout (location = 1) out vec4 g_normal;   // w = 1 for shadow receivers
+layout (location = 2) out vec4 g_albedo;
+layout (location = 3) out vec4 g_specular;
+
+// Environment Uniforms
+uniform float specular_strength;
+uniform bool shadow_receiver;
+
+// Material Uniforms
+uniform sampler2D diffuse_texture;
+uniform sampler2D specular_texture;
+uniform sampler2D normal_texture;
+uniform vec3 diffuse;
+uniform vec3 specular;
+
+void main()
+{
+    vec3 normal = normalize(tbn * (texture(normal_texture, uv).rgb * 2.0 - 1.0));
+    float spec_mag = texture(specular_texture, uv).x * specular_strength;
+
+    g_position = vec4(frag_pos, 1.0);
+    g_normal = vec4(normal, shadow_receiver ? 1.0 : 0.0);
+    g_albedo = vec4(diffuse + texture(diffuse_texture, uv).xyz, 1.0);
+    g_specular = vec4(specular + vec3(spec_mag, spec_mag, spec_mag), 1.0);
+}
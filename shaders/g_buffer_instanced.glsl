@@ -0,0 +1,73 @@
+#shader vertex
+#version 330 core
+
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec3 aTangent;
+layout (location = 3) in vec3 aBitangent;
+layout (location = 4) in vec2 aTexCoords;
+// Per-instance attributes, laid out like the "instanced" shader's.
+layout (location = 5) in mat4 aModel;
+layout (location = 9) in vec3 aDiffuse;
+
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec2 uv;
+out vec3 frag_pos;
+out mat3 tbn;
+out vec3 instance_diffuse;
+
+
+void main()
+{
+    gl_Position = projection * view * aModel * vec4(aPos, 1.0);
+    uv = aTexCoords;
+    frag_pos = vec3(aModel * vec4(aPos, 1.0));
+    instance_diffuse = aDiffuse;
+
+    mat3 normalMatrix = transpose(inverse(mat3(aModel)));
+    vec3 T = normalize(normalMatrix * aTangent);
+    vec3 N = normalize(normalMatrix * aNormal);
+    T = normalize(T - dot(T, N) * N);
+    vec3 B = cross(N, T);
+
+    tbn = mat3(T, B, N);
+}
+
+#shader fragment
+#version 330 core
+
+// g_buffer.glsl for meshes drawn with simple_instanced.glsl, whose model matrix and diffuse
+// colour come per instance.
+
+in vec2 uv;
+in vec3 frag_pos;
+in mat3 tbn;
+in vec3 instance_diffuse;
+
+layout (location = 0) out vec4 g_position; // w = 1 wherever something was drawn
+layout (location = 1) out vec4 g_normal;   // w = 1 for shadow receivers
+layout (location = 2) out vec4 g_albedo;
+layout (location = 3) out vec4 g_specular;
+
+// Environment Uniforms
+uniform float specular_strength;
+uniform bool shadow_receiver;
+
+// Material Uniforms
+uniform sampler2D diffuse_texture;
+uniform sampler2D specular_texture;
+uniform sampler2D normal_texture;
+uniform vec3 specular;
+
+void main()
+{
+    vec3 normal = normalize(tbn * (texture(normal_texture, uv).rgb * 2.0 - 1.0));
+    float spec_mag = texture(specular_texture, uv).x * specular_strength;
+
+    g_position = vec4(frag_pos, 1.0);
+    g_normal = vec4(normal, shadow_receiver ? 1.0 : 0.0);
+    g_albedo = vec4(instance_diffuse + texture(diffuse_texture, uv).xyz, 1.0);
+    g_specular = vec4(specular + vec3(spec_mag, spec_mag, spec_mag), 1.0);
+}
@@ -0,0 +1,102 @@
+// Scene lights, filled by Renderer::submit_lights. See graphics/light.rs for the packing.
+
+// Keep in sync with MAX_LIGHTS in graphics/light.rs
+#define MAX_LIGHTS 16
+#define DIRECTIONAL_LIGHT 0
+#define POINT_LIGHT 1
+#define SPOT_LIGHT 2
+
+struct Light {
+  vec4 position;    // xyz, w = kind
+  vec4 direction;   // xyz, w = cos(inner cone angle)
+  vec4 color;       // rgb * intensity, w = cos(outer cone angle)
+  vec4 attenuation; // constant, linear, quadratic, w = shadow map slot + 1, 0 if unshadowed
+};
+
+layout (std140) uniform Lights {
+  ivec4 light_count;
+  Light lights[MAX_LIGHTS];
+};
+
+// Shadow maps, filled by Renderer::render_scene's shadow pass.
+// Keep in sync with MAX_SHADOW_MAPS in graphics/shadow.rs
+#define MAX_SHADOW_MAPS 4
+
+uniform sampler2D shadow_maps[MAX_SHADOW_MAPS];
+uniform mat4 shadow_matrices[MAX_SHADOW_MAPS];
+uniform float shadow_bias;
+uniform int shadow_pcf_radius;
+uniform bool shadow_receiver;
+
+// Sampler arrays can only be indexed with constants.
+float shadowDepth(int slot, vec2 uv)
+{
+  if (slot == 0) return texture(shadow_maps[0], uv).r;
+  if (slot == 1) return texture(shadow_maps[1], uv).r;
+  if (slot == 2) return texture(shadow_maps[2], uv).r;
+  return texture(shadow_maps[3], uv).r;
+}
+
+// Fraction of the light from the given shadow map's light that reaches pos, averaged over the
+// neighbouring texels (PCF) to soften the edges. cos_theta is between the normal and the light.
+float shadowFactor(int slot, vec3 pos, float cos_theta)
+{
+  vec4 light_space = shadow_matrices[slot] * vec4(pos, 1.0);
+  vec3 coords = light_space.xyz / light_space.w * 0.5 + 0.5;
+  // Past the far plane of the light.
+  if (coords.z > 1.0) {
+    return 1.0;
+  }
+  // Surfaces at a grazing angle to the light need more bias against acne.
+  float bias = max(shadow_bias * 10.0 * (1.0 - cos_theta), shadow_bias);
+  vec2 texel = 1.0 / vec2(textureSize(shadow_maps[0], 0));
+  float lit = 0.0;
+  for (int x = -shadow_pcf_radius; x <= shadow_pcf_radius; x++) {
+    for (int y = -shadow_pcf_radius; y <= shadow_pcf_radius; y++) {
+      float closest = shadowDepth(slot, coords.xy + vec2(x, y) * texel);
+      lit += coords.z - bias > closest ? 0.0 : 1.0;
+    }
+  }
+  float width = float(2 * shadow_pcf_radius + 1);
+  return lit / (width * width);
+}
+
+// Blinn-Phong diffuse and specular light arriving at pos from every light, shadowed if it
+// receives shadows. Everything is in world space.
+void blinnPhongShadowed(vec3 pos, vec3 normal, vec3 view_direction, float power, bool receives_shadows,
+                        out vec3 diffuse, out vec3 specular)
+{
+  diffuse = vec3(0.0);
+  specular = vec3(0.0);
+  for (int i = 0; i < light_count.x; i++) {
+    Light light = lights[i];
+    int kind = int(light.position.w);
+    vec3 light_direction;
+    float strength = 1.0;
+    if (kind == DIRECTIONAL_LIGHT) {
+      light_direction = normalize(-light.direction.xyz);
+    } else {
+      vec3 to_light = light.position.xyz - pos;
+      float d = length(to_light);
+      light_direction = to_light / d;
+      strength = 1.0 / (light.attenuation.x + light.attenuation.y * d + light.attenuation.z * d * d);
+      if (kind == SPOT_LIGHT) {
+        float theta = dot(light_direction, normalize(-light.direction.xyz));
+        strength *= clamp((theta - light.color.w) / (light.direction.w - light.color.w), 0.0, 1.0);
+      }
+    }
+    int shadow_slot = int(light.attenuation.w) - 1;
+    if (receives_shadows && shadow_slot >= 0) {
+      strength *= shadowFactor(shadow_slot, pos, max(dot(normal, light_direction), 0.0));
+    }
+    vec3 halfway_direction = normalize(light_direction + view_direction);
+    diffuse += max(dot(normal, light_direction), 0.0) * strength * light.color.rgb;
+    specular += pow(max(dot(normal, halfway_direction), 0.0), power) * strength * light.color.rgb;
+  }
+}
+
+// As above, shadowed when the object drawn is a shadow receiver.
+void blinnPhong(vec3 pos, vec3 normal, vec3 view_direction, float power, out vec3 diffuse, out vec3 specular)
+{
+  blinnPhongShadowed(pos, normal, view_direction, power, shadow_receiver, diffuse, specular);
+}
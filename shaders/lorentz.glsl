@@ -29,6 +29,8 @@ void main()
 #version 330 core
 out vec4 FragColor;
 
+#include "shaders/lorentz_helper.glsl"
+
 in vec2 finalUv;
 in vec3 finalNormal;
 in vec3 finalWorldPos;
@@ -46,4 +48,5 @@ void main()
   // }
 	// FragColor = texture(diffuse_texture, uv);
   if (FragColor.a < 0.5) discard;
+  FragColor = vec4(relativisticColor(FragColor.rgb, finalWorldPos), FragColor.a);
 }
\ No newline at end of file
@@ -6,9 +6,14 @@ uniform int lorentzFlag;
 // If 0, no relativity at all
 // If 1, enable lorentz transform
 // If 2, enable lorentz transform and light travel time considerations
+// If 3, additionally doppler shift colors
+// If 4, additionally apply searchlight (relativistic beaming) brightness
 
 
 uniform vec3 cameraPos;
+// Set when the model matrix is a RetardedTransform, which already places the entity where its
+// light says it is. The light travel time is then not applied again.
+uniform bool retardedModel;
 
 
 uniform mat3 changeOfBasis;
@@ -44,9 +49,72 @@ vec3 transformRelativistic(vec3 pos)
   vec3 ret = pos;
   if (lorentzFlag != 0) {
     ret = lorentzTransform(ret);
-    if (lorentzFlag == 2 && beta > 0.01) {
+    if (lorentzFlag >= 2 && beta > 0.01 && !retardedModel) {
         ret = timeTransform(ret);
     }
   }
   return ret;
+}
+
+// Keep in sync with relativity/doppler.rs
+const float RED_WAVELENGTH = 650.0;
+const float GREEN_WAVELENGTH = 510.0;
+const float BLUE_WAVELENGTH = 440.0;
+
+float dopplerFactor(vec3 pos)
+{
+  vec3 toSource = pos - cameraPos;
+  if (beta < 0.000001 || dot(toSource, toSource) < 0.000001) {
+    return 1.0;
+  }
+  // The first row of changeOfBasis is the direction of travel.
+  float cosTheta = (changeOfBasis * normalize(toSource)).x;
+  return gamma * (1.0 + beta * cosTheta);
+}
+
+vec3 wavelengthToRgb(float w)
+{
+  vec3 rgb = vec3(0.0);
+  if (w >= 380.0 && w < 440.0) {
+    rgb = vec3(-(w - 440.0) / 60.0, 0.0, 1.0);
+  } else if (w >= 440.0 && w < 490.0) {
+    rgb = vec3(0.0, (w - 440.0) / 50.0, 1.0);
+  } else if (w >= 490.0 && w < 510.0) {
+    rgb = vec3(0.0, 1.0, -(w - 510.0) / 20.0);
+  } else if (w >= 510.0 && w < 580.0) {
+    rgb = vec3((w - 510.0) / 70.0, 1.0, 0.0);
+  } else if (w >= 580.0 && w < 645.0) {
+    rgb = vec3(1.0, -(w - 645.0) / 65.0, 0.0);
+  } else if (w >= 645.0 && w <= 780.0) {
+    rgb = vec3(1.0, 0.0, 0.0);
+  }
+  float falloff = 1.0;
+  if (w < 380.0 || w > 780.0) {
+    falloff = 0.0;
+  } else if (w < 420.0) {
+    falloff = 0.3 + 0.7 * (w - 380.0) / 40.0;
+  } else if (w > 700.0) {
+    falloff = 0.3 + 0.7 * (780.0 - w) / 80.0;
+  }
+  return rgb * falloff;
+}
+
+vec3 dopplerShiftColor(vec3 color, float doppler)
+{
+  return wavelengthToRgb(RED_WAVELENGTH / doppler) * color.r
+    + wavelengthToRgb(GREEN_WAVELENGTH / doppler) * color.g
+    + wavelengthToRgb(BLUE_WAVELENGTH / doppler) * color.b;
+}
+
+vec3 relativisticColor(vec3 color, vec3 pos)
+{
+  vec3 ret = color;
+  if (lorentzFlag >= 3) {
+    float doppler = dopplerFactor(pos);
+    ret = dopplerShiftColor(ret, doppler);
+    if (lorentzFlag == 4) {
+      ret = ret * pow(doppler, 4.0);
+    }
+  }
+  return ret;
 }
\ No newline at end of file
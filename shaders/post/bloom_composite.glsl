@@ -0,0 +1,29 @@
+#shader vertex
+#version 330 core
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec2 aUv;
+
+out vec2 uv;
+
+void main()
+{
+    gl_Position = vec4(aPos, 0.0, 1.0);
+    uv = aUv;
+}
+
+#shader fragment
+#version 330 core
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex;
+uniform sampler2D bloom;
+uniform float intensity;
+
+void main()
+{
+    vec3 color = texture(tex, uv).rgb + intensity * texture(bloom, uv).rgb;
+    FragColor = vec4(color, 1.0);
+}
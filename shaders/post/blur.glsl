@@ -0,0 +1,38 @@
+#shader vertex
+#version 330 core
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec2 aUv;
+
+out vec2 uv;
+
+void main()
+{
+    gl_Position = vec4(aPos, 0.0, 1.0);
+    uv = aUv;
+}
+
+#shader fragment
+#version 330 core
+
+// One direction of a separable 9-tap gaussian blur.
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex;
+uniform bool horizontal;
+
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main()
+{
+    vec2 step = 1.0 / vec2(textureSize(tex, 0));
+    step = horizontal ? vec2(step.x, 0.0) : vec2(0.0, step.y);
+    vec3 color = texture(tex, uv).rgb * weights[0];
+    for (int i = 1; i < 5; i++) {
+        color += texture(tex, uv + step * i).rgb * weights[i];
+        color += texture(tex, uv - step * i).rgb * weights[i];
+    }
+    FragColor = vec4(color, 1.0);
+}
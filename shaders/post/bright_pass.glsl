@@ -0,0 +1,36 @@
+#shader vertex
+#version 330 core
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec2 aUv;
+
+out vec2 uv;
+
+void main()
+{
+    gl_Position = vec4(aPos, 0.0, 1.0);
+    uv = aUv;
+}
+
+#shader fragment
+#version 330 core
+
+// Keeps only what is brighter than `threshold`, fading in over a small knee so that bloom
+// doesn't pop on and off.
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex;
+uniform float threshold;
+
+void main()
+{
+    vec3 color = texture(tex, uv).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    float knee = 0.5 * threshold;
+    float soft = clamp(luminance - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 0.0001);
+    float contribution = max(soft, luminance - threshold) / max(luminance, 0.0001);
+    FragColor = vec4(color * contribution, 1.0);
+}
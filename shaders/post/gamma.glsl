@@ -0,0 +1,28 @@
+#shader vertex
+#version 330 core
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec2 aUv;
+
+out vec2 uv;
+
+void main()
+{
+    gl_Position = vec4(aPos, 0.0, 1.0);
+    uv = aUv;
+}
+
+#shader fragment
+#version 330 core
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex;
+uniform float gamma;
+
+void main()
+{
+    vec3 color = max(texture(tex, uv).rgb, vec3(0.0));
+    FragColor = vec4(pow(color, vec3(1.0 / gamma)), 1.0);
+}
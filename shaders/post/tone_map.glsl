@@ -0,0 +1,46 @@
+#shader vertex
+#version 330 core
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec2 aUv;
+
+out vec2 uv;
+
+void main()
+{
+    gl_Position = vec4(aPos, 0.0, 1.0);
+    uv = aUv;
+}
+
+#shader fragment
+#version 330 core
+
+// Maps HDR color into [0, 1]. Matches ToneMapper::id.
+
+in vec2 uv;
+
+out vec4 FragColor;
+
+uniform sampler2D tex;
+uniform float exposure;
+uniform int tone_mapper;
+
+vec3 reinhard(vec3 color) {
+    return color / (color + vec3(1.0));
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve.
+vec3 aces(vec3 color) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), 0.0, 1.0);
+}
+
+void main()
+{
+    vec3 color = texture(tex, uv).rgb * exposure;
+    color = tone_mapper == 1 ? aces(color) : reinhard(color);
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,20 @@
+#shader vertex
+#version 330 core
+
+// Only the depth of shadow casters as seen from a light, see ShadowPassStep.
+layout (location = 0) in vec3 aPos;
+
+uniform mat4 light_space;
+uniform mat4 model;
+
+void main()
+{
+    gl_Position = light_space * model * vec4(aPos, 1.0);
+}
+
+#shader fragment
+#version 330 core
+
+void main()
+{
+}
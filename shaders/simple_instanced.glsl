@@ -1,44 +1,91 @@
 #shader vertex
 #version 330 core
 
-
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec3 aNormal;
-layout (location = 2) in vec2 aTexCoords;
-layout (location = 3) in mat4 aModel;
-layout (location = 7) in uint aDiffuseIndex;
-// layout (location = 4) in vec3 ambient;
+layout (location = 2) in vec3 aTangent;
+layout (location = 3) in vec3 aBitangent;
+layout (location = 4) in vec2 aTexCoords;
+// Per-instance attributes, see the "instanced" shader's instance layout in GameBuilder. They
+// start at INSTANCE_ATTRIBUTE_START, and building the shader checks these locations against it.
+layout (location = 5) in mat4 aModel;
+layout (location = 9) in vec3 aDiffuse;
 
 uniform mat4 view;
 uniform mat4 projection;
 
-out VS_OUT {
-  vec2 TexCoords;
-  flat uint DiffuseIndex;
-} vs_out;
+out vec2 uv;
+out vec3 frag_pos;
+out mat3 tbn;
+out vec3 instance_diffuse;
+
 
 void main()
 {
-    gl_Position = gl_Position = projection * view * aModel * vec4(aPos, 1.0f);
-    vs_out.TexCoords = aTexCoords;
-    vs_out.DiffuseIndex = aDiffuseIndex;
+    gl_Position = projection * view * aModel * vec4(aPos, 1.0);
+    uv = aTexCoords;
+    frag_pos = vec3(aModel * vec4(aPos, 1.0));
+    instance_diffuse = aDiffuse;
 
+    mat3 normalMatrix = transpose(inverse(mat3(aModel)));
+    vec3 T = normalize(normalMatrix * aTangent);
+    vec3 N = normalize(normalMatrix * aNormal);
+    T = normalize(T - dot(T, N) * N);
+    vec3 B = cross(N, T);
+
+    tbn = mat3(T, B, N);
 }
-#shader fragment
-#version 430 core
 
-uniform sampler2D diffuse_texture[32];
+#shader fragment
+#version 330 core
 
-in VS_OUT {
-  vec2 TexCoords;
-  flat uint DiffuseIndex;
-} fs_in;
+in vec2 uv;
+in vec3 frag_pos;
+in mat3 tbn;
+in vec3 instance_diffuse;
 
 out vec4 FragColor;
 
+// Environment Uniforms
+uniform vec3 camera_position;
+uniform vec3 light_ambient;
+uniform float ambient_strength;
+uniform float diffuse_strength;
+uniform float specular_power;
+uniform float specular_strength;
+
+// Material Uniforms, shared by every instance in the draw
+uniform sampler2D diffuse_texture;
+uniform sampler2D specular_texture;
+uniform sampler2D normal_texture;
+uniform vec3 ambient;
+uniform vec3 specular;
+
+#include "shaders/lorentz_helper.glsl"
+#include "shaders/simultaneity_helper.glsl"
+#include "shaders/lights.glsl"
 
 void main()
 {
-	FragColor =  texture(diffuse_texture[fs_in.DiffuseIndex], fs_in.TexCoords);
-  if (FragColor.a < 0.5) discard;
-}
\ No newline at end of file
+
+    vec3 normal = normalize(tbn * (texture(normal_texture, uv).rgb * 2.0 - 1.0));
+    vec3 view_direction = normalize(camera_position - frag_pos);
+
+    // ambient
+    vec3 ambient_lighting = ambient_strength * light_ambient;
+
+    // diffuse and specular, from every light
+    vec3 diffuse_lighting;
+    vec3 specular_lighting;
+    blinnPhong(frag_pos, normal, view_direction, specular_power, diffuse_lighting, specular_lighting);
+    diffuse_lighting *= diffuse_strength;
+    float spec_mag = texture(specular_texture, uv).x * specular_strength;
+
+    vec3 ambient_contrib =  (ambient  + texture(diffuse_texture, uv).xyz) * ambient_lighting;
+    vec3 diffuse_contrib =  (instance_diffuse + texture(diffuse_texture, uv).xyz) * diffuse_lighting;
+    vec3 specular_contrib = (specular + vec3(spec_mag, spec_mag, spec_mag)) * specular_lighting;
+
+    // Linear HDR, gamma is applied by the post-processing chain.
+    vec3 lit = relativisticColor(ambient_contrib + diffuse_contrib + specular_contrib, frag_pos);
+    FragColor = vec4(simultaneityOverlayColor(lit, frag_pos), 1.0);
+}
@@ -10,15 +10,10 @@ layout (location = 4) in vec2 aTexCoords;
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
-uniform vec3 light_position;
-uniform vec3 camera_position;
 
 out vec2 uv;
 out vec3 frag_pos;
-out vec3 frag_normal;
-out vec3 tangent_light_position;
-out vec3 tangent_camera_position;
-out vec3 tangent_frag_position;
+out mat3 tbn;
 
 
 void main()
@@ -26,7 +21,6 @@ void main()
     gl_Position = projection * view * model * vec4(aPos, 1.0);
     uv = aTexCoords;
     frag_pos = vec3(model * vec4(aPos, 1.0));
-    frag_normal = aNormal;
 
     mat3 normalMatrix = transpose(inverse(mat3(model)));
     vec3 T = normalize(normalMatrix * aTangent);
@@ -34,37 +28,25 @@ void main()
     T = normalize(T - dot(T, N) * N);
     vec3 B = cross(N, T);
 
-    mat3 TBN = transpose(mat3(T, B, N));
-    
-    tangent_light_position = TBN * light_position;
-    tangent_camera_position  = TBN * camera_position;
-    tangent_frag_position  = TBN * frag_pos;
-
+    tbn = mat3(T, B, N);
 }
 
 #shader fragment
 #version 330 core
 
 in vec2 uv;
-in vec3 frag_position;
-in vec3 frag_normal;
-in vec3 tangent_light_position;
-in vec3 tangent_camera_position;
-in vec3 tangent_frag_position;
-in vec3 light_position;
-in vec3 camera_position;
+in vec3 frag_pos;
+in mat3 tbn;
 
 out vec4 FragColor;
 
 // Environment Uniforms
+uniform vec3 camera_position;
 uniform vec3 light_ambient;
-uniform vec3 light_diffuse;
-uniform vec3 light_specular;
 uniform float ambient_strength;
 uniform float diffuse_strength;
 uniform float specular_power;
 uniform float specular_strength;
-uniform float gamma;
 
 // Material Uniforms
 uniform sampler2D diffuse_texture;
@@ -74,34 +56,31 @@ uniform vec3 ambient;
 uniform vec3 diffuse;
 uniform vec3 specular;
 
-vec3 gamma_correct(vec3 rgb) {
-    return pow(rgb, vec3(1.0/gamma));
-}
+#include "shaders/lorentz_helper.glsl"
+#include "shaders/simultaneity_helper.glsl"
+#include "shaders/lights.glsl"
 
 void main()
 {
 
-    vec3 normal = normalize(texture(normal_texture, uv).rgb * 2.0 - 1.0);
+    vec3 normal = normalize(tbn * (texture(normal_texture, uv).rgb * 2.0 - 1.0));
+    vec3 view_direction = normalize(camera_position - frag_pos);
 
     // ambient
     vec3 ambient_lighting = ambient_strength * light_ambient;
 
-    // diffuse
-    vec3 light_direction = normalize(tangent_light_position - tangent_frag_position);
-    float diff = max(dot(light_direction, normal), 0.0);
-    vec3 diffuse_lighting = diff * light_diffuse * diffuse_strength;
-
-    // specular
-    vec3 view_direction = normalize(tangent_camera_position - tangent_frag_position);
-    vec3 halfway_direction = normalize(light_direction + view_direction);  
-    float spec = pow(max(dot(normal, halfway_direction), 0.0), specular_power);
-  	vec3 specular_lighting = spec * light_specular;
+    // diffuse and specular, from every light
+    vec3 diffuse_lighting;
+    vec3 specular_lighting;
+    blinnPhong(frag_pos, normal, view_direction, specular_power, diffuse_lighting, specular_lighting);
+    diffuse_lighting *= diffuse_strength;
     float spec_mag = texture(specular_texture, uv).x * specular_strength;
 
     vec3 ambient_contrib =  (ambient  + texture(diffuse_texture, uv).xyz) * ambient_lighting;
     vec3 diffuse_contrib =  (diffuse  + texture(diffuse_texture, uv).xyz) * diffuse_lighting;
     vec3 specular_contrib = (specular + vec3(spec_mag, spec_mag, spec_mag)) * specular_lighting;
 
-    // FragColor = vec4(normal, 1.0); // vec4(ambient_contrib + diffuse_contrib + specular_contrib, 1.0);
-    FragColor = vec4(gamma_correct(ambient_contrib) + gamma_correct(diffuse_contrib) + gamma_correct(specular_contrib), 1.0);
-}
\ No newline at end of file
+    // Linear HDR, gamma is applied by the post-processing chain.
+    vec3 lit = relativisticColor(ambient_contrib + diffuse_contrib + specular_contrib, frag_pos);
+    FragColor = vec4(simultaneityOverlayColor(lit, frag_pos), 1.0);
+}
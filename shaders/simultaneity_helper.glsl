@@ -0,0 +1,47 @@
+
+// Requires shaders/lorentz_helper.glsl to be included first.
+
+uniform int simultaneityOverlay;
+uniform float speedOfLight;
+
+const vec3 FUTURE_TINT = vec3(1.0, 0.35, 0.2);
+const vec3 PAST_TINT = vec3(0.2, 0.45, 1.0);
+const vec3 LIGHT_CONE_COLOR = vec3(1.0, 0.9, 0.3);
+// Offsets (in seconds) at which the tint saturates.
+const float TINT_RANGE = 4.0;
+
+
+// World-time offset of the event at pos that the player considers simultaneous with now.
+// Keep in sync with RelativisticObserver::simultaneity_offset
+float simultaneityOffset(vec3 pos)
+{
+  return beta * (changeOfBasis * (pos - cameraPos)).x / speedOfLight;
+}
+
+// 1.0 on integer values of x, fading to 0.0 over roughly a pixel.
+float gridLine(float x)
+{
+  float width = fwidth(x);
+  return 1.0 - smoothstep(0.0, 1.5 * width, abs(fract(x - 0.5) - 0.5));
+}
+
+vec3 simultaneityOverlayColor(vec3 color, vec3 pos)
+{
+  if (simultaneityOverlay == 0) {
+    return color;
+  }
+  float offset = simultaneityOffset(pos);
+  float lightTime = length(pos - cameraPos) / speedOfLight;
+
+  vec3 tint = offset >= 0.0 ? FUTURE_TINT : PAST_TINT;
+  tint = mix(vec3(1.0), tint, clamp(abs(offset) / TINT_RANGE, 0.0, 1.0));
+  vec3 ret = color * tint;
+
+  // Planes of equal simultaneity offset, one per second. Meaningless when at rest.
+  if (beta > 0.01) {
+    ret = mix(ret, tint, gridLine(offset));
+  }
+  // Intersections of the past light cone with the world, one per light-second.
+  ret = mix(ret, LIGHT_CONE_COLOR, 0.8 * gridLine(lightTime));
+  return ret;
+}
@@ -26,14 +26,9 @@ out vec4 FragColor;
 in vec3 uvw;
 
 uniform samplerCube skybox;
-uniform float gamma;
 
 
-vec3 gamma_correct(vec3 rgb) {
-    return pow(rgb, vec3(1.0/gamma));
-}
-
 void main()
 {
-  FragColor = vec4(gamma_correct(texture(skybox, uvw).xyz), 1.0);
+  FragColor = vec4(texture(skybox, uvw).xyz, 1.0);
 }
\ No newline at end of file